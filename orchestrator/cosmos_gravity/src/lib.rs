@@ -6,6 +6,7 @@
 #[macro_use]
 extern crate log;
 
+pub mod offline;
 pub mod proposals;
 pub mod query;
 pub mod send;
@@ -0,0 +1,453 @@
+//! Builds Cosmos transactions without a private key so that they can be signed on an offline
+//! machine, then signs and broadcasts them as separate steps
+
+use gravity_proto::{
+    cosmos_sdk_proto::cosmos::{
+        bank::v1beta1::MsgSend,
+        base::{abci::v1beta1::TxResponse, v1beta1::Coin as ProtoCoin},
+        gov::v1beta1::{MsgDeposit, MsgSubmitProposal, MsgVote, TextProposal},
+        tx::v1beta1::BroadcastMode,
+    },
+    gravity::{MsgCancelSendToEth, MsgSendToEth, MsgSetOrchestratorAddress},
+};
+use gravity_utils::{
+    clarity::{
+        utils::{bytes_to_hex_str, hex_str_to_bytes},
+        Uint256,
+    },
+    deep_space::{
+        error::CosmosGrpcError, Address, Coin, Contact, Fee, MessageArgs, Msg, PrivateKey,
+    },
+    error::GravityError,
+};
+use prost::Message;
+use prost_types::Any;
+use serde::{Deserialize, Serialize};
+
+use crate::send::{MEMO, TIMEOUT};
+
+pub const SEND_TO_ETH_TYPE_URL: &str = "/gravity.v1.MsgSendToEth";
+pub const CANCEL_SEND_TO_ETH_TYPE_URL: &str = "/gravity.v1.MsgCancelSendToEth";
+pub const SET_ORCHESTRATOR_ADDRESS_TYPE_URL: &str = "/gravity.v1.MsgSetOrchestratorAddress";
+pub const SUBMIT_PROPOSAL_TYPE_URL: &str = "/cosmos.gov.v1beta1.MsgSubmitProposal";
+pub const VOTE_TYPE_URL: &str = "/cosmos.gov.v1beta1.MsgVote";
+pub const DEPOSIT_TYPE_URL: &str = "/cosmos.gov.v1beta1.MsgDeposit";
+pub const BANK_SEND_TYPE_URL: &str = "/cosmos.bank.v1beta1.MsgSend";
+
+/// A message of an unsigned transaction, the value is the hex encoded protobuf of the message
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EncodedMsg {
+    pub type_url: String,
+    pub value: String,
+}
+
+impl EncodedMsg {
+    pub fn new<T: Message>(type_url: &str, msg: T) -> Self {
+        EncodedMsg {
+            type_url: type_url.to_string(),
+            value: bytes_to_hex_str(&msg.encode_to_vec()),
+        }
+    }
+
+    /// Decodes the message so that it can be signed, only Gravity, bank send and governance
+    /// messages are supported
+    pub fn to_msg(&self) -> Result<Msg, GravityError> {
+        let type_url = self.type_url.as_str();
+        Ok(match type_url {
+            SEND_TO_ETH_TYPE_URL => Msg::new(type_url, self.decode::<MsgSendToEth>()?),
+            CANCEL_SEND_TO_ETH_TYPE_URL => Msg::new(type_url, self.decode::<MsgCancelSendToEth>()?),
+            SET_ORCHESTRATOR_ADDRESS_TYPE_URL => {
+                Msg::new(type_url, self.decode::<MsgSetOrchestratorAddress>()?)
+            }
+            SUBMIT_PROPOSAL_TYPE_URL => Msg::new(type_url, self.decode::<MsgSubmitProposal>()?),
+            VOTE_TYPE_URL => Msg::new(type_url, self.decode::<MsgVote>()?),
+            DEPOSIT_TYPE_URL => Msg::new(type_url, self.decode::<MsgDeposit>()?),
+            BANK_SEND_TYPE_URL => Msg::new(type_url, self.decode::<MsgSend>()?),
+            _ => return Err(self.unsupported()),
+        })
+    }
+
+    /// Describes the fields of the message so that they can be checked before signing
+    pub fn describe(&self) -> Result<String, GravityError> {
+        Ok(match self.type_url.as_str() {
+            SEND_TO_ETH_TYPE_URL => {
+                let msg = self.decode::<MsgSendToEth>()?;
+                format!(
+                    "Send to Ethereum from {} to {} of {} with a bridge fee of {}",
+                    msg.sender,
+                    msg.eth_dest,
+                    print_coins(msg.amount.as_slice()),
+                    print_coins(msg.bridge_fee.as_slice())
+                )
+            }
+            CANCEL_SEND_TO_ETH_TYPE_URL => {
+                let msg = self.decode::<MsgCancelSendToEth>()?;
+                format!(
+                    "Cancel send to Ethereum {} from {}",
+                    msg.transaction_id, msg.sender
+                )
+            }
+            SET_ORCHESTRATOR_ADDRESS_TYPE_URL => {
+                let msg = self.decode::<MsgSetOrchestratorAddress>()?;
+                format!(
+                    "Set the delegate keys of validator {} to orchestrator {} and Ethereum address {}",
+                    msg.validator, msg.orchestrator, msg.eth_address
+                )
+            }
+            SUBMIT_PROPOSAL_TYPE_URL => {
+                let msg = self.decode::<MsgSubmitProposal>()?;
+                let (content_type, title) = match msg.content {
+                    // every proposal content starts with the title and description fields of
+                    // a text proposal
+                    Some(content) => (
+                        content.type_url,
+                        TextProposal::decode(content.value.as_slice())
+                            .map(|p| p.title)
+                            .unwrap_or_default(),
+                    ),
+                    None => (String::from("no content"), String::new()),
+                };
+                format!(
+                    "Submit proposal \"{}\" of type {} from {} with a deposit of {}",
+                    title,
+                    content_type,
+                    msg.proposer,
+                    print_coins(&msg.initial_deposit)
+                )
+            }
+            VOTE_TYPE_URL => {
+                let msg = self.decode::<MsgVote>()?;
+                let option = match msg.option {
+                    1 => "yes",
+                    2 => "abstain",
+                    3 => "no",
+                    4 => "no with veto",
+                    _ => "unspecified",
+                };
+                format!(
+                    "Vote {} on proposal {} from {}",
+                    option, msg.proposal_id, msg.voter
+                )
+            }
+            DEPOSIT_TYPE_URL => {
+                let msg = self.decode::<MsgDeposit>()?;
+                format!(
+                    "Deposit {} on proposal {} from {}",
+                    print_coins(&msg.amount),
+                    msg.proposal_id,
+                    msg.depositor
+                )
+            }
+            BANK_SEND_TYPE_URL => {
+                let msg = self.decode::<MsgSend>()?;
+                format!(
+                    "Send {} from {} to {}",
+                    print_coins(&msg.amount),
+                    msg.from_address,
+                    msg.to_address
+                )
+            }
+            _ => return Err(self.unsupported()),
+        })
+    }
+
+    fn decode<T: Message + Default>(&self) -> Result<T, GravityError> {
+        let bytes = match hex_str_to_bytes(&self.value) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return Err(GravityError::ValidationError(format!(
+                    "Invalid {} message value {:?}",
+                    self.type_url, e
+                )))
+            }
+        };
+        T::decode(bytes.as_slice()).map_err(|e| {
+            GravityError::ValidationError(format!("Invalid {} message {:?}", self.type_url, e))
+        })
+    }
+
+    fn unsupported(&self) -> GravityError {
+        GravityError::ValidationError(format!("Unsupported message type {}", self.type_url))
+    }
+}
+
+fn print_coins(coins: &[ProtoCoin]) -> String {
+    if coins.is_empty() {
+        return String::from("nothing");
+    }
+    coins
+        .iter()
+        .map(|c| format!("{}{}", c.amount, c.denom))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// A transaction with everything needed to sign it offline, the account number and sequence
+/// are those of the sender when the transaction was generated
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnsignedTx {
+    pub sender: String,
+    pub chain_id: String,
+    pub account_number: u64,
+    pub sequence: u64,
+    pub timeout_height: u64,
+    pub memo: String,
+    pub fee_amount: String,
+    pub fee_denom: String,
+    pub gas_limit: u64,
+    pub messages: Vec<EncodedMsg>,
+}
+
+/// A signed transaction ready to be broadcast
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedTx {
+    pub tx_bytes: String,
+}
+
+/// Builds a MsgSubmitProposal for a proposal `content` as `create_gov_proposal` does
+pub fn encode_gov_proposal(content: Any, deposit: Coin, proposer: Address) -> EncodedMsg {
+    EncodedMsg::new(
+        SUBMIT_PROPOSAL_TYPE_URL,
+        MsgSubmitProposal {
+            content: Some(content),
+            initial_deposit: vec![deposit.into()],
+            proposer: proposer.to_string(),
+        },
+    )
+}
+
+/// Looks up the account number and sequence of `sender` to build a transaction of `messages`
+/// that can be signed offline. The transaction is valid until `timeout_height`, zero means it
+/// never times out, since the default timeout of a few blocks is too short for signing offline
+pub async fn generate_unsigned_tx(
+    contact: &Contact,
+    sender: Address,
+    messages: Vec<EncodedMsg>,
+    fee: Coin,
+    gas_limit: u64,
+    timeout_height: u64,
+) -> Result<UnsignedTx, CosmosGrpcError> {
+    let mut args = contact
+        .get_message_args(
+            sender,
+            Fee {
+                amount: vec![fee],
+                gas_limit,
+                granter: None,
+                payer: None,
+            },
+        )
+        .await?;
+    args.timeout_height = timeout_height;
+    Ok(unsigned_tx_from_args(sender, args, messages))
+}
+
+/// Builds an unsigned transaction of `messages` with the fee, sequence and timeout of `args`
+fn unsigned_tx_from_args(
+    sender: Address,
+    args: MessageArgs,
+    messages: Vec<EncodedMsg>,
+) -> UnsignedTx {
+    let fee = args.fee.amount[0].clone();
+    UnsignedTx {
+        sender: sender.to_string(),
+        chain_id: args.chain_id,
+        account_number: args.account_number,
+        sequence: args.sequence,
+        timeout_height: args.timeout_height,
+        memo: MEMO.to_string(),
+        fee_amount: fee.amount.to_string(),
+        fee_denom: fee.denom,
+        gas_limit: args.fee.gas_limit,
+        messages,
+    }
+}
+
+/// Signs an unsigned transaction, this does not require a connection to the chain
+pub fn sign_tx(tx: &UnsignedTx, key: PrivateKey) -> Result<SignedTx, GravityError> {
+    let sender: Address = match tx.sender.parse() {
+        Ok(sender) => sender,
+        Err(e) => {
+            return Err(GravityError::ValidationError(format!(
+                "Invalid sender {} {:?}",
+                tx.sender, e
+            )))
+        }
+    };
+    // the prefix is everything before the bech32 separator
+    let prefix = tx.sender.rsplit_once('1').map(|(p, _)| p).unwrap_or("");
+    if key.to_address(prefix).ok() != Some(sender) {
+        return Err(GravityError::ValidationError(format!(
+            "This key is not the key of the sender {}",
+            tx.sender
+        )));
+    }
+    let mut msgs = Vec::new();
+    for msg in tx.messages.iter() {
+        msgs.push(msg.to_msg()?);
+    }
+    let fee_amount = Uint256::from_dec_or_hex_str_restricted(&tx.fee_amount)?;
+    let args = MessageArgs {
+        sequence: tx.sequence,
+        fee: Fee {
+            amount: vec![Coin {
+                amount: fee_amount,
+                denom: tx.fee_denom.clone(),
+            }],
+            gas_limit: tx.gas_limit,
+            granter: None,
+            payer: None,
+        },
+        timeout_height: tx.timeout_height,
+        chain_id: tx.chain_id.clone(),
+        account_number: tx.account_number,
+    };
+    match key.sign_std_msg(&msgs, args, &tx.memo) {
+        Ok(bytes) => Ok(SignedTx {
+            tx_bytes: bytes_to_hex_str(&bytes),
+        }),
+        Err(e) => Err(GravityError::ValidationError(format!(
+            "Failed to sign transaction {:?}",
+            e
+        ))),
+    }
+}
+
+/// Broadcasts a signed transaction and waits for it to be included in a block
+pub async fn broadcast_tx(contact: &Contact, tx: &SignedTx) -> Result<TxResponse, GravityError> {
+    let bytes = match hex_str_to_bytes(&tx.tx_bytes) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return Err(GravityError::ValidationError(format!(
+                "Invalid signed transaction {:?}",
+                e
+            )))
+        }
+    };
+    let res = contact.send_transaction(bytes, BroadcastMode::Sync).await?;
+    Ok(contact.wait_for_tx(res, TIMEOUT).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use gravity_proto::cosmos_sdk_proto::cosmos::tx::v1beta1::{AuthInfo, TxBody, TxRaw};
+
+    use super::*;
+
+    #[test]
+    fn test_generate_sign_decode() {
+        let key = PrivateKey::from_secret(&[1u8; 32]);
+        let sender = key.to_address("gravity").unwrap();
+        let msg = MsgSendToEth {
+            sender: sender.to_string(),
+            eth_dest: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            amount: Some(
+                Coin {
+                    amount: Uint256::from_u64(1000),
+                    denom: "anom".to_string(),
+                }
+                .into(),
+            ),
+            bridge_fee: Some(
+                Coin {
+                    amount: Uint256::from_u64(10),
+                    denom: "anom".to_string(),
+                }
+                .into(),
+            ),
+        };
+        let args = MessageArgs {
+            sequence: 3,
+            fee: Fee {
+                amount: vec![Coin {
+                    amount: Uint256::from_u64(100),
+                    denom: "anom".to_string(),
+                }],
+                gas_limit: 500_000,
+                granter: None,
+                payer: None,
+            },
+            timeout_height: 0,
+            chain_id: "gravity-test".to_string(),
+            account_number: 7,
+        };
+        let tx = unsigned_tx_from_args(
+            sender,
+            args,
+            vec![EncodedMsg::new(SEND_TO_ETH_TYPE_URL, msg.clone())],
+        );
+        // the unsigned tx is written to a file and signed elsewhere
+        let tx: UnsignedTx = serde_json::from_str(&serde_json::to_string(&tx).unwrap()).unwrap();
+        let signed = sign_tx(&tx, key).unwrap();
+
+        let raw = TxRaw::decode(hex_str_to_bytes(&signed.tx_bytes).unwrap().as_slice()).unwrap();
+        let body = TxBody::decode(raw.body_bytes.as_slice()).unwrap();
+        assert_eq!(body.timeout_height, 0);
+        assert_eq!(body.memo, MEMO);
+        assert_eq!(body.messages.len(), 1);
+        assert_eq!(body.messages[0].type_url, SEND_TO_ETH_TYPE_URL);
+        assert_eq!(
+            MsgSendToEth::decode(body.messages[0].value.as_slice()).unwrap(),
+            msg
+        );
+        let auth_info = AuthInfo::decode(raw.auth_info_bytes.as_slice()).unwrap();
+        let fee = auth_info.fee.unwrap();
+        assert_eq!(fee.gas_limit, 500_000);
+        assert_eq!(fee.amount[0].amount, "100");
+        assert_eq!(auth_info.signer_infos[0].sequence, 3);
+        assert_eq!(raw.signatures.len(), 1);
+
+        // a key that is not the sender's can not sign
+        assert!(sign_tx(&tx, PrivateKey::from_secret(&[2u8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let msg = EncodedMsg::new(
+            SEND_TO_ETH_TYPE_URL,
+            MsgSendToEth {
+                sender: "gravity1sender".to_string(),
+                eth_dest: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+                amount: Some(ProtoCoin {
+                    denom: "anom".to_string(),
+                    amount: "1000".to_string(),
+                }),
+                bridge_fee: Some(ProtoCoin {
+                    denom: "anom".to_string(),
+                    amount: "10".to_string(),
+                }),
+            },
+        );
+        assert_eq!(
+            msg.describe().unwrap(),
+            "Send to Ethereum from gravity1sender to 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266 of 1000anom with a bridge fee of 10anom"
+        );
+
+        let content = TextProposal {
+            title: "Upgrade".to_string(),
+            description: "An upgrade".to_string(),
+        };
+        let msg = EncodedMsg::new(
+            SUBMIT_PROPOSAL_TYPE_URL,
+            MsgSubmitProposal {
+                content: Some(Any {
+                    type_url: "/cosmos.gov.v1beta1.TextProposal".to_string(),
+                    value: content.encode_to_vec(),
+                }),
+                initial_deposit: vec![],
+                proposer: "gravity1proposer".to_string(),
+            },
+        );
+        assert_eq!(
+            msg.describe().unwrap(),
+            "Submit proposal \"Upgrade\" of type /cosmos.gov.v1beta1.TextProposal from gravity1proposer with a deposit of nothing"
+        );
+
+        let msg = EncodedMsg {
+            type_url: "/cosmos.staking.v1beta1.MsgDelegate".to_string(),
+            value: String::new(),
+        };
+        assert!(msg.describe().is_err());
+        assert!(msg.to_msg().is_err());
+    }
+}
@@ -8,14 +8,23 @@ use gravity_proto::{
         base::abci::v1beta1::TxResponse,
         params::v1beta1::{ParamChange, ParameterChangeProposal},
     },
-    gravity::{AirdropProposal as AirdropProposalMsg, IbcMetadataProposal, UnhaltBridgeProposal},
+    gravity::{
+        AirdropProposal as AirdropProposalMsg, IbcMetadataProposal, Params, UnhaltBridgeProposal,
+    },
 };
-use gravity_utils::deep_space::{
-    error::{AddressError, CosmosGrpcError},
-    utils::encode_any,
-    Address, Coin, Contact, PrivateKey,
+use gravity_utils::{
+    clarity::{Address as EthAddress, Uint256},
+    deep_space::{
+        error::{AddressError, CosmosGrpcError},
+        utils::encode_any,
+        Address, Coin, Contact, PrivateKey,
+    },
+    error::GravityError,
+    num_conversion::{format_token_amount, TokenAmount},
 };
+use prost_types::Any;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 pub const AIRDROP_PROPOSAL_TYPE_URL: &str = "/gravity.v1.AirdropProposal";
 pub const UNHALT_BRIDGE_PROPOSAL_TYPE_URL: &str = "/gravity.v1.UnhaltBridgeProposal";
@@ -70,6 +79,14 @@ pub async fn submit_airdrop_proposal(
     key: PrivateKey,
     wait_timeout: Option<Duration>,
 ) -> Result<TxResponse, CosmosGrpcError> {
+    let any = encode_airdrop_proposal(proposal);
+    contact
+        .create_gov_proposal(any, deposit, fee, key, wait_timeout)
+        .await
+}
+
+/// Encodes an airdrop proposal as a generic proposal
+pub fn encode_airdrop_proposal(proposal: AirdropProposalJson) -> Any {
     let mut byte_recipients = Vec::new();
     for r in proposal.recipients {
         byte_recipients.extend_from_slice(r.as_bytes())
@@ -84,11 +101,30 @@ pub async fn submit_airdrop_proposal(
     };
 
     // encode as a generic proposal
-    let any = encode_any(proposal_content, AIRDROP_PROPOSAL_TYPE_URL.to_string());
+    encode_any(proposal_content, AIRDROP_PROPOSAL_TYPE_URL.to_string())
+}
 
-    contact
-        .create_gov_proposal(any, deposit, fee, key, wait_timeout)
-        .await
+/// Unpacks the recipients of an airdrop proposal, which are stored as concatenated 20 byte
+/// addresses, pairing each with its amount
+pub fn decode_airdrop_recipients(
+    airdrop: &AirdropProposalMsg,
+    prefix: &str,
+) -> Result<Vec<(Address, u64)>, GravityError> {
+    if airdrop.recipients.len() % 20 != 0 || airdrop.recipients.len() / 20 != airdrop.amounts.len()
+    {
+        return Err(GravityError::ValidationError(format!(
+            "Malformed airdrop with {} bytes of recipients for {} amounts",
+            airdrop.recipients.len(),
+            airdrop.amounts.len()
+        )));
+    }
+    let mut recipients = Vec::new();
+    for (addr_bytes, amount) in airdrop.recipients.chunks(20).zip(airdrop.amounts.iter()) {
+        let mut buf = [0; 20];
+        buf.copy_from_slice(addr_bytes);
+        recipients.push((Address::from_bytes(buf, prefix.to_string())?, *amount));
+    }
+    Ok(recipients)
 }
 
 /// The proposal.json representation for pausing/unpausing the bridge easily
@@ -142,6 +178,12 @@ pub async fn submit_pause_bridge_proposal(
     key: PrivateKey,
     wait_timeout: Option<Duration>,
 ) -> Result<TxResponse, CosmosGrpcError> {
+    let proposal = pause_bridge_proposal(proposal);
+    submit_parameter_change_proposal(proposal, deposit, fee, contact, key, wait_timeout).await
+}
+
+/// Builds the parameter change proposal that pauses or unpauses the bridge
+pub fn pause_bridge_proposal(proposal: PauseBridgeProposalJson) -> ParameterChangeProposal {
     let mut params_to_change = Vec::new();
     let halt = ParamChange {
         subspace: "gravity".to_string(),
@@ -149,12 +191,11 @@ pub async fn submit_pause_bridge_proposal(
         value: format!("{}", proposal.paused),
     };
     params_to_change.push(halt);
-    let proposal = ParameterChangeProposal {
+    ParameterChangeProposal {
         title: proposal.title,
         description: proposal.description,
         changes: params_to_change,
-    };
-    submit_parameter_change_proposal(proposal, deposit, fee, contact, key, wait_timeout).await
+    }
 }
 
 /// Encodes and submits a proposal change bridge parameters, should maybe be in deep_space
@@ -173,14 +214,245 @@ pub async fn submit_parameter_change_proposal(
         .await
 }
 
+/// The params subspace of the Gravity module
+pub const GRAVITY_PARAMS_SUBSPACE: &str = "gravity";
+
+/// The JSON type of a Gravity module parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GravityParamType {
+    String,
+    Uint64,
+    Bool,
+    /// A decimal with 18 decimal places, such as a slashing fraction
+    Dec,
+    Coin,
+    EthAddress,
+    EthAddressList,
+    /// An ERC20 and the denom it is swapped into, or both empty
+    Erc20ToDenom,
+}
+
+/// The keys of the Gravity module parameters, as used in parameter change proposals. These are
+/// the keys of `Params.ParamSetPairs` in module/x/gravity/types/genesis.go
+pub const GRAVITY_PARAMS: &[(&str, GravityParamType)] = &[
+    ("GravityID", GravityParamType::String),
+    ("ContractHash", GravityParamType::String),
+    ("BridgeEthereumAddress", GravityParamType::EthAddress),
+    ("BridgeChainID", GravityParamType::Uint64),
+    ("SignedValsetsWindow", GravityParamType::Uint64),
+    ("SignedBatchesWindow", GravityParamType::Uint64),
+    ("SignedLogicCallsWindow", GravityParamType::Uint64),
+    ("TargetBatchTimeout", GravityParamType::Uint64),
+    ("AverageBlockTime", GravityParamType::Uint64),
+    ("AverageEthereumBlockTime", GravityParamType::Uint64),
+    ("SlashFractionValset", GravityParamType::Dec),
+    ("SlashFractionBatch", GravityParamType::Dec),
+    ("UnbondSlashingValsetsWindow", GravityParamType::Uint64),
+    ("SlashFractionBadEthSignature", GravityParamType::Dec),
+    ("ValsetReward", GravityParamType::Coin),
+    ("BridgeActive", GravityParamType::Bool),
+    ("EthereumBlacklist", GravityParamType::EthAddressList),
+    ("Erc20ToDenomPermanentSwap", GravityParamType::Erc20ToDenom),
+];
+
+/// The proposal.json representation for changing Gravity module parameters
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParamChangeProposalJson {
+    pub title: String,
+    pub description: String,
+    pub changes: Vec<ParamChangeJson>,
+}
+
+/// A single parameter change, `value` is plain JSON such as `10`, `"0.001"`, `true` or
+/// `{"denom": "ugraviton", "amount": "1000"}` and is converted to the chain's encoding
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParamChangeJson {
+    pub key: String,
+    pub value: Value,
+}
+
+/// Returns the type of a Gravity module parameter, or None if `key` is not a parameter
+pub fn gravity_param_type(key: &str) -> Option<GravityParamType> {
+    GRAVITY_PARAMS
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, t)| *t)
+}
+
+/// Decodes an sdk.Dec, which is stored as the string of its integer value with 18 decimals
+pub fn decode_dec(bytes: &[u8]) -> String {
+    match String::from_utf8(bytes.to_vec())
+        .ok()
+        .and_then(|s| Uint256::from_dec_or_hex_str_restricted(&s).ok())
+    {
+        Some(v) => format_token_amount(v, 18),
+        None => "0".to_string(),
+    }
+}
+
+/// Returns the current value of the Gravity module parameter `key` in the chain's JSON
+/// encoding, or None if `key` is not a parameter or its value is not known
+pub fn current_param_value(params: &Params, key: &str) -> Option<String> {
+    current_param_json(params, key).map(|value| value.to_string())
+}
+
+/// Returns the current value of the Gravity module parameter `key` as JSON, or None if `key`
+/// is not a parameter or its value is not known
+pub fn current_param_json(params: &Params, key: &str) -> Option<Value> {
+    let value = match key {
+        "GravityID" => json!(params.gravity_id),
+        "ContractHash" => json!(params.contract_source_hash),
+        "BridgeEthereumAddress" => json!(params.bridge_ethereum_address),
+        "BridgeChainID" => json!(params.bridge_chain_id.to_string()),
+        "SignedValsetsWindow" => json!(params.signed_valsets_window.to_string()),
+        "SignedBatchesWindow" => json!(params.signed_batches_window.to_string()),
+        "SignedLogicCallsWindow" => json!(params.signed_logic_calls_window.to_string()),
+        "TargetBatchTimeout" => json!(params.target_batch_timeout.to_string()),
+        "AverageBlockTime" => json!(params.average_block_time.to_string()),
+        "AverageEthereumBlockTime" => json!(params.average_ethereum_block_time.to_string()),
+        "SlashFractionValset" => json!(decode_dec(&params.slash_fraction_valset)),
+        "SlashFractionBatch" => json!(decode_dec(&params.slash_fraction_batch)),
+        "UnbondSlashingValsetsWindow" => {
+            json!(params.unbond_slashing_valsets_window.to_string())
+        }
+        "SlashFractionBadEthSignature" => {
+            json!(decode_dec(&params.slash_fraction_bad_eth_signature))
+        }
+        "ValsetReward" => match &params.valset_reward {
+            Some(c) => json!({"denom": c.denom, "amount": c.amount}),
+            None => json!({"denom": "", "amount": "0"}),
+        },
+        "BridgeActive" => json!(params.bridge_active),
+        "EthereumBlacklist" => json!(params.ethereum_blacklist),
+        // the generated Params do not include the permanent swap yet
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Checks that `value` is valid for the Gravity module parameter `key` and converts it to the
+/// chain's JSON encoding, where integers are quoted strings
+pub fn encode_param_value(key: &str, value: &Value) -> Result<String, GravityError> {
+    let invalid = |expected: &str| {
+        GravityError::ValidationError(format!(
+            "Invalid value {} for {}, expected {}",
+            value, key, expected
+        ))
+    };
+    let param_type = match gravity_param_type(key) {
+        Some(t) => t,
+        None => {
+            return Err(GravityError::ValidationError(format!(
+                "{} is not a Gravity parameter",
+                key
+            )))
+        }
+    };
+    // numbers may be given either as JSON numbers or as strings
+    let as_str = match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    let encoded = match param_type {
+        GravityParamType::String => match value {
+            Value::String(s) => json!(s),
+            _ => return Err(invalid("a string")),
+        },
+        GravityParamType::Uint64 => match as_str.and_then(|s| s.parse::<u64>().ok()) {
+            Some(v) => json!(v.to_string()),
+            None => return Err(invalid("an unsigned integer")),
+        },
+        GravityParamType::Bool => match value {
+            Value::Bool(b) => json!(b),
+            _ => return Err(invalid("true or false")),
+        },
+        GravityParamType::Dec => match as_str.and_then(|s| s.parse::<TokenAmount>().ok()) {
+            Some(v) => json!(format_token_amount(v.to_base_units(18)?, 18)),
+            None => return Err(invalid("a decimal")),
+        },
+        GravityParamType::Coin => {
+            let denom = value["denom"].as_str();
+            let amount = match &value["amount"] {
+                Value::String(s) => Uint256::from_dec_or_hex_str_restricted(s).ok(),
+                Value::Number(n) => Uint256::from_dec_or_hex_str_restricted(&n.to_string()).ok(),
+                _ => None,
+            };
+            match (denom, amount) {
+                (Some(denom), Some(amount)) => {
+                    json!({"denom": denom, "amount": amount.to_string()})
+                }
+                _ => return Err(invalid("{\"denom\": <denom>, \"amount\": <amount>}")),
+            }
+        }
+        GravityParamType::EthAddress => match value.as_str().map(str::parse::<EthAddress>) {
+            Some(Ok(a)) => json!(a.to_string()),
+            _ => return Err(invalid("an Ethereum address")),
+        },
+        GravityParamType::EthAddressList => {
+            let mut addresses = Vec::new();
+            for a in value
+                .as_array()
+                .ok_or_else(|| invalid("a list of Ethereum addresses"))?
+            {
+                match a.as_str().map(str::parse::<EthAddress>) {
+                    Some(Ok(a)) => addresses.push(a.to_string()),
+                    _ => return Err(invalid("a list of Ethereum addresses")),
+                }
+            }
+            json!(addresses)
+        }
+        GravityParamType::Erc20ToDenom => {
+            let erc20 = value["erc20"].as_str();
+            let denom = value["denom"].as_str();
+            match (erc20, denom) {
+                (Some(""), Some("")) => json!({"erc20": "", "denom": ""}),
+                (Some(erc20), Some(denom)) if !denom.is_empty() => {
+                    match erc20.parse::<EthAddress>() {
+                        Ok(erc20) => json!({"erc20": erc20.to_string(), "denom": denom}),
+                        Err(_) => return Err(invalid("an Ethereum address as the erc20")),
+                    }
+                }
+                _ => return Err(invalid("{\"erc20\": <address>, \"denom\": <denom>}")),
+            }
+        }
+    };
+    Ok(encoded.to_string())
+}
+
+/// Builds a parameter change proposal for the Gravity module, checking each change against the
+/// parameter types
+pub fn gravity_param_change_proposal(
+    proposal: ParamChangeProposalJson,
+) -> Result<ParameterChangeProposal, GravityError> {
+    let mut changes = Vec::new();
+    for change in proposal.changes {
+        changes.push(ParamChange {
+            subspace: GRAVITY_PARAMS_SUBSPACE.to_string(),
+            value: encode_param_value(&change.key, &change.value)?,
+            key: change.key,
+        });
+    }
+    if changes.is_empty() {
+        return Err(GravityError::ValidationError(
+            "The proposal does not change any parameters".into(),
+        ));
+    }
+    Ok(ParameterChangeProposal {
+        title: proposal.title,
+        description: proposal.description,
+        changes,
+    })
+}
+
 // local types for which we can implement serialize/deserialize
 // for json work
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct IbcMetadataProposalJson {
-    title: String,
-    description: String,
-    metadata: MetadataJson,
-    ibc_denom: String,
+    pub title: String,
+    pub description: String,
+    pub metadata: MetadataJson,
+    pub ibc_denom: String,
 }
 impl From<IbcMetadataProposalJson> for IbcMetadataProposal {
     fn from(v: IbcMetadataProposalJson) -> Self {
@@ -243,3 +515,122 @@ pub async fn submit_ibc_metadata_proposal(
         .create_gov_proposal(any, deposit, fee, key, wait_timeout)
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_param_json() {
+        let params = Params::default();
+        for (key, param_type) in GRAVITY_PARAMS.iter() {
+            let current = current_param_json(&params, key);
+            if *param_type == GravityParamType::Erc20ToDenom {
+                // the generated Params do not include the permanent swap yet
+                assert_eq!(current, None);
+                continue;
+            }
+            let current = current.unwrap_or_else(|| panic!("{} has no current value", key));
+            // the current value is in the same JSON encoding as a proposed value
+            match param_type {
+                GravityParamType::Uint64 => assert_eq!(current, json!("0"), "{}", key),
+                GravityParamType::Bool => assert!(current.is_boolean(), "{}", key),
+                GravityParamType::EthAddressList => assert!(current.is_array(), "{}", key),
+                GravityParamType::Coin => assert!(current["denom"].is_string(), "{}", key),
+                _ => assert!(current.is_string(), "{}", key),
+            }
+        }
+        assert_eq!(current_param_json(&params, "NotAParam"), None);
+    }
+
+    #[test]
+    fn test_encode_param_value() {
+        assert_eq!(
+            encode_param_value("SignedValsetsWindow", &json!(10000)).unwrap(),
+            "\"10000\""
+        );
+        assert_eq!(
+            encode_param_value("SignedValsetsWindow", &json!("10000")).unwrap(),
+            "\"10000\""
+        );
+        assert!(encode_param_value("SignedValsetsWindow", &json!(-1)).is_err());
+        assert!(encode_param_value("SignedValsetsWindow", &json!(true)).is_err());
+        assert_eq!(
+            encode_param_value("SlashFractionValset", &json!("0.0010")).unwrap(),
+            "\"0.001\""
+        );
+        assert!(encode_param_value("SlashFractionValset", &json!("0.1.2")).is_err());
+        assert_eq!(
+            encode_param_value("BridgeActive", &json!(false)).unwrap(),
+            "false"
+        );
+        assert!(encode_param_value("BridgeActive", &json!("false")).is_err());
+        assert_eq!(
+            encode_param_value(
+                "ValsetReward",
+                &json!({"denom": "ugraviton", "amount": 1000})
+            )
+            .unwrap(),
+            "{\"amount\":\"1000\",\"denom\":\"ugraviton\"}"
+        );
+        assert!(encode_param_value("ValsetReward", &json!({"denom": "ugraviton"})).is_err());
+        assert!(encode_param_value("BridgeEthereumAddress", &json!("0x1234")).is_err());
+        assert!(encode_param_value(
+            "EthereumBlacklist",
+            &json!(["0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"])
+        )
+        .is_ok());
+        assert!(encode_param_value("EthereumBlacklist", &json!(["0x1234"])).is_err());
+        assert!(encode_param_value(
+            "Erc20ToDenomPermanentSwap",
+            &json!({"erc20": "", "denom": ""})
+        )
+        .is_ok());
+        assert!(encode_param_value(
+            "Erc20ToDenomPermanentSwap",
+            &json!({"erc20": "", "denom": "ugraviton"})
+        )
+        .is_err());
+        // keys that are not in ParamSetPairs can not be changed
+        assert!(encode_param_value("ContractSourceHash", &json!("abc")).is_err());
+        assert!(encode_param_value("SlashFractionLogicCall", &json!("0.001")).is_err());
+    }
+
+    #[test]
+    fn test_gravity_param_change_proposal() {
+        let proposal: ParamChangeProposalJson = serde_json::from_str(
+            r#"{
+                "title": "Change windows",
+                "description": "Longer windows",
+                "changes": [
+                    {"key": "SignedValsetsWindow", "value": 20000},
+                    {"key": "BridgeActive", "value": true}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let proposal = gravity_param_change_proposal(proposal).unwrap();
+        assert_eq!(proposal.title, "Change windows");
+        assert_eq!(proposal.changes.len(), 2);
+        assert_eq!(proposal.changes[0].subspace, GRAVITY_PARAMS_SUBSPACE);
+        assert_eq!(proposal.changes[0].key, "SignedValsetsWindow");
+        assert_eq!(proposal.changes[0].value, "\"20000\"");
+        assert_eq!(proposal.changes[1].value, "true");
+
+        let empty = ParamChangeProposalJson {
+            title: "Nothing".into(),
+            description: "No changes".into(),
+            changes: vec![],
+        };
+        assert!(gravity_param_change_proposal(empty).is_err());
+        let invalid = ParamChangeProposalJson {
+            title: "Invalid".into(),
+            description: "Not a parameter".into(),
+            changes: vec![ParamChangeJson {
+                key: "NotAParam".into(),
+                value: json!(1),
+            }],
+        };
+        assert!(gravity_param_change_proposal(invalid).is_err());
+    }
+}
@@ -3,13 +3,16 @@ use std::convert::TryFrom;
 use gravity_proto::gravity::{
     query_client::QueryClient as GravityQueryClient, Attestation, Params, QueryAttestationsRequest,
     QueryBatchConfirmsRequest, QueryBatchFeeRequest, QueryBatchFeeResponse,
-    QueryCurrentValsetRequest, QueryDenomToErc20Request, QueryDenomToErc20Response,
-    QueryErc20ToDenomRequest, QueryErc20ToDenomResponse, QueryLastEventNonceByAddrRequest,
-    QueryLastPendingBatchRequestByAddrRequest, QueryLastPendingLogicCallByAddrRequest,
-    QueryLastPendingValsetRequestByAddrRequest, QueryLastValsetRequestsRequest,
-    QueryLogicConfirmsRequest, QueryOutgoingLogicCallsRequest, QueryOutgoingTxBatchesRequest,
-    QueryParamsRequest, QueryPendingSendToEth, QueryPendingSendToEthResponse,
-    QueryValsetConfirmsByNonceRequest, QueryValsetRequestRequest,
+    QueryBatchRequestByNonceRequest, QueryCurrentValsetRequest, QueryDelegateKeysByEthAddress,
+    QueryDelegateKeysByEthAddressResponse, QueryDelegateKeysByOrchestratorAddress,
+    QueryDelegateKeysByOrchestratorAddressResponse, QueryDelegateKeysByValidatorAddress,
+    QueryDelegateKeysByValidatorAddressResponse, QueryDenomToErc20Request,
+    QueryDenomToErc20Response, QueryErc20ToDenomRequest, QueryErc20ToDenomResponse,
+    QueryLastEventNonceByAddrRequest, QueryLastPendingBatchRequestByAddrRequest,
+    QueryLastPendingLogicCallByAddrRequest, QueryLastPendingValsetRequestByAddrRequest,
+    QueryLastValsetRequestsRequest, QueryLogicConfirmsRequest, QueryOutgoingLogicCallsRequest,
+    QueryOutgoingTxBatchesRequest, QueryParamsRequest, QueryPendingSendToEth,
+    QueryPendingSendToEthResponse, QueryValsetConfirmsByNonceRequest, QueryValsetRequestRequest,
 };
 use gravity_utils::{
     clarity::Address as EthAddress, deep_space::address::Address, error::GravityError, types::*,
@@ -146,6 +149,27 @@ pub async fn get_latest_transaction_batches(
     Ok(out)
 }
 
+/// gets the transaction batch for a given nonce and token contract
+pub async fn get_transaction_batch_by_nonce(
+    client: &mut GravityQueryClient<Channel>,
+    nonce: u64,
+    contract_address: EthAddress,
+) -> Result<TransactionBatch, GravityError> {
+    let response = client
+        .batch_request_by_nonce(QueryBatchRequestByNonceRequest {
+            nonce,
+            contract_address: contract_address.to_string(),
+        })
+        .await?;
+    match response.into_inner().batch {
+        Some(batch) => TransactionBatch::try_from(batch),
+        None => Err(GravityError::ValidationError(format!(
+            "No batch with nonce {} for token {}",
+            nonce, contract_address
+        ))),
+    }
+}
+
 /// get all batch confirmations for a given nonce and denom
 pub async fn get_transaction_batch_signatures(
     client: &mut GravityQueryClient<Channel>,
@@ -300,3 +324,40 @@ pub async fn get_pending_batch_fees(
     let request = client.batch_fees(QueryBatchFeeRequest {}).await?;
     Ok(request.into_inner())
 }
+
+/// Gets the delegate Ethereum and Orchestrator addresses set by a validator
+pub async fn get_delegate_keys_by_validator(
+    client: &mut GravityQueryClient<Channel>,
+    validator_address: String,
+) -> Result<QueryDelegateKeysByValidatorAddressResponse, GravityError> {
+    let request = client
+        .get_delegate_key_by_validator(QueryDelegateKeysByValidatorAddress { validator_address })
+        .await?;
+    Ok(request.into_inner())
+}
+
+/// Gets the validator and Orchestrator addresses a delegate Ethereum address was set by
+pub async fn get_delegate_keys_by_eth(
+    client: &mut GravityQueryClient<Channel>,
+    eth_address: EthAddress,
+) -> Result<QueryDelegateKeysByEthAddressResponse, GravityError> {
+    let request = client
+        .get_delegate_key_by_eth(QueryDelegateKeysByEthAddress {
+            eth_address: eth_address.to_string(),
+        })
+        .await?;
+    Ok(request.into_inner())
+}
+
+/// Gets the validator and Ethereum addresses a delegate Orchestrator address was set by
+pub async fn get_delegate_keys_by_orchestrator(
+    client: &mut GravityQueryClient<Channel>,
+    orchestrator_address: Address,
+) -> Result<QueryDelegateKeysByOrchestratorAddressResponse, GravityError> {
+    let request = client
+        .get_delegate_key_by_orchestrator(QueryDelegateKeysByOrchestratorAddress {
+            orchestrator_address: orchestrator_address.to_string(),
+        })
+        .await?;
+    Ok(request.into_inner())
+}
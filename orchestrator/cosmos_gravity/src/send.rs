@@ -364,6 +364,42 @@ pub async fn send_to_eth(
         .await
 }
 
+/// Sends tokens from Cosmos to Ethereum to many destinations in a single transaction, each send
+/// is a (destination, amount, bridge_fee) and is batched independently like a `send_to_eth`
+pub async fn send_to_eth_many(
+    private_key: PrivateKey,
+    sends: &[(EthAddress, Coin, Coin)],
+    fee: Coin,
+    contact: &Contact,
+) -> Result<TxResponse, CosmosGrpcError> {
+    let our_address = private_key.to_address(&contact.get_prefix()).unwrap();
+    let mut msgs = Vec::new();
+    for (destination, amount, bridge_fee) in sends {
+        if amount.denom != bridge_fee.denom {
+            return Err(CosmosGrpcError::BadInput(format!(
+                "{} {} is an invalid denom set for SendToEth you must pay fees in the same token your sending",
+                amount.denom, bridge_fee.denom,
+            )));
+        }
+        let msg_send_to_eth = MsgSendToEth {
+            sender: our_address.to_string(),
+            eth_dest: destination.to_string(),
+            amount: Some(amount.clone().into()),
+            bridge_fee: Some(bridge_fee.clone().into()),
+        };
+        msgs.push(Msg::new("/gravity.v1.MsgSendToEth", msg_send_to_eth));
+    }
+    contact
+        .send_message(
+            &msgs,
+            Some(MEMO.to_string()),
+            &[fee],
+            Some(TIMEOUT),
+            private_key,
+        )
+        .await
+}
+
 pub async fn send_request_batch(
     private_key: PrivateKey,
     denom: String,
@@ -453,3 +489,50 @@ pub async fn cancel_send_to_eth(
         )
         .await
 }
+
+/// Replaces an unbatched send to Ethereum with a new send of `amount` paying `bridge_fee`. The cancel
+/// and the new send are made in a single transaction, so if either fails neither is executed
+#[allow(clippy::too_many_arguments)]
+pub async fn resend_to_eth_with_fee(
+    private_key: PrivateKey,
+    transaction_id: u64,
+    destination: EthAddress,
+    amount: Coin,
+    bridge_fee: Coin,
+    fee: Coin,
+    contact: &Contact,
+) -> Result<TxResponse, CosmosGrpcError> {
+    let our_address = private_key.to_address(&contact.get_prefix()).unwrap();
+    if amount.denom != bridge_fee.denom {
+        return Err(CosmosGrpcError::BadInput(format!(
+            "{} {} is an invalid denom set for SendToEth you must pay fees in the same token your sending",
+            amount.denom, bridge_fee.denom,
+        )));
+    }
+
+    let msg_cancel_send_to_eth = MsgCancelSendToEth {
+        transaction_id,
+        sender: our_address.to_string(),
+    };
+    let msg_send_to_eth = MsgSendToEth {
+        sender: our_address.to_string(),
+        eth_dest: destination.to_string(),
+        amount: Some(amount.into()),
+        bridge_fee: Some(bridge_fee.into()),
+    };
+
+    // the cancel must come first, it returns the tokens used by the new send
+    let msgs = [
+        Msg::new("/gravity.v1.MsgCancelSendToEth", msg_cancel_send_to_eth),
+        Msg::new("/gravity.v1.MsgSendToEth", msg_send_to_eth),
+    ];
+    contact
+        .send_message(
+            &msgs,
+            Some(MEMO.to_string()),
+            &[fee],
+            Some(TIMEOUT),
+            private_key,
+        )
+        .await
+}
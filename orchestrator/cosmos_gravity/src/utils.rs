@@ -1,12 +1,14 @@
 use gravity_proto::gravity::{
-    query_client::QueryClient as GravityQueryClient, OutgoingLogicCall as ProtoLogicCall,
-    OutgoingTxBatch as ProtoBatch, Valset as ProtoValset,
+    query_client::QueryClient as GravityQueryClient, MsgBatchSendToEthClaim, MsgErc20DeployedClaim,
+    MsgLogicCallExecutedClaim, MsgSendToCosmosClaim, MsgValsetUpdatedClaim,
+    OutgoingLogicCall as ProtoLogicCall, OutgoingTxBatch as ProtoBatch, Valset as ProtoValset,
 };
 use gravity_utils::{
     deep_space::{utils::encode_any, Address as CosmosAddress},
     get_with_retry::RETRY_TIME,
     types::{LogicCall, TransactionBatch, Valset},
 };
+use prost::Message;
 use prost_types::Any;
 use tokio::time::sleep;
 use tonic::transport::Channel;
@@ -33,6 +35,72 @@ pub async fn get_last_event_nonce_with_retry(
     }
 }
 
+/// The claim of an attestation decoded by its type
+pub enum AttestationClaim {
+    SendToCosmos(MsgSendToCosmosClaim),
+    BatchSendToEth(MsgBatchSendToEthClaim),
+    Erc20Deployed(MsgErc20DeployedClaim),
+    LogicCallExecuted(MsgLogicCallExecutedClaim),
+    ValsetUpdated(MsgValsetUpdatedClaim),
+}
+
+impl AttestationClaim {
+    /// Decodes the claim of an attestation, returns None for claims of an unknown type
+    pub fn decode(claim: &Any) -> Option<Self> {
+        let value = claim.value.as_slice();
+        match claim.type_url.as_str() {
+            "/gravity.v1.MsgSendToCosmosClaim" => MsgSendToCosmosClaim::decode(value)
+                .ok()
+                .map(AttestationClaim::SendToCosmos),
+            "/gravity.v1.MsgBatchSendToEthClaim" => MsgBatchSendToEthClaim::decode(value)
+                .ok()
+                .map(AttestationClaim::BatchSendToEth),
+            "/gravity.v1.MsgERC20DeployedClaim" => MsgErc20DeployedClaim::decode(value)
+                .ok()
+                .map(AttestationClaim::Erc20Deployed),
+            "/gravity.v1.MsgLogicCallExecutedClaim" => MsgLogicCallExecutedClaim::decode(value)
+                .ok()
+                .map(AttestationClaim::LogicCallExecuted),
+            "/gravity.v1.MsgValsetUpdatedClaim" => MsgValsetUpdatedClaim::decode(value)
+                .ok()
+                .map(AttestationClaim::ValsetUpdated),
+            _ => None,
+        }
+    }
+
+    /// A short description of the event claimed
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AttestationClaim::SendToCosmos(_) => "send to Cosmos",
+            AttestationClaim::BatchSendToEth(_) => "batch executed",
+            AttestationClaim::Erc20Deployed(_) => "ERC20 deployed",
+            AttestationClaim::LogicCallExecuted(_) => "logic call executed",
+            AttestationClaim::ValsetUpdated(_) => "valset updated",
+        }
+    }
+
+    pub fn event_nonce(&self) -> u64 {
+        match self {
+            AttestationClaim::SendToCosmos(c) => c.event_nonce,
+            AttestationClaim::BatchSendToEth(c) => c.event_nonce,
+            AttestationClaim::Erc20Deployed(c) => c.event_nonce,
+            AttestationClaim::LogicCallExecuted(c) => c.event_nonce,
+            AttestationClaim::ValsetUpdated(c) => c.event_nonce,
+        }
+    }
+
+    /// The Ethereum block height of the event
+    pub fn block_height(&self) -> u64 {
+        match self {
+            AttestationClaim::SendToCosmos(c) => c.block_height,
+            AttestationClaim::BatchSendToEth(c) => c.block_height,
+            AttestationClaim::Erc20Deployed(c) => c.block_height,
+            AttestationClaim::LogicCallExecuted(c) => c.block_height,
+            AttestationClaim::ValsetUpdated(c) => c.block_height,
+        }
+    }
+}
+
 pub enum BadSignatureEvidence {
     Valset(Valset),
     Batch(TransactionBatch),
@@ -0,0 +1,73 @@
+//! Helper functions for managing the ERC20 allowance of the Gravity contract, which must be able to
+//! transfer tokens from the sender in order to send them to Cosmos
+
+use std::time::Duration;
+
+use gravity_utils::{
+    clarity::{abi::encode_call, u256, Address, PrivateKey as EthPrivateKey, Uint256},
+    error::GravityError,
+    u64_array_bigints,
+    web30::{client::Web3, types::SendTxOption},
+};
+
+/// How the Gravity contract is approved to transfer the tokens being sent to Cosmos. There is
+/// no EIP-2612 permit mode, the Gravity contract's sendToCosmos has no variant that accepts a
+/// permit signature, so the permit would have to be submitted in a transaction of its own and
+/// would cost as much as the approval it replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Erc20Approval {
+    /// Approve the maximum possible amount if the allowance is insufficient
+    Max,
+    /// Approve exactly the amount being sent if the allowance is insufficient
+    Exact,
+    /// Only use the existing allowance, failing if it is insufficient
+    Existing,
+}
+
+/// Gets the amount `spender` is allowed to transfer from `owner`
+pub async fn get_erc20_allowance(
+    erc20: Address,
+    owner: Address,
+    spender: Address,
+    web3: &Web3,
+) -> Result<Uint256, GravityError> {
+    let payload = encode_call(
+        "allowance(address,address)",
+        &[owner.into(), spender.into()],
+    )?;
+    let val = web3
+        .simulate_transaction(erc20, u256!(0), payload, owner, None)
+        .await?;
+    match Uint256::from_bytes_be(&val) {
+        Some(allowance) => Ok(allowance),
+        None => Err(GravityError::ValidationError(format!(
+            "Invalid allowance response from {}",
+            erc20
+        ))),
+    }
+}
+
+/// Approves `spender` to transfer exactly `amount` of `erc20`, an amount of zero revokes an
+/// existing approval. Returns the txid of the approval
+pub async fn approve_erc20_amount(
+    erc20: Address,
+    spender: Address,
+    amount: Uint256,
+    sender_secret: EthPrivateKey,
+    wait_timeout: Duration,
+    web3: &Web3,
+    options: Vec<SendTxOption>,
+) -> Result<Uint256, GravityError> {
+    let txid = web3
+        .send_transaction(
+            erc20,
+            encode_call("approve(address,uint256)", &[spender.into(), amount.into()])?,
+            u256!(0),
+            sender_secret.to_address(),
+            &sender_secret,
+            options,
+        )
+        .await?;
+    web3.wait_for_transaction(txid, wait_timeout, None).await?;
+    Ok(txid)
+}
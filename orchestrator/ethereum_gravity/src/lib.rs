@@ -4,10 +4,15 @@
 extern crate log;
 
 pub mod deploy_erc20;
+pub mod erc20_approval;
 pub mod logic_call;
 pub mod message_signatures;
+pub mod offline;
+pub mod pending_calls;
+pub mod private_submission;
 pub mod send_to_cosmos;
 pub mod submit_batch;
 mod test_cases;
 pub mod utils;
 pub mod valset_update;
+pub mod weth;
@@ -15,11 +15,16 @@ use gravity_utils::{
 
 use crate::{
     message_signatures::encode_logic_call_confirm_hashed,
-    utils::{encode_valset_struct, get_logic_call_nonce, GasCost},
+    private_submission::send_relayer_transaction,
+    utils::{encode_valset_struct, get_logic_call_nonce, GasCost, RelayOutcome},
 };
 
+/// The Solidity signature of the Gravity contract's submitLogicCall function
+pub const SUBMIT_LOGIC_CALL_SIGNATURE: &str = "submitLogicCall((address[],uint256[],uint256,uint256,address),(uint8,bytes32,bytes32)[],(uint256[],address[],uint256[],address[],address,bytes,uint256,bytes32,uint256))";
+
 /// this function generates an appropriate Ethereum transaction
-/// to submit the provided logic call
+/// to submit the provided logic call. The call is skipped if it was already submitted
+/// or has timed out
 #[allow(clippy::too_many_arguments)]
 pub async fn send_eth_logic_call(
     current_valset: &Valset,
@@ -30,7 +35,8 @@ pub async fn send_eth_logic_call(
     gravity_contract_address: EthAddress,
     gravity_id: String,
     our_eth_key: EthPrivateKey,
-) -> Result<(), GravityError> {
+    private_submission: Option<&PrivateSubmissionConfig>,
+) -> Result<RelayOutcome, GravityError> {
     let new_call_nonce = call.invalidation_nonce;
     let eth_address = our_eth_key.to_address();
     info!(
@@ -53,50 +59,55 @@ pub async fn send_eth_logic_call(
             "Someone else updated the LogicCall to {}, exiting early",
             before_nonce
         );
-        return Ok(());
+        return Ok(RelayOutcome::Skipped);
     } else if current_block_height > Uint256::from_u64(call.timeout) {
         info!(
             "This LogicCall is timed out. timeout block: {} current block: {}, exiting early",
             current_block_height, call.timeout
         );
-        return Ok(());
+        return Ok(RelayOutcome::Skipped);
     }
 
     let payload = encode_logic_call_payload(current_valset, &call, confirms, gravity_id)?;
 
-    let tx = web3
-        .send_transaction(
-            gravity_contract_address,
-            payload,
-            u256!(0),
-            eth_address,
-            &our_eth_key,
-            vec![],
-        )
-        .await?;
+    let tx = send_relayer_transaction(
+        web3,
+        gravity_contract_address,
+        payload,
+        our_eth_key,
+        vec![],
+        private_submission,
+        timeout,
+    )
+    .await?;
     info!("Sent batch update with txid {:#066x}", tx);
 
-    web3.wait_for_transaction(tx, timeout, None).await?;
+    // the transaction has been sent, so errors from here on are logged rather than returned
+    // and the relay is reported as sent but unconfirmed
+    if let Err(e) = web3.wait_for_transaction(tx, timeout, None).await {
+        error!("Failed waiting for LogicCall {:#066x} {:?}", tx, e);
+        return Ok(RelayOutcome::Sent);
+    }
 
-    let last_nonce = get_logic_call_nonce(
+    match get_logic_call_nonce(
         gravity_contract_address,
         call.invalidation_id,
         eth_address,
         web3,
     )
-    .await?;
-    if last_nonce != new_call_nonce {
-        error!(
+    .await
+    {
+        Ok(last_nonce) if last_nonce != new_call_nonce => error!(
             "Current nonce is {} expected to update to nonce {}",
             last_nonce, new_call_nonce
-        );
-    } else {
-        info!(
+        ),
+        Ok(last_nonce) => info!(
             "Successfully updated LogicCall with new Nonce {:?}",
             last_nonce
-        );
+        ),
+        Err(e) => warn!("Failed to get the LogicCall nonce after the update {:?}", e),
     }
-    Ok(())
+    Ok(RelayOutcome::Confirmed)
 }
 
 /// Returns the cost in Eth of sending this batch
@@ -203,11 +214,7 @@ fn encode_logic_call_payload(
         sig_arrays.sigs,
         Token::Struct(struct_tokens.to_vec()),
     ];
-    let payload = encode_call(
-        "submitLogicCall((address[],uint256[],uint256,uint256,address),(uint8,bytes32,bytes32)[],(uint256[],address[],uint256[],address[],address,bytes,uint256,bytes32,uint256))",
-        tokens,
-    )
-    .unwrap();
+    let payload = encode_call(SUBMIT_LOGIC_CALL_SIGNATURE, tokens).unwrap();
     trace!("Tokens {:?}", tokens);
 
     Ok(payload)
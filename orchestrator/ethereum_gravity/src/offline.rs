@@ -0,0 +1,168 @@
+//! Builds unsigned Ethereum transactions so that they can be signed by an external signer, such
+//! as a hardware wallet or an offline machine, instead of with a private key passed to gbt
+
+use gravity_utils::{
+    clarity::{
+        abi::{encode_call, Token},
+        u256,
+        utils::bytes_to_hex_str,
+        Address, Uint256,
+    },
+    deep_space::address::Address as CosmosAddress,
+    error::GravityError,
+    u64_array_bigints,
+    web30::{amm::WETH_CONTRACT_ADDRESS, client::Web3},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    erc20_approval::{get_erc20_allowance, Erc20Approval},
+    send_to_cosmos::SEND_TO_COSMOS_GAS_LIMIT,
+};
+
+/// The gas limit of the WETH deposit and ERC20 approval transactions
+pub const ERC20_GAS_LIMIT: Uint256 = u256!(100_000);
+
+/// An unsigned Ethereum transaction, in the same hex encoded format as the parameters of
+/// `eth_signTransaction`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedEthTx {
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    pub nonce: String,
+    pub gas: String,
+    pub gas_price: String,
+    pub chain_id: String,
+}
+
+/// The parameters shared by all of the transactions of a sender
+struct TxContext {
+    from: Address,
+    nonce: Uint256,
+    gas_price: Uint256,
+    chain_id: u64,
+}
+
+impl TxContext {
+    /// Builds the next transaction of the sender, incrementing the nonce
+    fn next_tx(
+        &mut self,
+        to: Address,
+        value: Uint256,
+        data: Vec<u8>,
+        gas: Uint256,
+    ) -> UnsignedEthTx {
+        let tx = UnsignedEthTx {
+            from: self.from.to_string(),
+            to: to.to_string(),
+            value: format!("{:#x}", value),
+            data: format!("0x{}", bytes_to_hex_str(&data)),
+            nonce: format!("{:#x}", self.nonce),
+            gas: format!("{:#x}", gas),
+            gas_price: format!("{:#x}", self.gas_price),
+            chain_id: format!("{:#x}", self.chain_id),
+        };
+        self.nonce = self.nonce.checked_add(u256!(1)).unwrap();
+        tx
+    }
+}
+
+/// Builds the unsigned transactions to send `amount` of `erc20` to Cosmos from `sender`, in the
+/// order they must be submitted. When `wrap` is set `erc20` must be WETH and the ETH is wrapped
+/// first, an approval is only included if the current allowance is insufficient and `revoke`
+/// appends a transaction setting the allowance back to zero
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_send_to_cosmos_txs(
+    erc20: Address,
+    gravity_contract: Address,
+    amount: Uint256,
+    cosmos_destination: CosmosAddress,
+    sender: Address,
+    wrap: bool,
+    approval: Erc20Approval,
+    revoke: bool,
+    web3: &Web3,
+) -> Result<Vec<UnsignedEthTx>, GravityError> {
+    let mut ctx = TxContext {
+        from: sender,
+        nonce: web3.eth_get_transaction_count(sender).await?,
+        gas_price: web3.eth_gas_price().await?,
+        chain_id: web3.net_version().await?,
+    };
+    let mut txs = Vec::new();
+
+    if wrap {
+        txs.push(ctx.next_tx(
+            *WETH_CONTRACT_ADDRESS,
+            amount,
+            encode_call("deposit()", &[])?,
+            ERC20_GAS_LIMIT,
+        ));
+    }
+
+    let allowance = get_erc20_allowance(erc20, sender, gravity_contract, web3).await?;
+    if allowance < amount {
+        let approved = match approval {
+            Erc20Approval::Max => Uint256::max_value(),
+            Erc20Approval::Exact => amount,
+            Erc20Approval::Existing => {
+                return Err(GravityError::ValidationError(format!(
+                    "The existing allowance of {} for the gravity contract is less than {}",
+                    allowance, amount
+                )))
+            }
+        };
+        // tokens such as USDT revert when a nonzero allowance is changed to another nonzero
+        // value, so an insufficient allowance is reset to zero first
+        if !allowance.is_zero() {
+            txs.push(ctx.next_tx(
+                erc20,
+                u256!(0),
+                encode_call(
+                    "approve(address,uint256)",
+                    &[gravity_contract.into(), u256!(0).into()],
+                )?,
+                ERC20_GAS_LIMIT,
+            ));
+        }
+        txs.push(ctx.next_tx(
+            erc20,
+            u256!(0),
+            encode_call(
+                "approve(address,uint256)",
+                &[gravity_contract.into(), approved.into()],
+            )?,
+            ERC20_GAS_LIMIT,
+        ));
+    }
+
+    txs.push(ctx.next_tx(
+        gravity_contract,
+        u256!(0),
+        encode_call(
+            "sendToCosmos(address,string,uint256)",
+            &[
+                erc20.into(),
+                Token::String(cosmos_destination.to_string()),
+                amount.into(),
+            ],
+        )?,
+        SEND_TO_COSMOS_GAS_LIMIT,
+    ));
+
+    if revoke {
+        txs.push(ctx.next_tx(
+            erc20,
+            u256!(0),
+            encode_call(
+                "approve(address,uint256)",
+                &[gravity_contract.into(), u256!(0).into()],
+            )?,
+            ERC20_GAS_LIMIT,
+        ));
+    }
+    Ok(txs)
+}
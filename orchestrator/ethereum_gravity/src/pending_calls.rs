@@ -0,0 +1,344 @@
+//! Inspects transactions to the Gravity contract that have been broadcast but not yet included
+//! in a block, this allows relayers to detect that a competing relayer is already submitting
+//! the same batch, valset or logic call and avoid paying for a failed transaction
+
+use std::time::Duration;
+
+use gravity_utils::{
+    clarity::{utils::hex_str_to_bytes, Address as EthAddress, Uint256},
+    error::GravityError,
+    web30::{client::Web3, jsonrpc::client::HttpClient},
+};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    logic_call::SUBMIT_LOGIC_CALL_SIGNATURE, submit_batch::SUBMIT_BATCH_SIGNATURE,
+    valset_update::UPDATE_VALSET_SIGNATURE,
+};
+
+/// A relay to the Gravity contract found in a pending transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingGravityCall {
+    Valset {
+        nonce: u64,
+    },
+    Batch {
+        token_contract: EthAddress,
+        nonce: u64,
+    },
+    LogicCall {
+        invalidation_id: Vec<u8>,
+        nonce: u64,
+    },
+}
+
+impl PendingGravityCall {
+    /// Returns true if this pending call makes `other` impossible to submit, meaning
+    /// it is for the same valset, token or invalidation id with a nonce at least as high
+    pub fn supersedes(&self, other: &PendingGravityCall) -> bool {
+        match (self, other) {
+            (
+                PendingGravityCall::Valset { nonce },
+                PendingGravityCall::Valset { nonce: other_nonce },
+            ) => nonce >= other_nonce,
+            (
+                PendingGravityCall::Batch {
+                    token_contract,
+                    nonce,
+                },
+                PendingGravityCall::Batch {
+                    token_contract: other_token_contract,
+                    nonce: other_nonce,
+                },
+            ) => token_contract == other_token_contract && nonce >= other_nonce,
+            (
+                PendingGravityCall::LogicCall {
+                    invalidation_id,
+                    nonce,
+                },
+                PendingGravityCall::LogicCall {
+                    invalidation_id: other_invalidation_id,
+                    nonce: other_nonce,
+                },
+            ) => invalidation_id == other_invalidation_id && nonce >= other_nonce,
+            _ => false,
+        }
+    }
+}
+
+/// Gets the relays to the Gravity contract which are currently pending, sent by anyone other than
+/// `our_address`. The node's txpool is used if it is available, otherwise the pending block is used
+pub async fn get_pending_gravity_calls(
+    web3: &Web3,
+    gravity_contract_address: EthAddress,
+    our_address: EthAddress,
+    timeout: Duration,
+) -> Result<Vec<PendingGravityCall>, GravityError> {
+    // web3 only exposes the standard methods, the node's client is used for the others
+    let client = HttpClient::new(&web3.get_url());
+    let transactions = match client
+        .request_method::<_, Value>("txpool_content", json!([]), timeout)
+        .await
+    {
+        Ok(content) => {
+            // txpool content is organized as {"pending": {sender: {nonce: tx}}}
+            let mut transactions = Vec::new();
+            if let Some(pending) = content.get("pending").and_then(|p| p.as_object()) {
+                for sender in pending.values().filter_map(|s| s.as_object()) {
+                    transactions.extend(sender.values().cloned());
+                }
+            }
+            transactions
+        }
+        Err(e) => {
+            trace!(
+                "txpool_content unavailable, using the pending block {:?}",
+                e
+            );
+            let block: Value = client
+                .request_method("eth_getBlockByNumber", json!(["pending", true]), timeout)
+                .await?;
+            match block.get("transactions").and_then(|t| t.as_array()) {
+                Some(transactions) => transactions.clone(),
+                None => Vec::new(),
+            }
+        }
+    };
+
+    let mut calls = Vec::new();
+    for tx in transactions {
+        if get_address_field(&tx, "to") != Some(gravity_contract_address)
+            || get_address_field(&tx, "from") == Some(our_address)
+        {
+            continue;
+        }
+        let input = tx
+            .get("input")
+            .and_then(|i| i.as_str())
+            .and_then(|i| hex_str_to_bytes(i).ok());
+        if let Some(call) = input.and_then(|i| decode_gravity_call(&i)) {
+            calls.push(call);
+        }
+    }
+    Ok(calls)
+}
+
+fn get_address_field(tx: &Value, field: &str) -> Option<EthAddress> {
+    tx.get(field)
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse().ok())
+}
+
+/// Gets the 32 byte word at `offset` in `args`
+fn get_word(args: &[u8], offset: usize) -> Option<&[u8]> {
+    args.get(offset..offset.checked_add(32)?)
+}
+
+fn get_u64(args: &[u8], offset: usize) -> Option<u64> {
+    Uint256::from_bytes_be(get_word(args, offset)?)?.try_resize_to_u64()
+}
+
+fn get_usize(args: &[u8], offset: usize) -> Option<usize> {
+    get_u64(args, offset).map(|v| v as usize)
+}
+
+fn get_selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// A transfer paid out by a batch, as it appears in the submitBatch call data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchTransfer {
+    pub amount: Uint256,
+    pub destination: EthAddress,
+    pub fee: Uint256,
+}
+
+/// Gets the elements of the dynamic array whose offset is the argument at `arg`
+fn get_array(args: &[u8], arg: usize) -> Option<Vec<&[u8]>> {
+    let offset = get_usize(args, arg.checked_mul(32)?)?;
+    let len = get_usize(args, offset)?;
+    let mut elements = Vec::new();
+    for i in 0..len {
+        elements.push(get_word(
+            args,
+            offset.checked_add(32)?.checked_add(i.checked_mul(32)?)?,
+        )?);
+    }
+    Some(elements)
+}
+
+/// Decodes the transfers of a submitBatch call, returning None if it's not a batch
+/// or can not be decoded. Batches on Ethereum do not include the ids of their sends,
+/// so the transfers are the only way to relate an executed batch to a send
+pub fn decode_batch_transfers(input: &[u8]) -> Option<Vec<BatchTransfer>> {
+    if input.get(0..4)? != get_selector(SUBMIT_BATCH_SIGNATURE) {
+        return None;
+    }
+    let args = &input[4..];
+    // the amounts, destinations and fees are the dynamic arguments 2, 3 and 4
+    let amounts = get_array(args, 2)?;
+    let destinations = get_array(args, 3)?;
+    let fees = get_array(args, 4)?;
+    if amounts.len() != destinations.len() || amounts.len() != fees.len() {
+        return None;
+    }
+    let mut transfers = Vec::new();
+    for ((amount, destination), fee) in amounts.iter().zip(destinations).zip(fees) {
+        transfers.push(BatchTransfer {
+            amount: Uint256::from_bytes_be(amount)?,
+            destination: EthAddress::from_slice(&destination[12..]).ok()?,
+            fee: Uint256::from_bytes_be(fee)?,
+        });
+    }
+    Some(transfers)
+}
+
+/// Decodes the call data of a Gravity contract call, returning the relay it performs
+/// or None if it's not a relay or can not be decoded
+pub fn decode_gravity_call(input: &[u8]) -> Option<PendingGravityCall> {
+    let selector = input.get(0..4)?;
+    let args = &input[4..];
+    if selector == get_selector(SUBMIT_BATCH_SIGNATURE) {
+        // the batch nonce and token contract are static arguments 5 and 6
+        let token_contract = EthAddress::from_slice(&get_word(args, 6 * 32)?[12..]).ok()?;
+        Some(PendingGravityCall::Batch {
+            token_contract,
+            nonce: get_u64(args, 5 * 32)?,
+        })
+    } else if selector == get_selector(UPDATE_VALSET_SIGNATURE) {
+        // the new valset is a dynamic struct, the first argument is its offset
+        // and the nonce is the third field of the struct
+        let new_valset = get_usize(args, 0)?;
+        Some(PendingGravityCall::Valset {
+            nonce: get_u64(args, new_valset.checked_add(2 * 32)?)?,
+        })
+    } else if selector == get_selector(SUBMIT_LOGIC_CALL_SIGNATURE) {
+        // the logic call args are a dynamic struct at the offset in the third argument
+        // the invalidation id and nonce are the eighth and ninth fields of the struct
+        let logic_call = get_usize(args, 2 * 32)?;
+        Some(PendingGravityCall::LogicCall {
+            invalidation_id: get_word(args, logic_call.checked_add(7 * 32)?)?.to_vec(),
+            nonce: get_u64(args, logic_call.checked_add(8 * 32)?)?,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gravity_utils::{
+        clarity::abi::{encode_call, Token},
+        types::Valset,
+    };
+
+    use super::*;
+    use crate::utils::encode_valset_struct;
+
+    #[test]
+    fn test_decode_gravity_calls() {
+        let valset = Valset {
+            nonce: 7,
+            members: Vec::new(),
+            reward_amount: Uint256::default(),
+            reward_token: None,
+        };
+        let token_contract: EthAddress = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+            .parse()
+            .unwrap();
+
+        let batch = encode_call(
+            SUBMIT_BATCH_SIGNATURE,
+            &[
+                encode_valset_struct(&valset),
+                Token::Dynamic(Vec::new()),
+                Token::Dynamic(Vec::new()),
+                Token::Dynamic(Vec::new()),
+                Token::Dynamic(Vec::new()),
+                42u64.into(),
+                token_contract.into(),
+                1000u64.into(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            decode_gravity_call(&batch),
+            Some(PendingGravityCall::Batch {
+                token_contract,
+                nonce: 42
+            })
+        );
+
+        let new_valset = Valset {
+            nonce: 8,
+            ..valset.clone()
+        };
+        let valset_update = encode_call(
+            UPDATE_VALSET_SIGNATURE,
+            &[
+                encode_valset_struct(&new_valset),
+                encode_valset_struct(&valset),
+                Token::Dynamic(Vec::new()),
+            ],
+        )
+        .unwrap();
+        let decoded = decode_gravity_call(&valset_update).unwrap();
+        assert_eq!(decoded, PendingGravityCall::Valset { nonce: 8 });
+        assert!(decoded.supersedes(&PendingGravityCall::Valset { nonce: 8 }));
+        assert!(!decoded.supersedes(&PendingGravityCall::Valset { nonce: 9 }));
+
+        assert_eq!(decode_gravity_call(&[1, 2, 3, 4, 5]), None);
+    }
+
+    #[test]
+    fn test_decode_batch_transfers() {
+        let valset = Valset {
+            nonce: 7,
+            members: Vec::new(),
+            reward_amount: Uint256::default(),
+            reward_token: None,
+        };
+        let token_contract: EthAddress = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+            .parse()
+            .unwrap();
+        let destination: EthAddress = "0x0000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let transfers = vec![
+            BatchTransfer {
+                amount: Uint256::from_u64(100),
+                destination,
+                fee: Uint256::from_u64(3),
+            },
+            BatchTransfer {
+                amount: Uint256::from_u64(200),
+                destination: token_contract,
+                fee: Uint256::from_u64(5),
+            },
+        ];
+        let batch = encode_call(
+            SUBMIT_BATCH_SIGNATURE,
+            &[
+                encode_valset_struct(&valset),
+                Token::Dynamic(Vec::new()),
+                Token::Dynamic(transfers.iter().map(|t| Token::Uint(t.amount)).collect()),
+                transfers
+                    .iter()
+                    .map(|t| t.destination)
+                    .collect::<Vec<EthAddress>>()
+                    .into(),
+                Token::Dynamic(transfers.iter().map(|t| Token::Uint(t.fee)).collect()),
+                42u64.into(),
+                token_contract.into(),
+                1000u64.into(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(decode_batch_transfers(&batch), Some(transfers));
+        assert_eq!(decode_batch_transfers(&batch[..batch.len() - 32]), None);
+        assert_eq!(decode_batch_transfers(&[1, 2, 3, 4, 5]), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! Submission of relayer transactions, either through the public Ethereum rpc or through a
+//! separately configured private endpoint. Transactions sent privately are signed locally so
+//! that the exact same transaction can be broadcast publicly if the private endpoint does not
+//! get it included within the configured number of blocks. Privately sent transactions are
+//! watched in a background task so that the relayer loop is not blocked while they wait
+
+use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+use gravity_utils::{
+    clarity::{
+        u256, utils::bytes_to_hex_str, Address as EthAddress, PrivateKey as EthPrivateKey,
+        Transaction, Uint256,
+    },
+    error::GravityError,
+    types::{PrivateSubmissionConfig, PrivateSubmissionMethod},
+    u64_array_bigints,
+    web30::{
+        client::Web3,
+        jsonrpc::{client::HttpClient, error::Web3Error},
+        types::{SendTxOption, TransactionRequest},
+    },
+};
+use hyper::{body::to_bytes, header::CONTENT_TYPE, Body, Client, Method, Request};
+use hyper_tls::HttpsConnector;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use tokio::time::sleep;
+
+/// The header used to authenticate bundles with Flashbots style endpoints
+const FLASHBOTS_SIGNATURE_HEADER: &str = "X-Flashbots-Signature";
+
+/// How often a privately sent transaction is checked for inclusion
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    // senders with a privately sent transaction that is still being watched, any other
+    // transaction from the same sender would reuse its nonce and replace it
+    static ref WATCHED_SENDERS: Mutex<HashSet<EthAddress>> = Mutex::new(HashSet::new());
+}
+
+/// Sends a transaction calling `gravity_contract_address` with `payload` and returns the txid.
+/// Without a private submission config this is a regular broadcast through `web3`, otherwise
+/// the transaction is sent to the private endpoint and a background task broadcasts it
+/// publicly if it has not been included after `fallback_blocks`. Sending is refused while a
+/// private transaction from the same key is still being watched
+pub async fn send_relayer_transaction(
+    web3: &Web3,
+    gravity_contract_address: EthAddress,
+    payload: Vec<u8>,
+    our_eth_key: EthPrivateKey,
+    options: Vec<SendTxOption>,
+    private_submission: Option<&PrivateSubmissionConfig>,
+    timeout: Duration,
+) -> Result<Uint256, GravityError> {
+    let eth_address = our_eth_key.to_address();
+    let config = match private_submission {
+        Some(config) => config,
+        None => {
+            return Ok(web3
+                .send_transaction(
+                    gravity_contract_address,
+                    payload,
+                    u256!(0),
+                    eth_address,
+                    &our_eth_key,
+                    options,
+                )
+                .await?)
+        }
+    };
+    if has_pending_private_transaction(eth_address) {
+        return Err(GravityError::RecoverableError(format!(
+            "A private transaction from {} has not been included yet",
+            eth_address
+        )));
+    }
+
+    let nonce = web3.eth_get_transaction_count(eth_address).await?;
+    let mut gas_price = web3.eth_gas_price().await?;
+    for option in options.iter() {
+        if let SendTxOption::GasPriceMultiplier(multiplier) = option {
+            let gas_price_float: f64 = gas_price.to_string().parse().unwrap();
+            gas_price = Uint256::from_u128((gas_price_float * *multiplier as f64) as u128);
+        }
+    }
+    let gas_limit = web3
+        .eth_estimate_gas(TransactionRequest {
+            from: Some(eth_address),
+            to: gravity_contract_address,
+            nonce: Some(nonce.into()),
+            gas_price: Some(gas_price.into()),
+            gas: None,
+            value: Some(u256!(0).into()),
+            data: Some(payload.clone().into()),
+        })
+        .await?;
+    let net_version = web3.net_version().await?;
+    let tx = Transaction {
+        to: gravity_contract_address,
+        nonce,
+        gas_price,
+        gas_limit,
+        value: u256!(0),
+        data: payload,
+        signature: None,
+    }
+    .sign(&our_eth_key, Some(net_version));
+    let raw_tx = tx.to_bytes()?;
+    let txid = Uint256::from_bytes_be(&Keccak256::digest(&raw_tx)).unwrap();
+    let start_height = web3.eth_block_number().await?;
+
+    WATCHED_SENDERS.lock().unwrap().insert(eth_address);
+    let watch = PrivateTransaction {
+        raw_tx,
+        txid,
+        nonce,
+        start_height,
+        key: our_eth_key,
+    };
+    let (web3, config) = (web3.clone(), config.clone());
+    tokio::spawn(async move {
+        if let Err(e) = watch_private_transaction(&web3, &config, &watch, timeout).await {
+            error!(
+                "Stopped watching private relayer transaction {:#066x} {:?}",
+                watch.txid, e
+            );
+        }
+        WATCHED_SENDERS.lock().unwrap().remove(&eth_address);
+    });
+    Ok(txid)
+}
+
+/// Returns true if a privately sent transaction from `address` has not been included yet
+pub fn has_pending_private_transaction(address: EthAddress) -> bool {
+    WATCHED_SENDERS.lock().unwrap().contains(&address)
+}
+
+/// A signed relayer transaction sent to a private endpoint
+struct PrivateTransaction {
+    raw_tx: Vec<u8>,
+    txid: Uint256,
+    nonce: Uint256,
+    start_height: Uint256,
+    key: EthPrivateKey,
+}
+
+/// Submits the transaction to the private endpoint, bundles are submitted again for every
+/// block, until a receipt for it exists. After `fallback_blocks` without one it is broadcast
+/// through the public Ethereum rpc
+async fn watch_private_transaction(
+    web3: &Web3,
+    config: &PrivateSubmissionConfig,
+    tx: &PrivateTransaction,
+    timeout: Duration,
+) -> Result<(), GravityError> {
+    let eth_address = tx.key.to_address();
+    let fallback_height = tx
+        .start_height
+        .checked_add(Uint256::from_u64(config.fallback_blocks))
+        .unwrap();
+    let mut last_submitted_height = None;
+    loop {
+        let receipt: Value = HttpClient::new(&web3.get_url())
+            .request_method(
+                "eth_getTransactionReceipt",
+                json!([format!("{:#066x}", tx.txid)]),
+                timeout,
+            )
+            .await?;
+        if !receipt.is_null() {
+            info!(
+                "Private relayer transaction {:#066x} was included in block {}",
+                tx.txid, receipt["blockNumber"]
+            );
+            return Ok(());
+        }
+        // without a receipt a nonce that moved past ours means another transaction took it
+        if web3.eth_get_transaction_count(eth_address).await? > tx.nonce {
+            warn!(
+                "Private relayer transaction {:#066x} was replaced by another transaction from {}",
+                tx.txid, eth_address
+            );
+            return Ok(());
+        }
+        let current_height = web3.eth_block_number().await?;
+        if current_height >= fallback_height {
+            break;
+        }
+        // a raw transaction only needs to be sent once, bundles target a specific block
+        let should_submit = match (config.method, last_submitted_height) {
+            (_, None) => true,
+            (PrivateSubmissionMethod::Bundle, Some(height)) => height != current_height,
+            (PrivateSubmissionMethod::RawTransaction, Some(_)) => false,
+        };
+        if should_submit {
+            let target_height = current_height.checked_add(u256!(1)).unwrap();
+            match submit_private_transaction(config, &tx.raw_tx, target_height, tx.key, timeout)
+                .await
+            {
+                Ok(()) => info!(
+                    "Sent relayer transaction {:#066x} to private endpoint for block {}",
+                    tx.txid, target_height
+                ),
+                Err(e) => warn!("Private submission to {} failed with {:?}", config.url, e),
+            }
+            last_submitted_height = Some(current_height);
+        }
+        sleep(WATCH_INTERVAL).await;
+    }
+
+    warn!(
+        "Relayer transaction {:#066x} was not included within {} blocks, broadcasting publicly",
+        tx.txid, config.fallback_blocks
+    );
+    web3.eth_send_raw_transaction(tx.raw_tx.clone()).await?;
+    Ok(())
+}
+
+/// Sends a signed transaction to the private endpoint, `target_height` is the block
+/// a bundle is submitted for
+pub async fn submit_private_transaction(
+    config: &PrivateSubmissionConfig,
+    raw_tx: &[u8],
+    target_height: Uint256,
+    our_eth_key: EthPrivateKey,
+    timeout: Duration,
+) -> Result<(), GravityError> {
+    let raw_tx = format!("0x{}", bytes_to_hex_str(raw_tx));
+    match config.method {
+        PrivateSubmissionMethod::RawTransaction => {
+            HttpClient::new(&config.url)
+                .request_method::<_, Value>("eth_sendRawTransaction", json!([raw_tx]), timeout)
+                .await?;
+        }
+        PrivateSubmissionMethod::Bundle => {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_sendBundle",
+                "params": [{
+                    "txs": [raw_tx],
+                    "blockNumber": format!("{:#x}", target_height),
+                }],
+            })
+            .to_string();
+            // the signature is over the hex encoded hash of the exact request body
+            let body_hash = format!(
+                "0x{}",
+                bytes_to_hex_str(&Keccak256::digest(body.as_bytes()))
+            );
+            let signature = our_eth_key.sign_ethereum_msg(body_hash.as_bytes());
+            let header = format!(
+                "{}:0x{}",
+                our_eth_key.to_address(),
+                bytes_to_hex_str(&signature.to_bytes())
+            );
+            send_signed_bundle(&config.url, body, header, timeout).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Posts a bundle request with its signature header. web30's client can't add headers and the
+/// signature must be over the exact request body, so this request is made directly
+async fn send_signed_bundle(
+    url: &str,
+    body: String,
+    signature_header: String,
+    timeout: Duration,
+) -> Result<(), Web3Error> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(FLASHBOTS_SIGNATURE_HEADER, signature_header)
+        .body(Body::from(body))
+        .map_err(|e| Web3Error::BadInput(format!("Invalid bundle request {:?}", e)))?;
+
+    let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+    let response = match tokio::time::timeout(timeout, client.request(request)).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            return Err(Web3Error::BadResponse(format!(
+                "Bundle request to {} failed with {:?}",
+                url, e
+            )))
+        }
+        Err(_) => {
+            return Err(Web3Error::BadResponse(format!(
+                "Bundle request to {} timed out",
+                url
+            )))
+        }
+    };
+    let status = response.status();
+    let bytes = to_bytes(response.into_body())
+        .await
+        .map_err(|e| Web3Error::BadResponse(format!("Failed to read response {:?}", e)))?;
+    let response: Value = serde_json::from_slice(&bytes).map_err(|e| {
+        Web3Error::BadResponse(format!(
+            "Invalid bundle response with status {} {:?}",
+            status, e
+        ))
+    })?;
+    match response.get("error") {
+        Some(error) => Err(Web3Error::BadResponse(format!(
+            "Bundle rejected by {}: {}",
+            url, error
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+
+    use hyper::{
+        body::to_bytes,
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use serde_json::Value;
+
+    use super::*;
+
+    /// Starts a mock JSON-RPC endpoint which records every request body and
+    /// signature header it receives, returning the endpoint url
+    fn start_mock_endpoint(requests: Arc<Mutex<Vec<(Value, Option<String>)>>>) -> String {
+        let make_service = make_service_fn(move |_| {
+            let requests = requests.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let requests = requests.clone();
+                    async move {
+                        let signature = request
+                            .headers()
+                            .get(FLASHBOTS_SIGNATURE_HEADER)
+                            .map(|h| h.to_str().unwrap().to_string());
+                        let body = to_bytes(request.into_body()).await.unwrap();
+                        let body: Value = serde_json::from_slice(&body).unwrap();
+                        let result = match body["method"].as_str() {
+                            Some("eth_sendBundle") => json!({ "bundleHash": "0x01" }),
+                            _ => json!("0x02"),
+                        };
+                        requests.lock().unwrap().push((body, signature));
+                        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": result });
+                        Ok::<_, Infallible>(Response::new(Body::from(response.to_string())))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    #[tokio::test]
+    async fn test_private_submission_mock_endpoint() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let url = start_mock_endpoint(requests.clone());
+        let key = EthPrivateKey::from_slice(&[0x42; 32]).unwrap();
+        let raw_tx = vec![0xf8, 0x6b, 0x01, 0x02];
+        let timeout = Duration::from_secs(10);
+
+        let mut config = PrivateSubmissionConfig {
+            url,
+            method: PrivateSubmissionMethod::RawTransaction,
+            fallback_blocks: 5,
+        };
+        submit_private_transaction(&config, &raw_tx, u256!(10), key, timeout)
+            .await
+            .unwrap();
+        config.method = PrivateSubmissionMethod::Bundle;
+        submit_private_transaction(&config, &raw_tx, u256!(10), key, timeout)
+            .await
+            .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+
+        let (raw, raw_signature) = &requests[0];
+        assert_eq!(raw["method"], "eth_sendRawTransaction");
+        assert_eq!(raw["params"][0], "0xf86b0102");
+        assert!(raw_signature.is_none());
+
+        let (bundle, bundle_signature) = &requests[1];
+        assert_eq!(bundle["method"], "eth_sendBundle");
+        assert_eq!(bundle["params"][0]["txs"][0], "0xf86b0102");
+        assert_eq!(bundle["params"][0]["blockNumber"], "0xa");
+        let bundle_signature = bundle_signature.as_ref().unwrap();
+        assert!(bundle_signature.starts_with(&format!("{}:0x", key.to_address())));
+    }
+}
@@ -13,6 +13,8 @@ use gravity_utils::{
     web30::{client::Web3, types::SendTxOption},
 };
 
+use crate::erc20_approval::{approve_erc20_amount, get_erc20_allowance, Erc20Approval};
+
 pub const SEND_TO_COSMOS_GAS_LIMIT: Uint256 = u256!(100_000);
 
 #[allow(clippy::too_many_arguments)]
@@ -25,6 +27,7 @@ pub async fn send_to_cosmos(
     wait_timeout: Duration,
     web3: &Web3,
     options: Vec<SendTxOption>,
+    approval: Erc20Approval,
 ) -> Result<Uint256, GravityError> {
     let sender_address = sender_secret.to_address();
 
@@ -48,59 +51,99 @@ pub async fn send_to_cosmos(
     }
 
     // add nonce to options
-    let nonce = web3.eth_get_transaction_count(sender_address).await?;
+    let mut nonce = web3.eth_get_transaction_count(sender_address).await?;
     options.push(SendTxOption::Nonce(nonce));
 
     // rapidly changing gas prices can cause this to fail, a quick retry loop here
     // retries in a way that assists our transaction stress test
     let check_and_approve_erc20_transfer = async {
         loop {
-            if let Ok(approved) = web3
-                .check_erc20_approved(erc20, sender_address, gravity_contract)
-                .await
-            {
-                if !approved {
-                    info!(
-                        "Approving MAX {} from {} for gravity contract",
-                        erc20, sender_address
-                    );
-                    let txid = web3
-                        .approve_erc20_transfers(
-                            erc20,
-                            &sender_secret,
-                            gravity_contract,
-                            None,
-                            options.clone(),
-                        )
-                        .await
-                        .expect("Can't approve erc20 transfers within timeout");
-                    debug!(
-                        "We are not approved for ERC20 transfers, approving txid: {:#066x}",
-                        txid
-                    );
-                    web3.wait_for_transaction(txid, wait_timeout, None)
-                        .await
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "Can't await for transaction within timeout, txid: {:#066x}",
-                                txid
-                            )
-                        });
-                    // increment the nonce for the next call
-                    options.push(SendTxOption::Nonce(nonce.checked_add(u256!(1)).unwrap()));
-                }
-                break;
+            let allowance =
+                match get_erc20_allowance(erc20, sender_address, gravity_contract, web3).await {
+                    Ok(allowance) => allowance,
+                    Err(_) => continue,
+                };
+            if allowance >= amount {
+                return Ok(());
+            }
+            if approval == Erc20Approval::Existing {
+                return Err(GravityError::ValidationError(format!(
+                    "The existing allowance of {} for the gravity contract is less than {}",
+                    allowance, amount
+                )));
             }
+            // tokens such as USDT revert when a nonzero allowance is changed to another nonzero
+            // value, so an insufficient allowance is reset to zero first
+            if !allowance.is_zero() {
+                info!(
+                    "Resetting the allowance of {} from {} for gravity contract",
+                    erc20, sender_address
+                );
+                approve_erc20_amount(
+                    erc20,
+                    gravity_contract,
+                    u256!(0),
+                    sender_secret,
+                    wait_timeout,
+                    web3,
+                    options.clone(),
+                )
+                .await?;
+                // increment the nonce for the next call
+                nonce = nonce.checked_add(u256!(1)).unwrap();
+                options.push(SendTxOption::Nonce(nonce));
+            }
+            let txid = if approval == Erc20Approval::Max {
+                info!(
+                    "Approving MAX {} from {} for gravity contract",
+                    erc20, sender_address
+                );
+                let txid = web3
+                    .approve_erc20_transfers(
+                        erc20,
+                        &sender_secret,
+                        gravity_contract,
+                        None,
+                        options.clone(),
+                    )
+                    .await?;
+                web3.wait_for_transaction(txid, wait_timeout, None).await?;
+                txid
+            } else {
+                info!(
+                    "Approving {} {} from {} for gravity contract",
+                    amount, erc20, sender_address
+                );
+                approve_erc20_amount(
+                    erc20,
+                    gravity_contract,
+                    amount,
+                    sender_secret,
+                    wait_timeout,
+                    web3,
+                    options.clone(),
+                )
+                .await?
+            };
+            debug!(
+                "We are not approved for ERC20 transfers, approving txid: {:#066x}",
+                txid
+            );
+            // increment the nonce for the next call
+            nonce = nonce.checked_add(u256!(1)).unwrap();
+            options.push(SendTxOption::Nonce(nonce));
+            return Ok(());
         }
     };
 
-    if tokio::time::timeout(wait_timeout, check_and_approve_erc20_transfer)
-        .await
-        .is_err()
-    {
-        return Err(GravityError::UnrecoverableError(
-            "Can't check and approve erc20 transfer within timeout".into(),
-        ));
+    match tokio::time::timeout(wait_timeout, check_and_approve_erc20_transfer).await {
+        Ok(Ok(())) => (),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            return Err(GravityError::UnrecoverableError(
+                "Can't check and approve erc20 transfer within timeout".into(),
+            ))
+        }
     }
 
     info!(
@@ -134,3 +177,120 @@ pub async fn send_to_cosmos(
 
     Ok(tx_hash)
 }
+
+/// Sends many (erc20, amount, destination) transfers to Cosmos from one sender. Each ERC20 is
+/// approved once for exactly the total being sent if the existing allowance is insufficient, then
+/// every send is submitted with sequential nonces and awaited together. A send that fails to submit
+/// stops the remaining sends, since their nonces could never be included. Returns the result of
+/// each send in order, sends that were never submitted are returned as errors
+pub async fn send_to_cosmos_many(
+    gravity_contract: Address,
+    sends: &[(Address, Uint256, CosmosAddress)],
+    sender_secret: EthPrivateKey,
+    wait_timeout: Duration,
+    web3: &Web3,
+) -> Result<Vec<Result<Uint256, GravityError>>, GravityError> {
+    let sender_address = sender_secret.to_address();
+    let mut nonce = web3.eth_get_transaction_count(sender_address).await?;
+
+    let mut totals: Vec<(Address, Uint256)> = Vec::new();
+    for (erc20, amount, _) in sends {
+        match totals.iter_mut().find(|(token, _)| token == erc20) {
+            Some((_, total)) => *total = total.checked_add(*amount).unwrap(),
+            None => totals.push((*erc20, *amount)),
+        }
+    }
+    for (erc20, total) in totals {
+        let allowance = get_erc20_allowance(erc20, sender_address, gravity_contract, web3).await?;
+        if allowance >= total {
+            continue;
+        }
+        // tokens such as USDT revert when a nonzero allowance is changed to another nonzero
+        // value, so an insufficient allowance is reset to zero first
+        if !allowance.is_zero() {
+            approve_erc20_amount(
+                erc20,
+                gravity_contract,
+                u256!(0),
+                sender_secret,
+                wait_timeout,
+                web3,
+                vec![SendTxOption::Nonce(nonce)],
+            )
+            .await?;
+            nonce = nonce.checked_add(u256!(1)).unwrap();
+        }
+        info!(
+            "Approving {} {} from {} for gravity contract",
+            total, erc20, sender_address
+        );
+        approve_erc20_amount(
+            erc20,
+            gravity_contract,
+            total,
+            sender_secret,
+            wait_timeout,
+            web3,
+            vec![SendTxOption::Nonce(nonce)],
+        )
+        .await?;
+        nonce = nonce.checked_add(u256!(1)).unwrap();
+    }
+
+    let mut results = Vec::new();
+    let mut submitted = Vec::new();
+    for (erc20, amount, cosmos_destination) in sends {
+        if results.iter().any(Result::is_err) {
+            results.push(Err(GravityError::UnrecoverableError(
+                "Not sent because an earlier send failed".into(),
+            )));
+            continue;
+        }
+        info!(
+            "Sending {}{} from {} to cosmos {}",
+            amount, erc20, sender_address, cosmos_destination
+        );
+        let res = web3
+            .send_transaction(
+                gravity_contract,
+                encode_call(
+                    "sendToCosmos(address,string,uint256)",
+                    &[
+                        (*erc20).into(),
+                        Token::String(cosmos_destination.to_string()),
+                        (*amount).into(),
+                    ],
+                )?,
+                u256!(0),
+                sender_address,
+                &sender_secret,
+                vec![
+                    SendTxOption::GasLimit(SEND_TO_COSMOS_GAS_LIMIT),
+                    SendTxOption::Nonce(nonce),
+                ],
+            )
+            .await;
+        match res {
+            Ok(tx_hash) => {
+                submitted.push(tx_hash);
+                results.push(Ok(tx_hash));
+            }
+            Err(e) => results.push(Err(e.into())),
+        }
+        nonce = nonce.checked_add(u256!(1)).unwrap();
+    }
+
+    for tx_hash in submitted {
+        if let Err(e) = web3.wait_for_transaction(tx_hash, wait_timeout, None).await {
+            for res in results.iter_mut() {
+                if matches!(res, Ok(hash) if *hash == tx_hash) {
+                    *res = Err(GravityError::UnrecoverableError(format!(
+                        "Transaction {:#066x} was not included {:?}",
+                        tx_hash, e
+                    )));
+                }
+            }
+        }
+    }
+    Ok(results)
+}
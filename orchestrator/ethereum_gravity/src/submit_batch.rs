@@ -15,11 +15,16 @@ use gravity_utils::{
 
 use crate::{
     message_signatures::encode_tx_batch_confirm_hashed,
-    utils::{encode_valset_struct, get_tx_batch_nonce, GasCost},
+    private_submission::send_relayer_transaction,
+    utils::{encode_valset_struct, get_tx_batch_nonce, GasCost, RelayOutcome},
 };
 
+/// The Solidity signature of the Gravity contract's submitBatch function
+pub const SUBMIT_BATCH_SIGNATURE: &str = "submitBatch((address[],uint256[],uint256,uint256,address),(uint8,bytes32,bytes32)[],uint256[],address[],uint256[],uint256,address,uint256)";
+
 /// this function generates an appropriate Ethereum transaction
-/// to submit the provided transaction batch
+/// to submit the provided transaction batch. The batch is skipped if it was already
+/// submitted or has timed out
 #[allow(clippy::too_many_arguments)]
 pub async fn send_eth_transaction_batch(
     current_valset: &Valset,
@@ -30,7 +35,8 @@ pub async fn send_eth_transaction_batch(
     gravity_contract_address: EthAddress,
     gravity_id: String,
     our_eth_key: EthPrivateKey,
-) -> Result<(), GravityError> {
+    private_submission: Option<&PrivateSubmissionConfig>,
+) -> Result<RelayOutcome, GravityError> {
     let new_batch_nonce = batch.nonce;
     let eth_address = our_eth_key.to_address();
     info!(
@@ -52,47 +58,52 @@ pub async fn send_eth_transaction_batch(
             "Someone else updated the batch to {}, exiting early",
             before_nonce
         );
-        return Ok(());
+        return Ok(RelayOutcome::Skipped);
     } else if current_block_height > Uint256::from_u64(batch.batch_timeout) {
         info!(
             "This batch is timed out. timeout block: {} current block: {}, exiting early",
             current_block_height, batch.batch_timeout
         );
-        return Ok(());
+        return Ok(RelayOutcome::Skipped);
     }
 
     let payload = encode_batch_payload(current_valset, &batch, confirms, gravity_id)?;
 
-    let tx = web3
-        .send_transaction(
-            gravity_contract_address,
-            payload,
-            u256!(0),
-            eth_address,
-            &our_eth_key,
-            vec![SendTxOption::GasPriceMultiplier(1.10f32)],
-        )
-        .await?;
+    let tx = send_relayer_transaction(
+        web3,
+        gravity_contract_address,
+        payload,
+        our_eth_key,
+        vec![SendTxOption::GasPriceMultiplier(1.10f32)],
+        private_submission,
+        timeout,
+    )
+    .await?;
     info!("Sent batch update with txid {:#066x}", tx);
 
-    web3.wait_for_transaction(tx, timeout, None).await?;
+    // the transaction has been sent, so errors from here on are logged rather than returned
+    // and the relay is reported as sent but unconfirmed
+    if let Err(e) = web3.wait_for_transaction(tx, timeout, None).await {
+        error!("Failed waiting for batch update {:#066x} {:?}", tx, e);
+        return Ok(RelayOutcome::Sent);
+    }
 
-    let last_nonce = get_tx_batch_nonce(
+    match get_tx_batch_nonce(
         gravity_contract_address,
         batch.token_contract,
         eth_address,
         web3,
     )
-    .await?;
-    if last_nonce != new_batch_nonce {
-        error!(
+    .await
+    {
+        Ok(last_nonce) if last_nonce != new_batch_nonce => error!(
             "Current nonce is {} expected to update to nonce {}",
             last_nonce, new_batch_nonce
-        );
-    } else {
-        info!("Successfully updated Batch with new Nonce {:?}", last_nonce);
+        ),
+        Ok(last_nonce) => info!("Successfully updated Batch with new Nonce {:?}", last_nonce),
+        Err(e) => warn!("Failed to get the batch nonce after the update {:?}", e),
     }
-    Ok(())
+    Ok(RelayOutcome::Confirmed)
 }
 
 /// Returns the cost in Eth of sending this batch
@@ -172,8 +183,7 @@ fn encode_batch_payload(
         batch.token_contract.into(),
         batch.batch_timeout.into(),
     ];
-    let payload = encode_call("submitBatch((address[],uint256[],uint256,uint256,address),(uint8,bytes32,bytes32)[],uint256[],address[],uint256[],uint256,address,uint256)",
-    tokens).unwrap();
+    let payload = encode_call(SUBMIT_BATCH_SIGNATURE, tokens).unwrap();
     trace!("Tokens {:?}", tokens);
 
     Ok(payload)
@@ -151,6 +151,18 @@ impl GasCost {
     }
 }
 
+/// The result of submitting a relayer transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayOutcome {
+    /// Nothing was sent because the relay was already done or has timed out
+    Skipped,
+    /// A transaction was sent but it was not seen in a block before the timeout, it may
+    /// still be included later or it may have been dropped
+    Sent,
+    /// A transaction was sent and has been included in a block
+    Confirmed,
+}
+
 /// This encodes the solidity struct ValsetArgs from the Gravity
 /// contract useful for all three major contract calls
 /// struct ValsetArgs {
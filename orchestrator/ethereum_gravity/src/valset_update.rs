@@ -15,11 +15,16 @@ use gravity_utils::{
 
 use crate::{
     message_signatures::encode_valset_confirm_hashed,
-    utils::{encode_valset_struct, get_valset_nonce, GasCost},
+    private_submission::send_relayer_transaction,
+    utils::{encode_valset_struct, get_valset_nonce, GasCost, RelayOutcome},
 };
 
+/// The Solidity signature of the Gravity contract's updateValset function
+pub const UPDATE_VALSET_SIGNATURE: &str = "updateValset((address[],uint256[],uint256,uint256,address),(address[],uint256[],uint256,uint256,address),(uint8,bytes32,bytes32)[])";
+
 /// this function generates an appropriate Ethereum transaction
-/// to submit the provided validator set and signatures.
+/// to submit the provided validator set and signatures. The update is skipped if
+/// someone else already updated the valset
 #[allow(clippy::too_many_arguments)]
 pub async fn send_eth_valset_update(
     new_valset: &Valset,
@@ -30,7 +35,8 @@ pub async fn send_eth_valset_update(
     gravity_contract_address: EthAddress,
     gravity_id: String,
     our_eth_key: EthPrivateKey,
-) -> Result<(), GravityError> {
+    private_submission: Option<&PrivateSubmissionConfig>,
+) -> Result<RelayOutcome, GravityError> {
     let old_nonce = old_valset.nonce;
     let new_nonce = new_valset.nonce;
     assert!(new_nonce > old_nonce);
@@ -45,38 +51,42 @@ pub async fn send_eth_valset_update(
             "Someone else updated the valset to {}, exiting early",
             before_nonce
         );
-        return Ok(());
+        return Ok(RelayOutcome::Skipped);
     }
 
     let payload = encode_valset_update_payload(new_valset, old_valset, confirms, gravity_id)?;
 
-    let tx = web3
-        .send_transaction(
-            gravity_contract_address,
-            payload,
-            u256!(0),
-            eth_address,
-            &our_eth_key,
-            vec![SendTxOption::GasPriceMultiplier(1.10f32)],
-        )
-        .await?;
+    let tx = send_relayer_transaction(
+        web3,
+        gravity_contract_address,
+        payload,
+        our_eth_key,
+        vec![SendTxOption::GasPriceMultiplier(1.10f32)],
+        private_submission,
+        timeout,
+    )
+    .await?;
     info!("Sent valset update with txid {:#066x}", tx);
 
-    web3.wait_for_transaction(tx, timeout, None).await?;
+    // the transaction has been sent, so errors from here on are logged rather than returned
+    // and the relay is reported as sent but unconfirmed
+    if let Err(e) = web3.wait_for_transaction(tx, timeout, None).await {
+        error!("Failed waiting for valset update {:#066x} {:?}", tx, e);
+        return Ok(RelayOutcome::Sent);
+    }
 
-    let last_nonce = get_valset_nonce(gravity_contract_address, eth_address, web3).await?;
-    if last_nonce != new_nonce {
-        error!(
+    match get_valset_nonce(gravity_contract_address, eth_address, web3).await {
+        Ok(last_nonce) if last_nonce != new_nonce => error!(
             "Current nonce is {} expected to update to nonce {}",
             last_nonce, new_nonce
-        );
-    } else {
-        info!(
+        ),
+        Ok(last_nonce) => info!(
             "Successfully updated Valset with new Nonce {:?}",
             last_nonce
-        );
+        ),
+        Err(e) => warn!("Failed to get the valset nonce after the update {:?}", e),
     }
-    Ok(())
+    Ok(RelayOutcome::Confirmed)
 }
 
 /// Returns the cost in Eth of sending this valset update
@@ -153,8 +163,7 @@ pub fn encode_valset_update_payload(
     // // These are arrays of the parts of the current validator's signatures
     // Signature[] _sigs,
     let tokens = &[new_valset_token, old_valset_token, sig_arrays.sigs];
-    let payload = encode_call("updateValset((address[],uint256[],uint256,uint256,address),(address[],uint256[],uint256,uint256,address),(uint8,bytes32,bytes32)[])",
-    tokens).unwrap();
+    let payload = encode_call(UPDATE_VALSET_SIGNATURE, tokens).unwrap();
 
     Ok(payload)
 }
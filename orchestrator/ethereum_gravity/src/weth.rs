@@ -0,0 +1,57 @@
+//! Helper functions for wrapping ETH into WETH so that it can be sent to Cosmos, and for
+//! unwrapping WETH that has been sent back to Ethereum
+
+use std::time::Duration;
+
+use gravity_utils::{
+    clarity::{abi::encode_call, u256, PrivateKey as EthPrivateKey, Uint256},
+    error::GravityError,
+    u64_array_bigints,
+    web30::{amm::WETH_CONTRACT_ADDRESS, client::Web3, types::SendTxOption},
+};
+
+/// Wraps `amount` of ETH into WETH using the WETH contract's `deposit()`, waiting for the
+/// transaction to be included. Returns the txid of the deposit
+pub async fn wrap_eth(
+    amount: Uint256,
+    sender_secret: EthPrivateKey,
+    wait_timeout: Duration,
+    web3: &Web3,
+    options: Vec<SendTxOption>,
+) -> Result<Uint256, GravityError> {
+    let txid = web3
+        .send_transaction(
+            *WETH_CONTRACT_ADDRESS,
+            encode_call("deposit()", &[])?,
+            amount,
+            sender_secret.to_address(),
+            &sender_secret,
+            options,
+        )
+        .await?;
+    web3.wait_for_transaction(txid, wait_timeout, None).await?;
+    Ok(txid)
+}
+
+/// Unwraps `amount` of WETH back into ETH using the WETH contract's `withdraw(uint256)`, waiting
+/// for the transaction to be included. Returns the txid of the withdrawal
+pub async fn unwrap_weth(
+    amount: Uint256,
+    sender_secret: EthPrivateKey,
+    wait_timeout: Duration,
+    web3: &Web3,
+    options: Vec<SendTxOption>,
+) -> Result<Uint256, GravityError> {
+    let txid = web3
+        .send_transaction(
+            *WETH_CONTRACT_ADDRESS,
+            encode_call("withdraw(uint256)", &[amount.into()])?,
+            u256!(0),
+            sender_secret.to_address(),
+            &sender_secret,
+            options,
+        )
+        .await?;
+    web3.wait_for_transaction(txid, wait_timeout, None).await?;
+    Ok(txid)
+}
@@ -3,10 +3,11 @@
 
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{ArgEnum, Parser};
 use gravity_utils::{
     clarity::{Address as EthAddress, PrivateKey as EthPrivateKey},
     deep_space::{address::Address as CosmosAddress, Coin, PrivateKey as CosmosPrivateKey},
+    num_conversion::TokenAmount,
 };
 
 /// Gravity Bridge tools (gbt) provides tools for interacting with the Onomy Gravity bridge for Cosmos based blockchains.
@@ -21,11 +22,12 @@ pub struct Opts {
     pub quiet: bool,
     /// The home directory for Gravity Bridge Tools, by default
     /// $HOME/.althea_gbt/
-    #[clap(short, long, parse(from_str))]
+    #[clap(short, long, parse(from_str), env = "GBT_HOME")]
     pub home: Option<PathBuf>,
-    /// Set the address prefix for the Cosmos chain
-    #[clap(short, long, default_value = "gravity")]
-    pub address_prefix: String,
+    /// Set the address prefix for the Cosmos chain, by default `address_prefix` in the
+    /// [connection] section of the config or gravity
+    #[clap(short, long, env = "GBT_ADDRESS_PREFIX")]
+    pub address_prefix: Option<String>,
     #[clap(subcommand)]
     pub subcmd: SubCommand,
 }
@@ -35,60 +37,132 @@ pub enum SubCommand {
     Orchestrator(OrchestratorOpts),
     Relayer(RelayerOpts),
     Client(ClientOpts),
+    Query(QueryOpts),
     Gov(GovOpts),
     Keys(KeyOpts),
+    Tx(TxOpts),
     Init(InitOpts),
+    Config(ConfigOpts),
 }
 
 /// The Gravity Bridge orchestrator is required for all validators of the Cosmos chain running
-/// the Gravity Bridge module. It contains an Ethereum Signer, Oracle, and optional relayer
+/// the Gravity Bridge module. It contains an Ethereum Signer, Oracle, and optional relayer.
+/// Every option can also be set in the config, flags take precedence over GBT_* environment
+/// variables which take precedence over the config. Without --ethereum-key or
+/// --ethereum-mnemonic, or GBT_ETHEREUM_KEY or GBT_ETHEREUM_MNEMONIC, the Ethereum key is read
+/// from `ethereum_key_file` in the [keys] section of the config
 #[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
 pub struct OrchestratorOpts {
-    /// Cosmos mnemonic phrase containing the tokens you would like to send
-    #[clap(short, long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
-    /// An Ethereum private key containing ETH to pay for fees, this will also hold the relayers earnings
-    /// in the near future it will be possible to disable the Orchestrators integrated relayer
-    #[clap(short, long, parse(try_from_str))]
-    pub ethereum_key: EthPrivateKey,
-    /// (Optional) The Cosmos gRPC server that will be used
-    #[clap(long, default_value = "http://localhost:9090")]
-    pub cosmos_grpc: String,
-    /// (Optional) The Ethereum RPC server that will be used
-    #[clap(long, default_value = "http://localhost:8545")]
-    pub ethereum_rpc: String,
+    #[clap(flatten)]
+    pub setup: OrchestratorSetupOpts,
+    #[clap(subcommand)]
+    pub subcmd: Option<OrchestratorSubcommand>,
+}
+
+#[derive(Parser)]
+pub enum OrchestratorSubcommand {
+    Check(OrchestratorCheckOpts),
+}
+
+/// Check everything the orchestrator needs and exit, without starting the orchestrator. Every
+/// check is reported as PASS, WARN or FAIL and the command fails if any check fails
+#[derive(Parser)]
+pub struct OrchestratorCheckOpts {
+    #[clap(flatten)]
+    pub setup: OrchestratorSetupOpts,
+    /// (Optional) Also fail if any check warns
+    #[clap(long)]
+    pub strict: bool,
+    #[clap(flatten)]
+    pub thresholds: PreflightThresholds,
+}
+
+/// The balances below which the preflight check warns. The defaults are rough estimates, tune
+/// them to the activity of the bridge and the gas prices seen on Ethereum
+#[derive(Parser, Clone, Copy)]
+pub struct PreflightThresholds {
+    /// (Optional) The number of transactions the orchestrator is expected to submit in a day, one
+    /// for every event observed on Ethereum and every valset, batch and logic call it signs
+    #[clap(long, default_value = "200")]
+    pub daily_transactions: u64,
+    /// (Optional) Warn if the Cosmos balance pays fees for fewer than this many days
+    #[clap(long, default_value = "30")]
+    pub min_fee_days: u64,
+    /// (Optional) The gas used to relay a valset update, the most common relay of an integrated
+    /// relayer
+    #[clap(long, default_value = "500000")]
+    pub valset_relay_gas: u64,
+    /// (Optional) Warn if the Ethereum balance of an integrated relayer pays for fewer than this
+    /// many valset relays at the current gas price
+    #[clap(long, default_value = "10")]
+    pub min_relays: u64,
+}
+
+/// The keys and endpoints of the orchestrator
+#[derive(Parser)]
+pub struct OrchestratorSetupOpts {
+    /// Cosmos mnemonic phrase containing the tokens you would like to send, by default read from
+    /// `cosmos_phrase_file` in the [keys] section of the config
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        env = "GBT_COSMOS_PHRASE",
+        hide_env_values = true
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
+    #[clap(flatten)]
+    pub ethereum: EthereumKeyOpts,
+    /// (Optional) The Cosmos gRPC server that will be used, by default http://localhost:9090
+    #[clap(long, env = "GBT_COSMOS_GRPC")]
+    pub cosmos_grpc: Option<String>,
+    /// (Optional) The Ethereum RPC server that will be used, by default http://localhost:8545
+    #[clap(long, env = "GBT_ETHEREUM_RPC")]
+    pub ethereum_rpc: Option<String>,
     /// The Cosmos Denom and amount to pay Cosmos chain fees
-    #[clap(short, long, parse(try_from_str))]
-    pub fees: Coin,
+    #[clap(short, long, parse(try_from_str), env = "GBT_FEES")]
+    pub fees: Option<Coin>,
     /// The address fo the Gravity contract on Ethereum
-    #[clap(short, long, parse(try_from_str))]
+    #[clap(short, long, parse(try_from_str), env = "GBT_GRAVITY_CONTRACT_ADDRESS")]
     pub gravity_contract_address: Option<EthAddress>,
 }
 
 /// The Gravity Bridge Relayer is an unpermissioned role that takes data from the Cosmos blockchain
 /// packages it into Ethereum transactions and is paid to submit these transactions to the Ethereum blockchain
-/// The relayer will attempt to only relay profitable transactions, but there is no guarantee that it will succeed
+/// The relayer will attempt to only relay profitable transactions, but there is no guarantee that it will succeed.
+/// Every option can also be set in the config, flags take precedence over GBT_* environment
+/// variables which take precedence over the config. The Ethereum keys pay for fees, every
+/// --ethereum-key and every key derived at an --hd-path is added to a pool of keys. Each
+/// submission is signed by a key with enough ETH and no pending transactions, the relayers
+/// earnings are paid to the key that submitted the transaction. Without any keys given they are
+/// read from `ethereum_key_file` in the [keys] section of the config
 #[derive(Parser)]
 pub struct RelayerOpts {
-    /// An Ethereum private key containing ETH to pay for fees, this will also hold the relayers earnings
-    #[clap(short, long, parse(try_from_str))]
-    pub ethereum_key: EthPrivateKey,
-    /// Cosmos mnemonic phrase containing tokens used to pay fees on Cosmos for requesting batches
-    #[clap(long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
+    #[clap(flatten)]
+    pub ethereum: EthereumKeyOpts,
+    /// Cosmos mnemonic phrase containing tokens used to pay fees on Cosmos for requesting batches, by
+    /// default read from `cosmos_phrase_file` in the [keys] section of the config
+    #[clap(
+        long,
+        parse(try_from_str),
+        env = "GBT_COSMOS_PHRASE",
+        hide_env_values = true
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
     /// (Optional) The Cosmos Denom and amount to pay Cosmos chain fees. If not set this relayer will not automatically
     /// request batches
-    #[clap(short, long, parse(try_from_str))]
+    #[clap(short, long, parse(try_from_str), env = "GBT_FEES")]
     pub fees: Option<Coin>,
     /// The address fo the Gravity contract on Ethereum
-    #[clap(short, long, parse(try_from_str))]
+    #[clap(short, long, parse(try_from_str), env = "GBT_GRAVITY_CONTRACT_ADDRESS")]
     pub gravity_contract_address: Option<EthAddress>,
-    /// (Optional) The Ethereum RPC server that will be used
-    #[clap(long, default_value = "http://localhost:8545")]
-    pub ethereum_rpc: String,
-    /// (Optional) The Cosmos gRPC server that will be used to
-    #[clap(short, long, default_value = "http://localhost:9090")]
-    pub cosmos_grpc: String,
+    /// (Optional) The Ethereum RPC server that will be used, by default http://localhost:8545
+    #[clap(long, env = "GBT_ETHEREUM_RPC")]
+    pub ethereum_rpc: Option<String>,
+    /// (Optional) The Cosmos gRPC server that will be used, by default http://localhost:9090
+    #[clap(short, long, env = "GBT_COSMOS_GRPC")]
+    pub cosmos_grpc: Option<String>,
 }
 
 /// The Gravity Bridge client contains helpful command line tools for interacting with the Gravity bridge
@@ -103,53 +177,103 @@ pub enum ClientSubcommand {
     CosmosToEth(CosmosToEthOpts),
     EthToCosmos(EthToCosmosOpts),
     DeployErc20Representation(DeployErc20RepresentationOpts),
+    CancelSendToEth(CancelSendToEthOpts),
+    RequestBatch(RequestBatchOpts),
+    BumpFee(BumpFeeOpts),
+    Track(TrackOpts),
+    Bulk(BulkOpts),
 }
 
 /// Send Cosmos tokens to Ethereum
 #[derive(Parser)]
 pub struct CosmosToEthOpts {
     /// Cosmos mnemonic phrase containing the tokens you would like to send
-    #[clap(short, long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
     /// (Optional) The Cosmos gRPC server that will be used to submit the transaction
     #[clap(long, default_value = "http://localhost:9090")]
     pub cosmos_grpc: String,
-    /// The Denom and amount you wish to send eg: 1000000000anom
+    /// The amount you wish to send eg. 1.2, this is converted exactly using the decimals of the
+    /// denom metadata, or of the ERC20 for Ethereum originated tokens which have no metadata
     #[clap(short, long, parse(try_from_str))]
-    pub amount: Coin,
+    pub amount: TokenAmount,
+    /// The Cosmos Denom you wish to send eg: anom
+    #[clap(short, long)]
+    pub denom: String,
     /// The Cosmos Denom and amount to pay Cosmos chain fees eg: 1000000000anom
     #[clap(short, long, parse(try_from_str))]
     pub fee: Coin,
-    /// The amount you want to pay in bridge fees, these are used to pay relayers
-    /// on Ethereum and must be of the same denomination as `amount`
+    /// The amount you want to pay in bridge fees eg. 0.1, these are used to pay relayers
+    /// on Ethereum and are paid in `denom`
     #[clap(short, long, parse(try_from_str))]
-    pub bridge_fee: Coin,
+    pub bridge_fee: TokenAmount,
     /// The destination address on the Ethereum chain
     #[clap(short, long, parse(try_from_str))]
     pub eth_destination: EthAddress,
+    /// (Optional) When sending WETH, wait for it to arrive on Ethereum and unwrap it into ETH
+    /// using this key, which must be the key of the destination address
+    #[clap(long, parse(try_from_str), conflicts_with = "generate-only")]
+    pub unwrap_with_key: Option<EthPrivateKey>,
+    /// (Optional) The Ethereum RPC server that will be used to read the decimals of Ethereum
+    /// originated tokens and to unwrap WETH
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub ethereum_rpc: String,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
 }
 
 /// Send an Ethereum ERC20 token to Cosmos
 #[derive(Parser)]
 pub struct EthToCosmosOpts {
-    /// The Ethereum private key to use for sending tokens
-    #[clap(long, parse(try_from_str))]
-    pub ethereum_key: EthPrivateKey,
+    #[clap(flatten)]
+    pub ethereum: EthereumKeyOpts,
     /// (Optional) The Ethereum RPC server that will be used to submit the transaction
     #[clap(long, default_value = "http://localhost:8545")]
     pub ethereum_rpc: String,
+    /// (Optional) Write the unsigned Ethereum transactions to this file instead of signing and
+    /// submitting them, so that they can be signed by an external signer
+    #[clap(long, parse(try_from_str), requires = "from")]
+    pub generate_only: Option<PathBuf>,
+    /// The address that will sign the transactions written with --generate-only
+    #[clap(long, parse(try_from_str))]
+    pub from: Option<EthAddress>,
     /// The address fo the Gravity contract on Ethereum
     #[clap(short, long, parse(try_from_str))]
     pub gravity_contract_address: EthAddress,
     /// The ERC20 contract address of the ERC20 you are sending
+    #[clap(short, long, parse(try_from_str), required_unless_present = "eth")]
+    pub token_contract_address: Option<EthAddress>,
+    /// Send ETH by first wrapping it into WETH, instead of sending an ERC20
+    #[clap(long, conflicts_with = "token-contract-address")]
+    pub eth: bool,
+    /// The amount of tokens you are sending eg. 1.2, this is converted exactly
+    /// using the decimals of the ERC20
     #[clap(short, long, parse(try_from_str))]
-    pub token_contract_address: EthAddress,
-    /// The amount of tokens you are sending eg. 1.2
-    #[clap(short, long, parse(try_from_str))]
-    pub amount: f64,
+    pub amount: TokenAmount,
     /// The destination address on the Cosmos blockchain
     #[clap(short, long, parse(try_from_str))]
     pub destination: CosmosAddress,
+    /// (Optional) How to approve the Gravity contract to transfer your tokens if the current
+    /// allowance is insufficient. `max` approves an unlimited amount, `exact` approves only the
+    /// amount being sent and `existing` never approves. EIP-2612 permits are not supported since
+    /// the Gravity contract can't take one in the same transaction as the send
+    #[clap(long, arg_enum, default_value = "max")]
+    pub approve: ApprovalMode,
+    /// (Optional) Revoke the allowance of the Gravity contract once the tokens have been sent
+    #[clap(long)]
+    pub revoke: bool,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApprovalMode {
+    Max,
+    Exact,
+    Existing,
 }
 
 /// Deploy an ERC20 representation of a Cosmos asset on the Ethereum chain
@@ -165,12 +289,287 @@ pub struct DeployErc20RepresentationOpts {
     /// The Cosmos Denom you wish to create an ERC20 representation for
     #[clap(short, long)]
     pub cosmos_denom: String,
-    /// An Ethereum private key, containing enough ETH to pay for the transaction
+    #[clap(flatten)]
+    pub ethereum: EthereumKeyOpts,
+    /// The address fo the Gravity contract on Ethereum
+    #[clap(short, long, parse(try_from_str))]
+    pub gravity_contract_address: Option<EthAddress>,
+}
+
+/// Cancel a send to Ethereum that has not yet been included in a batch, returning the tokens
+/// and fees. Run without --transaction-id or --all to list your pending sends
+#[derive(Parser)]
+pub struct CancelSendToEthOpts {
+    /// Cosmos mnemonic phrase of the address that made the sends
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
+    /// (Optional) The Cosmos gRPC server that will be used to submit the transaction
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// The id of the send to cancel
+    #[clap(short, long, conflicts_with = "all")]
+    pub transaction_id: Option<u64>,
+    /// Cancel all of your sends that have not yet been included in a batch
+    #[clap(long)]
+    pub all: bool,
+    /// The Cosmos Denom and amount to pay Cosmos chain fees for each cancel eg: 1000000000anom,
+    /// required when cancelling
+    #[clap(short, long, parse(try_from_str))]
+    pub fee: Option<Coin>,
+    /// (Optional) The Ethereum RPC server used to read token decimals when listing sends,
+    /// amounts are shown in base units if it can't be reached
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub ethereum_rpc: String,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
+}
+
+/// Request a batch of the sends to Ethereum that are waiting for a token, relayers will only
+/// relay a batch once it has been requested and signed by the validators
+#[derive(Parser)]
+pub struct RequestBatchOpts {
+    /// Cosmos mnemonic phrase containing tokens to pay the fees
+    #[clap(short, long, parse(try_from_str))]
+    pub cosmos_phrase: CosmosPrivateKey,
+    /// (Optional) The Cosmos gRPC server that will be used to submit the transaction
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// The Cosmos Denom of the token to request a batch for
+    #[clap(short, long)]
+    pub denom: String,
+    /// The Cosmos Denom and amount to pay Cosmos chain fees eg: 1000000000anom
+    #[clap(short, long, parse(try_from_str))]
+    pub fee: Coin,
+    /// (Optional) The Ethereum RPC server used to read the decimals of the token, fees are
+    /// shown in base units if it can't be reached
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub ethereum_rpc: String,
+}
+
+/// Replace a send to Ethereum that has not yet been included in a batch with the same send paying
+/// a higher bridge fee. The cancel and the new send are made in a single transaction
+#[derive(Parser)]
+pub struct BumpFeeOpts {
+    /// Cosmos mnemonic phrase of the address that made the send
+    #[clap(short, long, parse(try_from_str))]
+    pub cosmos_phrase: CosmosPrivateKey,
+    /// (Optional) The Cosmos gRPC server that will be used to submit the transaction
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// The id of the send to replace, use `gbt client cancel-send-to-eth` to list your sends
+    #[clap(short, long)]
+    pub transaction_id: u64,
+    /// The new bridge fee, this must be of the same denomination as the send
+    #[clap(short, long, parse(try_from_str))]
+    pub bridge_fee: Coin,
+    /// The Cosmos Denom and amount to pay Cosmos chain fees eg: 1000000000anom
     #[clap(short, long, parse(try_from_str))]
-    pub ethereum_key: EthPrivateKey,
+    pub fee: Coin,
+    /// (Optional) The Ethereum RPC server used to read the decimals of the token and to price
+    /// the fees of each token in WETH, which is how they are ranked
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub ethereum_rpc: String,
+}
+
+/// Follow a transfer across the bridge. Track a send to Cosmos using the hash of the Ethereum
+/// transaction, or a send to Ethereum using its id and sender address
+#[derive(Parser)]
+pub struct TrackOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to perform the queries
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// (Optional) The Ethereum RPC server that will be used to perform the queries
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub ethereum_rpc: String,
     /// The address fo the Gravity contract on Ethereum
     #[clap(short, long, parse(try_from_str))]
     pub gravity_contract_address: Option<EthAddress>,
+    /// The hash of an Ethereum transaction sending tokens to Cosmos
+    #[clap(
+        short,
+        long,
+        conflicts_with = "send-id",
+        required_unless_present = "send-id"
+    )]
+    pub eth_tx: Option<String>,
+    /// The id of a send to Ethereum, as shown by `gbt client cancel-send-to-eth`
+    #[clap(long, requires = "sender")]
+    pub send_id: Option<u64>,
+    /// The Cosmos address that made the send to Ethereum
+    #[clap(long, parse(try_from_str))]
+    pub sender: Option<CosmosAddress>,
+}
+
+/// Send tokens to many destinations from a CSV file with the columns destination, amount,
+/// denom or token, and an optional bridge fee. Rows with a Cosmos destination send an ERC20 to
+/// Cosmos and rows with an Ethereum destination send a Cosmos denom to Ethereum. Amounts are
+/// decimal amounts of the token, eg. 1.2
+#[derive(Parser)]
+pub struct BulkOpts {
+    /// The CSV file of transfers to make
+    #[clap(long, parse(try_from_str))]
+    pub csv: PathBuf,
+    /// (Optional) Where to write the results, by default the CSV file name with `.results.csv`
+    #[clap(long, parse(try_from_str))]
+    pub results: Option<PathBuf>,
+    /// The Cosmos mnemonic phrase to send from, required to send to Ethereum
+    #[clap(long, parse(try_from_str))]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
+    #[clap(flatten)]
+    pub ethereum: EthereumKeyOpts,
+    /// (Optional) The Cosmos gRPC server that will be used to submit the transactions
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// (Optional) The Ethereum RPC server that will be used to submit the transactions and to read
+    /// the decimals of Ethereum originated denoms
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub ethereum_rpc: String,
+    /// (Optional) The address of the Gravity contract on Ethereum, by default the one set as a
+    /// chain parameter
+    #[clap(short, long, parse(try_from_str))]
+    pub gravity_contract_address: Option<EthAddress>,
+    /// The Cosmos Denom and amount to pay Cosmos chain fees for each transaction, required to send
+    /// to Ethereum
+    #[clap(short, long, parse(try_from_str))]
+    pub fee: Option<Coin>,
+    /// (Optional) A hard cap on the estimated gas of each Cosmos transaction. Sends to Ethereum
+    /// are packed assuming each uses at most 100000 gas, the transactions are not simulated, so
+    /// lower this if a packed transaction runs out of gas
+    #[clap(long, default_value = "2000000")]
+    pub max_tx_gas: u64,
+}
+
+/// Query the state of the Gravity Bridge module
+#[derive(Parser)]
+pub struct QueryOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to perform the query
+    #[clap(long, default_value = "http://localhost:9090", global = true)]
+    pub cosmos_grpc: String,
+    /// (Optional) The Ethereum RPC server used to read token decimals, so that amounts in text
+    /// output are shown with decimals. Amounts are shown in base units if it can't be reached
+    #[clap(long, default_value = "http://localhost:8545", global = true)]
+    pub ethereum_rpc: String,
+    /// (Optional) The output format, either human readable text or json
+    #[clap(short, long, arg_enum, default_value = "text", global = true)]
+    pub output: OutputFormat,
+    #[clap(subcommand)]
+    pub subcmd: QuerySubcommand,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Parser)]
+pub enum QuerySubcommand {
+    /// The Gravity module parameters
+    Params,
+    /// The current validator set, this is not necessarily a validator set that has been requested
+    CurrentValset,
+    /// The validator set requested at the given nonce
+    Valset(NonceQueryOpts),
+    /// The most recently requested validator sets
+    Valsets,
+    /// The orchestrator signatures for the validator set at the given nonce
+    ValsetConfirms(NonceQueryOpts),
+    /// The latest outgoing transaction batches
+    Batches,
+    /// The transaction batch for the given nonce and token
+    Batch(BatchQueryOpts),
+    /// The orchestrator signatures for the transaction batch for the given nonce and token
+    BatchConfirms(BatchQueryOpts),
+    /// The total fees of the transactions waiting to be batched for each token
+    BatchFees,
+    /// The latest outgoing logic calls
+    LogicCalls,
+    /// The orchestrator signatures for the logic call with the given invalidation id and nonce
+    LogicCallConfirms(LogicCallQueryOpts),
+    /// The latest attestations to Ethereum events
+    Attestations(AttestationsQueryOpts),
+    /// Transfers to Ethereum from a Cosmos address that have not yet been executed on Ethereum
+    PendingSendToEth(AddressQueryOpts),
+    /// The last Ethereum event nonce an orchestrator has attested to
+    LastEventNonce(AddressQueryOpts),
+    /// The ERC20 representing a Cosmos denom on Ethereum
+    DenomToErc20(DenomToErc20QueryOpts),
+    /// The Cosmos denom representing an ERC20 token
+    Erc20ToDenom(Erc20ToDenomQueryOpts),
+    /// The delegate keys registered by a validator, lookup using any one of the three keys
+    DelegateKeys(DelegateKeysQueryOpts),
+}
+
+#[derive(Parser)]
+pub struct NonceQueryOpts {
+    /// The nonce to query
+    #[clap(short, long)]
+    pub nonce: u64,
+}
+
+#[derive(Parser)]
+pub struct BatchQueryOpts {
+    /// The batch nonce
+    #[clap(short, long)]
+    pub nonce: u64,
+    /// The ERC20 contract address of the batched token
+    #[clap(short, long, parse(try_from_str))]
+    pub token_contract_address: EthAddress,
+}
+
+#[derive(Parser)]
+pub struct LogicCallQueryOpts {
+    /// The hex encoded invalidation id of the logic call
+    #[clap(short, long)]
+    pub invalidation_id: String,
+    /// The invalidation nonce of the logic call
+    #[clap(short, long)]
+    pub nonce: u64,
+}
+
+#[derive(Parser)]
+pub struct AttestationsQueryOpts {
+    /// (Optional) The maximum number of attestations to return
+    #[clap(short, long)]
+    pub limit: Option<u64>,
+}
+
+#[derive(Parser)]
+pub struct AddressQueryOpts {
+    /// The Cosmos address to query
+    #[clap(parse(try_from_str))]
+    pub address: CosmosAddress,
+}
+
+#[derive(Parser)]
+pub struct DenomToErc20QueryOpts {
+    /// The Cosmos denom to query
+    pub denom: String,
+}
+
+#[derive(Parser)]
+pub struct Erc20ToDenomQueryOpts {
+    /// The ERC20 contract address to query
+    #[clap(parse(try_from_str))]
+    pub erc20: EthAddress,
+}
+
+#[derive(Parser)]
+pub struct DelegateKeysQueryOpts {
+    /// The validator operator address
+    #[clap(long, conflicts_with_all = &["eth-address", "orchestrator-address"], required_unless_present_any = &["eth-address", "orchestrator-address"])]
+    pub validator_address: Option<String>,
+    /// The delegate Ethereum address
+    #[clap(long, parse(try_from_str), conflicts_with = "orchestrator-address")]
+    pub eth_address: Option<EthAddress>,
+    /// The delegate Orchestrator address
+    #[clap(long, parse(try_from_str))]
+    pub orchestrator_address: Option<CosmosAddress>,
 }
 
 /// Manage keys
@@ -182,7 +581,44 @@ pub struct KeyOpts {
 
 #[derive(Parser)]
 pub enum KeysSubcommand {
+    Generate(KeysGenerateOpts),
     RegisterOrchestratorAddress(RegisterOrchestratorAddressOpts),
+    Rotate(RotateKeysOpts),
+}
+
+/// Generate a new mnemonic and derive a Cosmos delegate key and an Ethereum key from it, the
+/// printed mnemonic must be written down since it is the only way to recover both keys
+#[derive(Parser)]
+pub struct KeysGenerateOpts {
+    /// (Optional) The coin type of the Cosmos key, the key is derived at m/44'/<coin type>'/0'/0/0.
+    /// Note that --cosmos-phrase always derives keys with coin type 118
+    #[clap(long, default_value = "118")]
+    pub coin_type: u32,
+    /// (Optional) The index of the Ethereum key, the key is derived at m/44'/60'/0'/0/<index>
+    #[clap(long, default_value = "0")]
+    pub ethereum_index: u32,
+    /// (Optional) Write the mnemonic to `cosmos_phrase_file` and the Ethereum private key to
+    /// `ethereum_key_file` from the [keys] section of the config. The files must not exist yet and
+    /// are created readable only by their owner
+    #[clap(long)]
+    pub store: bool,
+}
+
+/// Check a validator's delegate keys before a rotation. The Gravity module does not allow
+/// registered delegate keys to be replaced, so this only reports the current registration and the
+/// valsets, batches and logic calls its keys have not signed
+#[derive(Parser)]
+pub struct RotateKeysOpts {
+    /// The validator operator address, eg. gravityvaloper1...
+    #[clap(long)]
+    pub validator_address: String,
+    /// Only report the current registration and the signatures it owes, rotation itself is
+    /// rejected by the Gravity module
+    #[clap(long)]
+    pub check: bool,
+    /// (Optional) The Cosmos gRPC server that will be used to query the registration
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
 }
 
 /// Register delegate keys for the Gravity Orchestrator.
@@ -191,11 +627,15 @@ pub enum KeysSubcommand {
 #[derive(Parser)]
 pub struct RegisterOrchestratorAddressOpts {
     /// The Cosmos private key of the validator
-    #[clap(short, long, parse(try_from_str))]
-    pub validator_phrase: CosmosPrivateKey,
-    /// The Ethereum private key to register
-    #[clap(short, long, parse(try_from_str))]
-    pub ethereum_key: EthPrivateKey,
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub validator_phrase: Option<CosmosPrivateKey>,
+    #[clap(flatten)]
+    pub ethereum: EthereumKeyOpts,
     /// The phrase for the Cosmos key to register
     #[clap(short, long, parse(try_from_str))]
     pub cosmos_phrase: CosmosPrivateKey,
@@ -205,11 +645,137 @@ pub struct RegisterOrchestratorAddressOpts {
     /// The Cosmos Denom and amount to pay Cosmos chain fees
     #[clap(short, long, parse(try_from_str))]
     pub fees: Coin,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
+}
+
+/// Ethereum private keys, given directly or derived from a mnemonic phrase. Only the relayer
+/// accepts more than one key, keys given with both --ethereum-key and --ethereum-mnemonic are
+/// combined
+#[derive(Parser)]
+pub struct EthereumKeyOpts {
+    /// The Ethereum private key, this may be repeated where a pool of keys is supported
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        env = "GBT_ETHEREUM_KEY",
+        hide_env_values = true
+    )]
+    pub ethereum_key: Vec<EthPrivateKey>,
+    /// A mnemonic phrase to derive Ethereum private keys from
+    #[clap(long, env = "GBT_ETHEREUM_MNEMONIC", hide_env_values = true)]
+    pub ethereum_mnemonic: Option<String>,
+    /// (Optional) The HD path of a key derived from --ethereum-mnemonic, this may be repeated to
+    /// derive several keys. By default the first Ethereum account m/44'/60'/0'/0/0
+    #[clap(long, env = "GBT_HD_PATH", requires = "ethereum-mnemonic")]
+    pub hd_path: Vec<String>,
+}
+
+/// Options for writing an unsigned Cosmos transaction to a file instead of signing and
+/// broadcasting it, so that it can be signed on an offline machine with `gbt tx sign`
+#[derive(Parser)]
+pub struct GenerateOnlyOpts {
+    /// (Optional) Write the unsigned transaction to this file instead of signing and broadcasting it
+    #[clap(long, parse(try_from_str), requires = "from")]
+    pub generate_only: Option<PathBuf>,
+    /// The address that will sign the transaction written with --generate-only
+    #[clap(long, parse(try_from_str))]
+    pub from: Option<CosmosAddress>,
+    /// (Optional) The gas limit of the transaction written with --generate-only
+    #[clap(long, default_value = "500000")]
+    pub gas_limit: u64,
+    /// (Optional) The block height after which the transaction written with --generate-only can
+    /// no longer be included, the default of 0 means it never times out so that there is no
+    /// deadline for signing it offline. It can still only be used once, at its sequence
+    #[clap(long, default_value = "0")]
+    pub timeout_height: u64,
+}
+
+/// Sign and broadcast transactions generated with --generate-only
+#[derive(Parser)]
+pub struct TxOpts {
+    #[clap(subcommand)]
+    pub subcmd: TxSubcommand,
+}
+
+#[derive(Parser)]
+pub enum TxSubcommand {
+    Sign(TxSignOpts),
+    Broadcast(TxBroadcastOpts),
+}
+
+/// Sign an unsigned transaction, this does not connect to the chain and can be run offline
+#[derive(Parser)]
+pub struct TxSignOpts {
+    /// The unsigned transaction written with --generate-only
+    #[clap(long, parse(try_from_str))]
+    pub file: PathBuf,
+    /// The Cosmos mnemonic phrase of the address that generated the transaction
+    #[clap(short, long, parse(try_from_str))]
+    pub cosmos_phrase: CosmosPrivateKey,
+    /// Where to write the signed transaction
+    #[clap(short, long, parse(try_from_str))]
+    pub output: PathBuf,
+}
+
+/// Broadcast a transaction signed with `gbt tx sign`
+#[derive(Parser)]
+pub struct TxBroadcastOpts {
+    /// The signed transaction written by `gbt tx sign`
+    #[clap(long, parse(try_from_str))]
+    pub file: PathBuf,
+    /// (Optional) The Cosmos gRPC server that will be used to submit the transaction
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
 }
 
 /// Initialize configuration
 #[derive(Parser)]
-pub struct InitOpts {}
+pub struct InitOpts {
+    /// (Optional) Ask for the endpoints and relaying preferences, check them against the chain
+    /// and write a config that is ready to run
+    #[clap(long)]
+    pub interactive: bool,
+    /// (Optional) Write the config even if the config folder exists. Every value of the existing
+    /// config is carried over and the existing config is kept as config.toml.<timestamp>.bak
+    #[clap(long)]
+    pub force: bool,
+}
+
+/// Inspect and maintain the config file
+#[derive(Parser)]
+pub struct ConfigOpts {
+    #[clap(subcommand)]
+    pub subcmd: ConfigSubcommand,
+}
+
+#[derive(Parser)]
+pub enum ConfigSubcommand {
+    Validate(ConfigValidateOpts),
+    Show(ConfigShowOpts),
+    Upgrade(ConfigUpgradeOpts),
+}
+
+/// Check that the config file parses and that its values can be used, keys that are not
+/// recognized are reported since they are otherwise ignored
+#[derive(Parser)]
+pub struct ConfigValidateOpts {}
+
+/// Print the config the orchestrator and relayer would run with, along with whether each value
+/// comes from an environment variable, the config file or the defaults. Flags of the
+/// orchestrator and relayer other than --address-prefix are not taken into account
+#[derive(Parser)]
+pub struct ConfigShowOpts {}
+
+/// Add the keys introduced by newer versions of gbt to the config file with their default
+/// values, a copy of the current file is kept as config.toml.<timestamp>.bak
+#[derive(Parser)]
+pub struct ConfigUpgradeOpts {
+    /// (Optional) Print the keys that would be added without changing the config file
+    #[clap(long)]
+    pub dry_run: bool,
+}
 
 /// The Gravity Bridge Governance subcommand contains tools for interacting with governance and submitting
 /// proposal types custom to Gravity Bridge
@@ -227,6 +793,15 @@ pub enum GovSubcommand {
     #[clap(subcommand)]
     /// Query info about custom governance proposal types
     Query(GovQuerySubcommand),
+    #[clap(subcommand)]
+    /// Build airdrop proposals from a CSV file and verify them against one
+    Airdrop(GovAirdropSubcommand),
+    #[clap(subcommand)]
+    /// Build IBC metadata proposals from the denom trace of an IBC token
+    IbcMetadata(GovIbcMetadataSubcommand),
+    #[clap(subcommand)]
+    /// Build oracle unhalt proposals from the current attestations
+    OracleUnhalt(GovOracleUnhaltSubcommand),
 }
 
 #[derive(Parser)]
@@ -235,11 +810,13 @@ pub enum GovSubmitSubcommand {
     Airdrop(AirdropProposalOpts),
     EmergencyBridgeHalt(EmergencyBridgeHaltProposalOpts),
     OracleUnhalt(OracleUnhaltProposalOpts),
+    ParamChange(ParamChangeProposalOpts),
 }
 
 #[derive(Parser)]
 pub enum GovQuerySubcommand {
     Airdrop(AirdropQueryOpts),
+    Proposals(ProposalQueryOpts),
 }
 
 #[derive(Parser)]
@@ -256,6 +833,133 @@ pub struct AirdropQueryOpts {
     pub full_list: bool,
 }
 
+#[derive(Parser)]
+pub enum GovAirdropSubcommand {
+    Build(AirdropBuildOpts),
+    Verify(AirdropVerifyOpts),
+}
+
+/// Builds an airdrop proposal.json from a CSV file of `address,amount` rows, where the amount is
+/// in the base unit of the denom. Duplicate addresses are merged and the total is checked against
+/// the community pool
+#[derive(Parser)]
+pub struct AirdropBuildOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to query the community pool
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// Path to the CSV file of recipients
+    #[clap(long, parse(try_from_str))]
+    pub csv: PathBuf,
+    /// Path to write the proposal.json to
+    #[clap(short, long, parse(try_from_str))]
+    pub output: PathBuf,
+    /// The denom to airdrop from the community pool
+    #[clap(long)]
+    pub denom: String,
+    /// The title of the proposal
+    #[clap(long)]
+    pub title: String,
+    /// The description of the proposal
+    #[clap(long)]
+    pub description: String,
+}
+
+/// Compares an airdrop proposal, either on chain or a local proposal.json, against a CSV file of
+/// `address,amount` rows so that voters can audit it
+#[derive(Parser)]
+pub struct AirdropVerifyOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to query the proposal
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// Path to the CSV file of recipients
+    #[clap(long, parse(try_from_str))]
+    pub csv: PathBuf,
+    /// The id of the airdrop proposal on chain
+    #[clap(long, required_unless_present = "json", conflicts_with = "json")]
+    pub proposal_id: Option<u64>,
+    /// Path to a local proposal.json
+    #[clap(short, long, parse(try_from_str))]
+    pub json: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub enum GovIbcMetadataSubcommand {
+    Build(IbcMetadataBuildOpts),
+}
+
+/// Builds an IBC metadata proposal.json for an ibc/... denom. The base denom is looked up from the
+/// IBC denom trace and used to propose a display unit, for example uatom is displayed as atom. The
+/// exponent of the display unit becomes the decimals of the ERC20 and can not be changed once the
+/// ERC20 is deployed, so it must always be given with --exponent
+#[derive(Parser)]
+pub struct IbcMetadataBuildOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to query the denom and submit the proposal
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// The IBC denom to set metadata for, eg: ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2
+    #[clap(long)]
+    pub ibc_denom: String,
+    /// (Optional) The display unit, by default derived from the base denom
+    #[clap(long)]
+    pub display: Option<String>,
+    /// The exponent of the display unit, which becomes the decimals of the ERC20. It is always
+    /// required, when it can be guessed from the base denom the guess is suggested
+    #[clap(long)]
+    pub exponent: Option<u32>,
+    /// (Optional) The name of the token, by default the display unit
+    #[clap(long)]
+    pub name: Option<String>,
+    /// (Optional) The symbol of the token, by default the display unit in upper case
+    #[clap(long)]
+    pub symbol: Option<String>,
+    /// Path to write the proposal.json to
+    #[clap(short, long, parse(try_from_str))]
+    pub output: PathBuf,
+    /// (Optional) Submit the proposal with this phrase once it has been written
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        requires_all = &["deposit", "fees"]
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
+    /// The Cosmos Denom and amount to pay the governance proposal deposit when submitting
+    #[clap(short, long, parse(try_from_str))]
+    pub deposit: Option<Coin>,
+    /// The Cosmos Denom and amount to pay Cosmos chain fees when submitting
+    #[clap(short, long, parse(try_from_str))]
+    pub fees: Option<Coin>,
+}
+
+#[derive(Parser)]
+pub enum GovOracleUnhaltSubcommand {
+    Build(OracleUnhaltBuildOpts),
+}
+
+/// Inspects the attestations and the last event nonce of each validator to find the event the
+/// oracle is halted on, then writes an oracle unhalt proposal.json that resets the oracle to the
+/// last event that reached consensus
+#[derive(Parser)]
+pub struct OracleUnhaltBuildOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to query the attestations
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// Path to write the proposal.json to
+    #[clap(short, long, parse(try_from_str))]
+    pub output: PathBuf,
+}
+
+#[derive(Parser)]
+/// Lists and decodes the Gravity proposals that are being voted on and those that have passed
+pub struct ProposalQueryOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to perform the query
+    #[clap(short, long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// (Optional) display full recipients list for airdrops over 100 members
+    #[clap(short, long)]
+    pub full_list: bool,
+}
+
 /// An IBC metadata proposal is a Governance proposal which allows setting denom metadata
 /// for an IBC token. This is an essential first setup in taking IBC tokens to Ethereum.
 /// The provided denom metadata will be used to set the name, symbol, description, and decimals
@@ -266,8 +970,13 @@ pub struct IbcMetadataProposalOpts {
     #[clap(long, default_value = "http://localhost:9090")]
     pub cosmos_grpc: String,
     /// The phrase for an address containing enough funds to submit the proposal.
-    #[clap(short, long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
     /// Path to the proposal.json
     #[clap(short, long, parse(try_from_str))]
     pub json: PathBuf,
@@ -277,6 +986,8 @@ pub struct IbcMetadataProposalOpts {
     /// The Cosmos Denom and amount to pay Cosmos chain fees
     #[clap(short, long, parse(try_from_str))]
     pub fees: Coin,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
 }
 
 /// An Airdrop Proposal allows the community to create, vote on, and execute
@@ -287,8 +998,13 @@ pub struct AirdropProposalOpts {
     #[clap(long, default_value = "http://localhost:9090")]
     pub cosmos_grpc: String,
     /// The phrase for an address containing enough funds to submit the proposal.
-    #[clap(short, long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
     /// Path to the proposal.json
     #[clap(short, long, parse(try_from_str))]
     pub json: PathBuf,
@@ -298,6 +1014,8 @@ pub struct AirdropProposalOpts {
     /// The Cosmos Denom and amount to pay Cosmos chain fees
     #[clap(short, long, parse(try_from_str))]
     pub fees: Coin,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
 }
 
 /// In case of a critical bug or other event involving the bridge the Gravity Bridge community may
@@ -310,8 +1028,13 @@ pub struct EmergencyBridgeHaltProposalOpts {
     #[clap(long, default_value = "http://localhost:9090")]
     pub cosmos_grpc: String,
     /// The phrase for an address containing enough funds to submit the proposal.
-    #[clap(short, long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
     /// Path to the proposal.json
     #[clap(short, long, parse(try_from_str))]
     pub json: PathBuf,
@@ -321,6 +1044,8 @@ pub struct EmergencyBridgeHaltProposalOpts {
     /// The Cosmos Denom and amount to pay Cosmos chain fees
     #[clap(short, long, parse(try_from_str))]
     pub fees: Coin,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
 }
 
 /// If there is a fork on the Ethereum mainnet it may cause disagreement in the bridge Oracle
@@ -333,8 +1058,47 @@ pub struct OracleUnhaltProposalOpts {
     #[clap(long, default_value = "http://localhost:9090")]
     pub cosmos_grpc: String,
     /// The phrase for an address containing enough funds to submit the proposal.
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present = "generate-only"
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
+    /// Path to the proposal.json
     #[clap(short, long, parse(try_from_str))]
-    pub cosmos_phrase: CosmosPrivateKey,
+    pub json: PathBuf,
+    /// The Cosmos Denom and amount to pay the governance proposal deposit
+    #[clap(short, long, parse(try_from_str))]
+    pub deposit: Coin,
+    /// The Cosmos Denom and amount to pay Cosmos chain fees
+    #[clap(short, long, parse(try_from_str))]
+    pub fees: Coin,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
+}
+
+/// A parameter change proposal changes the parameters of the Gravity module, such as the
+/// slashing windows or the valset reward. The proposal.json contains a list of changes of the form
+/// {"key": "SignedValsetsWindow", "value": 10000} which are checked against the current parameters
+/// and displayed before the proposal is submitted, use --dry-run to review them first
+#[derive(Parser)]
+pub struct ParamChangeProposalOpts {
+    /// (Optional) The Cosmos gRPC server that will be used to submit the transaction
+    #[clap(long, default_value = "http://localhost:9090")]
+    pub cosmos_grpc: String,
+    /// The phrase for an address containing enough funds to submit the proposal.
+    #[clap(
+        short,
+        long,
+        parse(try_from_str),
+        required_unless_present_any = &["generate-only", "dry-run"]
+    )]
+    pub cosmos_phrase: Option<CosmosPrivateKey>,
+    /// (Optional) Print the parameter changes against the current parameters without submitting
+    /// the proposal
+    #[clap(long)]
+    pub dry_run: bool,
     /// Path to the proposal.json
     #[clap(short, long, parse(try_from_str))]
     pub json: PathBuf,
@@ -344,4 +1108,6 @@ pub struct OracleUnhaltProposalOpts {
     /// The Cosmos Denom and amount to pay Cosmos chain fees
     #[clap(short, long, parse(try_from_str))]
     pub fees: Coin,
+    #[clap(flatten)]
+    pub offline: GenerateOnlyOpts,
 }
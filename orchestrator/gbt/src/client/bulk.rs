@@ -0,0 +1,501 @@
+//! Sends tokens to many destinations from a CSV file. Every row is validated before anything is
+//! sent, sends to Ethereum are packed into as few Cosmos transactions as possible and sends to
+//! Cosmos share a single approval per ERC20
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use cosmos_gravity::{
+    query::{get_denom_to_erc20, get_gravity_params},
+    send::send_to_eth_many,
+};
+use ethereum_gravity::send_to_cosmos::send_to_cosmos_many;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::{
+    clarity::{Address as EthAddress, PrivateKey as EthPrivateKey, Uint256},
+    connection_prep::create_rpc_connections,
+    deep_space::{Address as CosmosAddress, Coin, Contact},
+    error::GravityError,
+    num_conversion::{get_erc20_decimals, TokenAmount},
+    web30::client::Web3,
+};
+use tonic::transport::Channel;
+
+use crate::{
+    args::BulkOpts,
+    keys::derive::optional_ethereum_key,
+    utils::{get_token_decimals, TIMEOUT},
+};
+
+/// The gas assumed for each MsgSendToEth when packing transactions, this is a fixed upper bound
+/// rather than a simulation so `--max-tx-gas` caps the estimate and not the measured usage
+const SEND_TO_ETH_MSG_GAS: u64 = 100_000;
+
+/// A row of the CSV file as written by the user
+struct Row {
+    line: usize,
+    destination: String,
+    amount: TokenAmount,
+    token: String,
+    bridge_fee: Option<TokenAmount>,
+}
+
+enum Transfer {
+    ToCosmos {
+        erc20: EthAddress,
+        amount: Uint256,
+        destination: CosmosAddress,
+    },
+    ToEth {
+        destination: EthAddress,
+        amount: Coin,
+        bridge_fee: Coin,
+    },
+}
+
+fn parse_rows(contents: &str) -> Result<Vec<Row>, Vec<String>> {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields[0].eq_ignore_ascii_case("destination") {
+            continue;
+        }
+        if fields.len() < 3 || fields.len() > 4 {
+            errors.push(format!(
+                "Line {}: expected destination, amount, denom or token and an optional bridge fee",
+                line_number
+            ));
+            continue;
+        }
+        let amount = match fields[1].parse() {
+            Ok(amount) => amount,
+            Err(e) => {
+                errors.push(format!("Line {}: {}", line_number, e));
+                continue;
+            }
+        };
+        let bridge_fee = match fields.get(3).filter(|f| !f.is_empty()) {
+            Some(fee) => match fee.parse() {
+                Ok(fee) => Some(fee),
+                Err(e) => {
+                    errors.push(format!("Line {}: {}", line_number, e));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        rows.push(Row {
+            line: line_number,
+            destination: fields[0].to_string(),
+            amount,
+            token: fields[2].to_string(),
+            bridge_fee,
+        });
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+pub async fn bulk(args: BulkOpts, address_prefix: String) -> Result<(), GravityError> {
+    let ethereum_key = optional_ethereum_key(&args.ethereum)?;
+    let contents = match fs::read_to_string(&args.csv) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read {:?} {:?}",
+                args.csv, e
+            )))
+        }
+    };
+    let rows = match parse_rows(&contents) {
+        Ok(rows) => rows,
+        Err(errors) => return Err(invalid_rows(errors)),
+    };
+    if rows.is_empty() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{:?} has no transfers",
+            args.csv
+        )));
+    }
+
+    let to_cosmos = rows
+        .iter()
+        .any(|r| r.destination.parse::<CosmosAddress>().is_ok());
+    // decimals of Ethereum originated denoms are read from their ERC20 even without sends to Cosmos
+    let decimals_web3 = Web3::new(&args.ethereum_rpc, TIMEOUT);
+    let ethereum_rpc = if to_cosmos {
+        Some(args.ethereum_rpc.clone())
+    } else {
+        None
+    };
+    let connections = create_rpc_connections(
+        address_prefix.clone(),
+        Some(args.cosmos_grpc),
+        ethereum_rpc,
+        TIMEOUT,
+    )
+    .await;
+    let contact = connections.contact.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    // every row is resolved and checked before anything is sent
+    let mut errors = Vec::new();
+    let mut transfers = Vec::new();
+    let mut erc20_decimals = HashMap::new();
+    let mut denom_decimals = HashMap::new();
+    for row in rows.iter() {
+        let res = if let Ok(destination) = row.destination.parse::<CosmosAddress>() {
+            let (web3, key) = match (&connections.web3, ethereum_key) {
+                (Some(web3), Some(key)) => (web3, key),
+                _ => {
+                    errors.push(format!(
+                        "Line {}: --ethereum-key is required to send to Cosmos",
+                        row.line
+                    ));
+                    continue;
+                }
+            };
+            resolve_to_cosmos(row, destination, web3, key, &mut erc20_decimals).await
+        } else if let Ok(destination) = row.destination.parse::<EthAddress>() {
+            resolve_to_eth(
+                row,
+                destination,
+                &contact,
+                &mut grpc,
+                &decimals_web3,
+                &mut denom_decimals,
+            )
+            .await
+        } else {
+            Err(GravityError::ValidationError(format!(
+                "{} is not a Cosmos or Ethereum address",
+                row.destination
+            )))
+        };
+        match res {
+            Ok(transfer) => transfers.push(transfer),
+            Err(e) => errors.push(format!("Line {}: {}", row.line, e)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(invalid_rows(errors));
+    }
+
+    // totals are checked against balances so a partially executed file is unlikely
+    let mut cosmos_totals: HashMap<String, Uint256> = HashMap::new();
+    let mut eth_totals: HashMap<EthAddress, Uint256> = HashMap::new();
+    for transfer in transfers.iter() {
+        match transfer {
+            Transfer::ToCosmos { erc20, amount, .. } => {
+                let total = eth_totals.entry(*erc20).or_default();
+                *total = total.checked_add(*amount).unwrap();
+            }
+            Transfer::ToEth {
+                amount, bridge_fee, ..
+            } => {
+                let total = cosmos_totals.entry(amount.denom.clone()).or_default();
+                *total = total
+                    .checked_add(amount.amount)
+                    .unwrap()
+                    .checked_add(bridge_fee.amount)
+                    .unwrap();
+            }
+        }
+    }
+    let cosmos_key = args.cosmos_phrase;
+    let fee = args.fee;
+    if !cosmos_totals.is_empty() {
+        let (key, fee) = match (cosmos_key, &fee) {
+            (Some(key), Some(fee)) => (key, fee),
+            _ => {
+                return Err(GravityError::UnrecoverableError(
+                    "--cosmos-phrase and --fee are required to send to Ethereum".into(),
+                ))
+            }
+        };
+        let address = key.to_address(&address_prefix).unwrap();
+        for (denom, total) in cosmos_totals.iter() {
+            let balance = contact
+                .get_balance(address, denom.clone())
+                .await?
+                .map(|c| c.amount)
+                .unwrap_or_default();
+            if balance < *total {
+                errors.push(format!(
+                    "{} has {}{} but the sends and bridge fees total {}{}",
+                    address, balance, denom, total, denom
+                ));
+            }
+        }
+        if !cosmos_totals.contains_key(&fee.denom)
+            && contact
+                .get_balance(address, fee.denom.clone())
+                .await?
+                .is_none()
+        {
+            errors.push(format!("{} has no {} to pay fees", address, fee.denom));
+        }
+    }
+    if let (Some(web3), Some(key)) = (&connections.web3, ethereum_key) {
+        for (erc20, total) in eth_totals.iter() {
+            let balance = web3.get_erc20_balance(*erc20, key.to_address()).await?;
+            if balance < *total {
+                errors.push(format!(
+                    "{} has {} of {} but the sends total {}",
+                    key.to_address(),
+                    balance,
+                    erc20,
+                    total
+                ));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(invalid_rows(errors));
+    }
+    info!("All {} transfers are valid", transfers.len());
+
+    let mut results: Vec<Result<String, String>> = vec![Err("Not sent".into()); transfers.len()];
+
+    // sends to Ethereum are packed into transactions by their estimated gas
+    let to_eth: Vec<(usize, (EthAddress, Coin, Coin))> = transfers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            Transfer::ToEth {
+                destination,
+                amount,
+                bridge_fee,
+            } => Some((i, (*destination, amount.clone(), bridge_fee.clone()))),
+            _ => None,
+        })
+        .collect();
+    if let (Some(key), Some(fee)) = (cosmos_key, fee) {
+        let msgs_per_tx = (args.max_tx_gas / SEND_TO_ETH_MSG_GAS).max(1) as usize;
+        for chunk in to_eth.chunks(msgs_per_tx) {
+            let sends: Vec<(EthAddress, Coin, Coin)> =
+                chunk.iter().map(|(_, send)| send.clone()).collect();
+            info!("Sending {} transfers to Ethereum", sends.len());
+            let res = match send_to_eth_many(key, &sends, fee.clone(), &contact).await {
+                Ok(tx) if tx.code == 0 => Ok(tx.txhash),
+                Ok(tx) => Err(format!("Transaction {} failed {}", tx.txhash, tx.raw_log)),
+                Err(e) => Err(format!("{:?}", e)),
+            };
+            match &res {
+                Ok(txhash) => info!("Send to Eth txid {}", txhash),
+                Err(e) => error!("Failed to send to Ethereum {}", e),
+            }
+            for (i, _) in chunk {
+                results[*i] = res.clone();
+            }
+        }
+    }
+
+    let to_cosmos: Vec<(usize, (EthAddress, Uint256, CosmosAddress))> = transfers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            Transfer::ToCosmos {
+                erc20,
+                amount,
+                destination,
+            } => Some((i, (*erc20, *amount, *destination))),
+            _ => None,
+        })
+        .collect();
+    if let (Some(web3), Some(key), false) = (&connections.web3, ethereum_key, to_cosmos.is_empty())
+    {
+        let gravity_contract_address = match args.gravity_contract_address {
+            Some(c) => c,
+            None => match get_gravity_params(&mut grpc).await?.bridge_ethereum_address.parse() {
+                Ok(c) => c,
+                Err(_) => {
+                    return Err(GravityError::UnrecoverableError(
+                        "The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into(),
+                    ))
+                }
+            },
+        };
+        let sends: Vec<(EthAddress, Uint256, CosmosAddress)> =
+            to_cosmos.iter().map(|(_, send)| *send).collect();
+        info!("Sending {} transfers to Cosmos", sends.len());
+        match send_to_cosmos_many(gravity_contract_address, &sends, key, TIMEOUT, web3).await {
+            Ok(sent) => {
+                for ((i, _), res) in to_cosmos.iter().zip(sent) {
+                    results[*i] = match res {
+                        Ok(tx_hash) => Ok(format!("{:#066x}", tx_hash)),
+                        Err(e) => Err(format!("{:?}", e)),
+                    };
+                }
+            }
+            Err(e) => error!("Failed to approve the sends to Cosmos {:?}", e),
+        }
+    }
+
+    let results_path = args.results.unwrap_or_else(|| {
+        let mut path = args.csv.clone().into_os_string();
+        path.push(".results.csv");
+        PathBuf::from(path)
+    });
+    let mut output = String::from("line,destination,amount,token,status,tx\n");
+    let mut failures = 0;
+    for (row, res) in rows.iter().zip(results) {
+        let (status, tx) = match res {
+            Ok(txhash) => ("sent".to_string(), txhash),
+            Err(e) => {
+                failures += 1;
+                (format!("failed: {}", e.replace(',', ";")), String::new())
+            }
+        };
+        output.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            row.line, row.destination, row.amount, row.token, status, tx
+        ));
+    }
+    if let Err(e) = fs::write(&results_path, output) {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Failed to write results to {:?} {:?}",
+            results_path, e
+        )));
+    }
+    info!("Results written to {:?}", results_path);
+    if failures > 0 {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{} of {} transfers failed, see {:?}",
+            failures,
+            rows.len(),
+            results_path
+        )));
+    }
+    Ok(())
+}
+
+fn invalid_rows(errors: Vec<String>) -> GravityError {
+    for e in errors.iter() {
+        error!("{}", e);
+    }
+    GravityError::UnrecoverableError(format!(
+        "{} problems found, nothing has been sent",
+        errors.len()
+    ))
+}
+
+async fn resolve_to_cosmos(
+    row: &Row,
+    destination: CosmosAddress,
+    web3: &Web3,
+    key: EthPrivateKey,
+    decimals_cache: &mut HashMap<EthAddress, u8>,
+) -> Result<Transfer, GravityError> {
+    if row.bridge_fee.is_some() {
+        return Err(GravityError::ValidationError(
+            "Sends to Cosmos do not take a bridge fee".into(),
+        ));
+    }
+    let erc20: EthAddress = match row.token.parse() {
+        Ok(erc20) => erc20,
+        Err(_) => {
+            return Err(GravityError::ValidationError(format!(
+                "{} is not an ERC20 address",
+                row.token
+            )))
+        }
+    };
+    let decimals = match decimals_cache.get(&erc20) {
+        Some(decimals) => *decimals,
+        None => {
+            let decimals = get_erc20_decimals(web3, erc20, key.to_address()).await?;
+            decimals_cache.insert(erc20, decimals);
+            decimals
+        }
+    };
+    Ok(Transfer::ToCosmos {
+        erc20,
+        amount: row.amount.to_base_units(decimals)?,
+        destination,
+    })
+}
+
+async fn resolve_to_eth(
+    row: &Row,
+    destination: EthAddress,
+    contact: &Contact,
+    grpc: &mut GravityQueryClient<Channel>,
+    web3: &Web3,
+    decimals_cache: &mut HashMap<String, u8>,
+) -> Result<Transfer, GravityError> {
+    let denom = row.token.clone();
+    let decimals = match decimals_cache.get(&denom) {
+        Some(decimals) => *decimals,
+        None => {
+            if get_denom_to_erc20(grpc, denom.clone()).await.is_err() {
+                return Err(GravityError::ValidationError(format!(
+                    "{} has no ERC20 representation",
+                    denom
+                )));
+            }
+            let decimals = get_token_decimals(contact, grpc, web3, &denom).await?;
+            decimals_cache.insert(denom.clone(), decimals);
+            decimals
+        }
+    };
+    let bridge_fee = match &row.bridge_fee {
+        Some(fee) => fee.to_base_units(decimals)?,
+        None => Uint256::default(),
+    };
+    Ok(Transfer::ToEth {
+        destination,
+        amount: Coin {
+            denom: denom.clone(),
+            amount: row.amount.to_base_units(decimals)?,
+        },
+        bridge_fee: Coin {
+            denom,
+            amount: bridge_fee,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rows() {
+        let csv = "destination,amount,token,bridge_fee\n# a comment\n\n0xabc,1.5,ugraviton,0.1\n cosmos1abc , 200 , 0xdef \n0xabc,3,ugraviton,\n";
+        let rows = parse_rows(csv).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].line, 4);
+        assert_eq!(rows[0].destination, "0xabc");
+        assert_eq!(rows[0].amount, "1.5".parse().unwrap());
+        assert_eq!(rows[0].token, "ugraviton");
+        assert_eq!(rows[0].bridge_fee, Some("0.1".parse().unwrap()));
+        assert_eq!(rows[1].line, 5);
+        assert_eq!(rows[1].destination, "cosmos1abc");
+        assert_eq!(rows[1].amount, "200".parse().unwrap());
+        assert_eq!(rows[1].token, "0xdef");
+        assert_eq!(rows[1].bridge_fee, None);
+        // an empty bridge fee column is the same as no bridge fee
+        assert_eq!(rows[2].bridge_fee, None);
+    }
+
+    #[test]
+    fn test_parse_rows_errors() {
+        let csv = "0xabc,1,ugraviton\n0xabc,1\n0xabc,1,ugraviton,1,1\n0xabc,-1,ugraviton\n0xabc,1.2.3,ugraviton\n0xabc,1,ugraviton,abc\n";
+        let errors = parse_rows(csv).unwrap_err();
+        assert_eq!(errors.len(), 5);
+        assert!(errors[0].starts_with("Line 2: expected destination, amount"));
+        assert!(errors[1].starts_with("Line 3: expected destination, amount"));
+        assert_eq!(errors[2], "Line 4: Invalid token amount -1");
+        assert_eq!(errors[3], "Line 5: Invalid token amount 1.2.3");
+        assert_eq!(errors[4], "Line 6: Invalid token amount abc");
+    }
+}
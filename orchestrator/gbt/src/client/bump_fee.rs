@@ -0,0 +1,226 @@
+use std::convert::TryFrom;
+
+use cosmos_gravity::{
+    query::{get_erc20_to_denom, get_pending_batch_fees, get_pending_send_to_eth},
+    send::resend_to_eth_with_fee,
+};
+use gravity_proto::gravity::BatchFees;
+use gravity_utils::{
+    clarity::{constants::ZERO_ADDRESS, Address as EthAddress, Uint256},
+    connection_prep::{check_for_fee, create_rpc_connections},
+    deep_space::Coin,
+    error::GravityError,
+    prices::get_weth_price,
+    types::BatchTransaction,
+    web30::client::Web3,
+};
+
+use crate::{
+    args::BumpFeeOpts,
+    utils::{AmountFormatter, TIMEOUT},
+};
+
+/// Returns the rank of `token` among the tokens waiting to be batched if the WETH value of its
+/// total fees was `total`, where 1 is the highest, along with the number of tokens ranked.
+/// `weth_totals` are the WETH values of the total fees of each token
+fn fee_rank(weth_totals: &[(String, Uint256)], token: &str, total: Uint256) -> (usize, usize) {
+    let mut rank = 1;
+    let mut count = 1;
+    for (_, other) in weth_totals.iter().filter(|(t, _)| t != token) {
+        count += 1;
+        if *other > total {
+            rank += 1;
+        }
+    }
+    (rank, count)
+}
+
+/// Prices the total fees of every token waiting to be batched in WETH the way relayers do,
+/// tokens that can't be priced are left out
+async fn get_weth_totals(web3: &Web3, batch_fees: &[BatchFees]) -> Vec<(String, Uint256)> {
+    let mut weth_totals = Vec::new();
+    for fees in batch_fees.iter() {
+        let (token, total) = match (
+            fees.token.parse::<EthAddress>(),
+            Uint256::from_dec_or_hex_str_restricted(&fees.total_fees),
+        ) {
+            (Ok(token), Ok(total)) => (token, total),
+            _ => continue,
+        };
+        match get_weth_price(token, total, ZERO_ADDRESS, web3).await {
+            Ok(price) => weth_totals.push((fees.token.clone(), price)),
+            Err(e) => info!("Could not price the fees of {} in WETH {:?}", token, e),
+        }
+    }
+    weth_totals
+}
+
+pub async fn bump_fee(args: BumpFeeOpts, address_prefix: String) -> Result<(), GravityError> {
+    let cosmos_key = args.cosmos_phrase;
+    let cosmos_grpc = args.cosmos_grpc;
+    let bridge_fee = args.bridge_fee;
+    let fee = args.fee;
+
+    let cosmos_address = cosmos_key.to_address(&address_prefix).unwrap();
+    let connections =
+        create_rpc_connections(address_prefix, Some(cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    let pending = get_pending_send_to_eth(&mut grpc, cosmos_address).await?;
+    let mut transfer = None;
+    for t in pending.unbatched_transfers {
+        let t = BatchTransaction::try_from(t)?;
+        if t.id == args.transaction_id {
+            transfer = Some(t);
+        }
+    }
+    let transfer = match transfer {
+        Some(t) => t,
+        None => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "{} has no unbatched send with id {}, sends that are already in a batch can not be replaced",
+                cosmos_address, args.transaction_id
+            )))
+        }
+    };
+
+    let token = transfer.erc20_token.token_contract_address;
+    let denom = get_erc20_to_denom(&mut grpc, token).await?.denom;
+    if bridge_fee.denom != denom {
+        return Err(GravityError::UnrecoverableError(format!(
+            "The bridge fee must be paid in {}, the denom of the send",
+            denom
+        )));
+    }
+    if bridge_fee.amount <= transfer.erc20_fee.amount {
+        return Err(GravityError::UnrecoverableError(format!(
+            "The new bridge fee {}{} must be greater than the current fee {}{}",
+            bridge_fee.amount, denom, transfer.erc20_fee.amount, denom
+        )));
+    }
+
+    // relayers request batches based on the WETH value of the total fees of the sends waiting
+    // for each token, so the totals of different tokens are only compared once priced in WETH
+    let batch_fees = get_pending_batch_fees(&mut grpc).await?.batch_fees;
+    if let Some(fees) = batch_fees.iter().find(|f| f.token == token.to_string()) {
+        let web3 = Web3::new(&args.ethereum_rpc, TIMEOUT);
+        let mut amounts = AmountFormatter::new(web3.clone());
+        let total = Uint256::from_dec_or_hex_str_restricted(&fees.total_fees)?;
+        let new_total = total
+            .checked_sub(transfer.erc20_fee.amount)
+            .unwrap_or_default()
+            .checked_add(bridge_fee.amount)
+            .unwrap();
+        info!(
+            "{} has {} sends waiting to be batched with a total of {} {} in fees",
+            denom,
+            fees.tx_count,
+            amounts.format(token, total).await,
+            denom
+        );
+        info!(
+            "Your fee would increase from {} {} to {} {} raising the total to {} {}",
+            amounts.format(token, transfer.erc20_fee.amount).await,
+            denom,
+            amounts.format(token, bridge_fee.amount).await,
+            denom,
+            amounts.format(token, new_total).await,
+            denom
+        );
+        let weth_totals = get_weth_totals(&web3, &batch_fees).await;
+        let prices = (
+            weth_totals.iter().find(|(t, _)| *t == fees.token),
+            get_weth_price(token, new_total, ZERO_ADDRESS, &web3).await,
+        );
+        match prices {
+            (Some((_, weth_total)), Ok(new_weth_total)) => {
+                let (rank, count) = fee_rank(&weth_totals, &fees.token, *weth_total);
+                let (new_rank, _) = fee_rank(&weth_totals, &fees.token, new_weth_total);
+                info!(
+                    "Priced in WETH {} would rank {} of {} tokens waiting to be batched by total fees, currently {}",
+                    denom, new_rank, count, rank
+                );
+                if count < batch_fees.len() {
+                    info!(
+                        "{} tokens waiting to be batched could not be priced and are not ranked",
+                        batch_fees.len() - count
+                    );
+                }
+            }
+            _ => info!(
+                "{} can not be priced in WETH, so it can't be ranked against other tokens",
+                denom
+            ),
+        }
+    }
+
+    let amount = Coin {
+        denom: denom.clone(),
+        amount: transfer.erc20_token.amount,
+    };
+    // the cancel returns the original fee, so only the increase must be available
+    let fee_increase = bridge_fee
+        .amount
+        .checked_sub(transfer.erc20_fee.amount)
+        .unwrap();
+    if fee.denom == denom {
+        let total_fee = Coin {
+            denom: denom.clone(),
+            amount: fee_increase.checked_add(fee.amount).unwrap(),
+        };
+        check_for_fee(&total_fee, cosmos_address, &contact).await?;
+    } else {
+        let total_fee = Coin {
+            denom: denom.clone(),
+            amount: fee_increase,
+        };
+        check_for_fee(&total_fee, cosmos_address, &contact).await?;
+        check_for_fee(&fee, cosmos_address, &contact).await?;
+    }
+
+    info!(
+        "Replacing send {} of {}{} to {}",
+        transfer.id, amount.amount, denom, transfer.destination
+    );
+    let res = resend_to_eth_with_fee(
+        cosmos_key,
+        transfer.id,
+        transfer.destination,
+        amount,
+        bridge_fee,
+        fee,
+        &contact,
+    )
+    .await;
+    match res {
+        Ok(tx_id) => info!("Bump fee txid {}", tx_id.txhash),
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to replace send! {:?}",
+                e
+            )))
+        }
+    }
+    info!("Your send has been replaced, the new send has a new id");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_rank() {
+        let weth_totals = vec![
+            ("a".to_string(), Uint256::from_u64(100)),
+            ("b".to_string(), Uint256::from_u64(50)),
+            ("c".to_string(), Uint256::from_u64(10)),
+        ];
+        assert_eq!(fee_rank(&weth_totals, "c", Uint256::from_u64(10)), (3, 3));
+        assert_eq!(fee_rank(&weth_totals, "c", Uint256::from_u64(60)), (2, 3));
+        assert_eq!(fee_rank(&weth_totals, "c", Uint256::from_u64(150)), (1, 3));
+        // a token that is not waiting yet is counted
+        assert_eq!(fee_rank(&weth_totals, "d", Uint256::from_u64(20)), (3, 4));
+    }
+}
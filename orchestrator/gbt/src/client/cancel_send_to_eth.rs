@@ -0,0 +1,130 @@
+use std::convert::TryFrom;
+
+use cosmos_gravity::{
+    offline::{EncodedMsg, CANCEL_SEND_TO_ETH_TYPE_URL},
+    query::get_pending_send_to_eth,
+};
+use gravity_proto::gravity::MsgCancelSendToEth;
+use gravity_utils::{
+    connection_prep::{check_for_fee, create_rpc_connections},
+    error::GravityError,
+    types::BatchTransaction,
+    web30::client::Web3,
+};
+
+use crate::{
+    args::CancelSendToEthOpts,
+    tx::offline::{sender_address, write_unsigned_tx},
+    utils::{AmountFormatter, TIMEOUT},
+};
+
+pub async fn cancel_send_to_eth(
+    args: CancelSendToEthOpts,
+    address_prefix: String,
+) -> Result<(), GravityError> {
+    let cosmos_key = args.cosmos_phrase;
+    let cosmos_grpc = args.cosmos_grpc;
+
+    let cosmos_address = sender_address(cosmos_key, &args.offline, &address_prefix)?;
+    let connections =
+        create_rpc_connections(address_prefix, Some(cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    let pending = get_pending_send_to_eth(&mut grpc, cosmos_address).await?;
+    let mut unbatched = Vec::new();
+    for transfer in pending.unbatched_transfers {
+        unbatched.push(BatchTransaction::try_from(transfer)?);
+    }
+
+    let to_cancel: Vec<u64> = match (args.transaction_id, args.all) {
+        (Some(id), _) => {
+            if !unbatched.iter().any(|t| t.id == id) {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "{} has no unbatched send with id {}, sends that are already in a batch can not be cancelled",
+                    cosmos_address, id
+                )));
+            }
+            vec![id]
+        }
+        (None, true) => unbatched.iter().map(|t| t.id).collect(),
+        (None, false) => {
+            let mut amounts = AmountFormatter::new(Web3::new(&args.ethereum_rpc, TIMEOUT));
+            info!("Pending sends to Ethereum from {}", cosmos_address);
+            for transfer in pending.transfers_in_batches {
+                let transfer = BatchTransaction::try_from(transfer)?;
+                let token = transfer.erc20_token.token_contract_address;
+                info!(
+                    "Id {} to {} amount {} fee {} token {} is in a batch and can not be cancelled",
+                    transfer.id,
+                    transfer.destination,
+                    amounts.format(token, transfer.erc20_token.amount).await,
+                    amounts.format(token, transfer.erc20_fee.amount).await,
+                    token
+                );
+            }
+            for transfer in unbatched.iter() {
+                let token = transfer.erc20_token.token_contract_address;
+                info!(
+                    "Id {} to {} amount {} fee {} token {}",
+                    transfer.id,
+                    transfer.destination,
+                    amounts.format(token, transfer.erc20_token.amount).await,
+                    amounts.format(token, transfer.erc20_fee.amount).await,
+                    token
+                );
+            }
+            info!("Use --transaction-id or --all to cancel unbatched sends");
+            return Ok(());
+        }
+    };
+
+    if to_cancel.is_empty() {
+        info!("{} has no unbatched sends to cancel", cosmos_address);
+        return Ok(());
+    }
+    let fee = match args.fee {
+        Some(fee) => fee,
+        None => {
+            return Err(GravityError::UnrecoverableError(
+                "A --fee is required to cancel sends".into(),
+            ))
+        }
+    };
+    check_for_fee(&fee, cosmos_address, &contact).await?;
+
+    if args.offline.generate_only.is_some() {
+        // offline all of the cancels are made in a single transaction so only one is signed
+        let msgs = to_cancel
+            .into_iter()
+            .map(|transaction_id| {
+                let msg = MsgCancelSendToEth {
+                    transaction_id,
+                    sender: cosmos_address.to_string(),
+                };
+                EncodedMsg::new(CANCEL_SEND_TO_ETH_TYPE_URL, msg)
+            })
+            .collect();
+        return write_unsigned_tx(&contact, &args.offline, msgs, fee).await;
+    }
+    let cosmos_key = cosmos_key.unwrap();
+    for id in to_cancel {
+        info!("Cancelling send {}", id);
+        let res =
+            cosmos_gravity::send::cancel_send_to_eth(cosmos_key, fee.clone(), &contact, id).await;
+        match res {
+            Ok(tx_id) => info!("Cancel send to Eth txid {}", tx_id.txhash),
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Failed to cancel send {}! {:?}",
+                    id, e
+                )))
+            }
+        }
+    }
+    info!(
+        "Your tokens and fees have been returned to {}",
+        cosmos_address
+    );
+    Ok(())
+}
@@ -1,35 +1,56 @@
-use cosmos_gravity::{query::get_denom_to_erc20, send::send_to_eth};
-use gravity_proto::gravity::QueryDenomToErc20Request;
+use std::time::{Duration, Instant};
+
+use cosmos_gravity::{
+    offline::{EncodedMsg, SEND_TO_ETH_TYPE_URL},
+    query::get_denom_to_erc20,
+    send::send_to_eth,
+};
+use ethereum_gravity::weth::unwrap_weth;
+use gravity_proto::gravity::{MsgSendToEth, QueryDenomToErc20Request};
 use gravity_utils::{
-    connection_prep::{check_for_fee, create_rpc_connections},
+    clarity::Address as EthAddress,
+    connection_prep::{check_for_eth, check_for_fee, create_rpc_connections},
+    deep_space::Coin,
     error::GravityError,
-    num_conversion::{print_eth, print_nom},
+    num_conversion::format_token_amount,
+    web30::{amm::WETH_CONTRACT_ADDRESS, client::Web3},
 };
+use tokio::time::sleep;
 
-use crate::{args::CosmosToEthOpts, utils::TIMEOUT};
+use crate::{
+    args::CosmosToEthOpts,
+    tx::offline::{sender_address, write_unsigned_tx},
+    utils::{get_token_decimals, TIMEOUT},
+};
+
+/// How often the destination balance is checked while waiting for WETH to arrive
+const UNWRAP_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How long to wait for WETH to arrive before giving up on unwrapping it
+const UNWRAP_TIMEOUT: Duration = Duration::from_secs(6 * 60 * 60);
 
 pub async fn cosmos_to_eth(
     args: CosmosToEthOpts,
     address_prefix: String,
 ) -> Result<(), GravityError> {
     let cosmos_key = args.cosmos_phrase;
-    let gravity_coin = args.amount;
+    let denom = args.denom;
     let fee = args.fee;
     let cosmos_grpc = args.cosmos_grpc;
     let eth_dest = args.eth_destination;
-    let bridge_fee = args.bridge_fee;
+    let unwrap_key = args.unwrap_with_key;
 
-    let cosmos_address = cosmos_key.to_address(&address_prefix).unwrap();
+    let cosmos_address = sender_address(cosmos_key, &args.offline, &address_prefix)?;
 
     info!("Sending from Cosmos address {}", cosmos_address);
+    let ethereum_rpc = unwrap_key.map(|_| args.ethereum_rpc.clone());
     let connections =
-        create_rpc_connections(address_prefix, Some(cosmos_grpc), None, TIMEOUT).await;
+        create_rpc_connections(address_prefix, Some(cosmos_grpc), ethereum_rpc, TIMEOUT).await;
     let contact = connections.contact.unwrap();
     let mut grpc = connections.grpc.unwrap();
 
-    let res = get_denom_to_erc20(&mut grpc, gravity_coin.denom.clone()).await;
-    let is_cosmos_originated = match res {
-        Ok(v) => v.cosmos_originated,
+    let res = get_denom_to_erc20(&mut grpc, denom.clone()).await;
+    let erc20 = match res {
+        Ok(v) => v.erc20.parse::<EthAddress>()?,
         Err(e) => {
             return Err(GravityError::UnrecoverableError(format!(
                 "Could not lookup denom is it valid? {:?}",
@@ -37,6 +58,47 @@ pub async fn cosmos_to_eth(
             )));
         }
     };
+    // unwrapping on arrival is checked before anything is sent
+    let unwrap = match unwrap_key {
+        Some(key) => {
+            if erc20 != *WETH_CONTRACT_ADDRESS {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Only WETH can be unwrapped, {} is represented by {}",
+                    denom, erc20
+                )));
+            }
+            if key.to_address() != eth_dest {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "--unwrap-with-key must be the key of the destination {}",
+                    eth_dest
+                )));
+            }
+            let web3 = connections.web3.unwrap();
+            check_for_eth(eth_dest, &web3).await?;
+            let starting_balance = web3
+                .get_erc20_balance(*WETH_CONTRACT_ADDRESS, eth_dest)
+                .await?;
+            Some((key, web3, starting_balance))
+        }
+        None => None,
+    };
+    // amounts are given and displayed in the display unit of the denom, Ethereum originated
+    // tokens have no metadata so the decimals of their ERC20 are read from Ethereum
+    let decimals = get_token_decimals(
+        &contact,
+        &mut grpc,
+        &Web3::new(&args.ethereum_rpc, TIMEOUT),
+        &denom,
+    )
+    .await?;
+    let gravity_coin = Coin {
+        denom: denom.clone(),
+        amount: args.amount.to_base_units(decimals)?,
+    };
+    let bridge_fee = Coin {
+        denom: denom.clone(),
+        amount: args.bridge_fee.to_base_units(decimals)?,
+    };
 
     let res = grpc
         .denom_to_erc20(QueryDenomToErc20Request {
@@ -69,15 +131,9 @@ pub async fn cosmos_to_eth(
     match balance {
         Some(balance) => {
             if balance.amount < amount.amount.checked_add(bridge_fee.amount).unwrap() {
-                return if is_cosmos_originated {
-                    Err(GravityError::UnrecoverableError(
-                        format!("Your transfer of {} {} tokens is greater than your balance of {} tokens. Remember you need some to pay for fees!",
-                                print_nom(amount.amount), gravity_coin.denom, print_nom(balance.amount))))
-                } else {
-                    Err(GravityError::UnrecoverableError(
-                        format!("Your transfer of {} {} tokens is greater than your balance of {} tokens. Remember you need some to pay for fees!",
-                                print_eth(amount.amount), gravity_coin.denom, print_eth(balance.amount))))
-                };
+                return Err(GravityError::UnrecoverableError(
+                    format!("Your transfer of {} {} tokens is greater than your balance of {} tokens. Remember you need some to pay for fees!",
+                            format_token_amount(amount.amount, decimals), gravity_coin.denom, format_token_amount(balance.amount, decimals))));
             }
         }
         None => {
@@ -88,12 +144,25 @@ pub async fn cosmos_to_eth(
         }
     }
 
+    if args.offline.generate_only.is_some() {
+        let msg = MsgSendToEth {
+            sender: cosmos_address.to_string(),
+            eth_dest: eth_dest.to_string(),
+            amount: Some(amount.into()),
+            bridge_fee: Some(bridge_fee.into()),
+        };
+        let msgs = vec![EncodedMsg::new(SEND_TO_ETH_TYPE_URL, msg)];
+        return write_unsigned_tx(&contact, &args.offline, msgs, fee).await;
+    }
+
     info!(
-        "Locking {} / {} into the batch pool",
-        amount.denom, gravity_coin.denom
+        "Locking {} {} into the batch pool with a bridge fee of {}",
+        format_token_amount(amount.amount, decimals),
+        denom,
+        format_token_amount(bridge_fee.amount, decimals)
     );
     let res = send_to_eth(
-        cosmos_key,
+        cosmos_key.unwrap(),
         eth_dest,
         amount.clone(),
         bridge_fee.clone(),
@@ -112,6 +181,40 @@ pub async fn cosmos_to_eth(
     }
     info!("Your funds are now waiting to be sent to Ethereum in a transaction batch!");
     info!("Depending on how much you and others attached in fees, this might take a while!");
-    info!("You can retrieve your funds using a CancelSendToEth message, up until they are sent to Ethereum");
+    info!("You can retrieve your funds using `gbt client cancel-send-to-eth`, up until they are included in a batch");
+    if let Some((key, web3, starting_balance)) = unwrap {
+        let expected = starting_balance.checked_add(amount.amount).unwrap();
+        info!("Waiting for the WETH to arrive at {} to unwrap it, this can be interrupted at any time and the WETH will still arrive", eth_dest);
+        let start = Instant::now();
+        loop {
+            match web3
+                .get_erc20_balance(*WETH_CONTRACT_ADDRESS, eth_dest)
+                .await
+            {
+                Ok(balance) if balance >= expected => break,
+                Ok(_) => info!("The WETH has not arrived yet"),
+                Err(e) => warn!("Failed to get the WETH balance of {} {:?}", eth_dest, e),
+            }
+            if Instant::now() - start > UNWRAP_TIMEOUT {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "The WETH did not arrive within {} hours, it will still arrive at {} and can be unwrapped by calling withdraw on the WETH contract {}",
+                    UNWRAP_TIMEOUT.as_secs() / 3600,
+                    eth_dest,
+                    *WETH_CONTRACT_ADDRESS
+                )));
+            }
+            sleep(UNWRAP_POLL_INTERVAL).await;
+        }
+        info!("The WETH has arrived, unwrapping it into ETH");
+        match unwrap_weth(amount.amount, key, TIMEOUT, &web3, vec![]).await {
+            Ok(tx_id) => info!("Unwrapped WETH txid: {:#066x}", tx_id),
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Your WETH arrived but could not be unwrapped, it is still held as WETH {} by {} and can be unwrapped by calling withdraw on the WETH contract {:?}",
+                    *WETH_CONTRACT_ADDRESS, eth_dest, e
+                )))
+            }
+        }
+    }
     Ok(())
 }
@@ -10,7 +10,7 @@ use gravity_utils::{
 };
 use tokio::time::sleep;
 
-use crate::{args::DeployErc20RepresentationOpts, utils::TIMEOUT};
+use crate::{args::DeployErc20RepresentationOpts, keys::derive::ethereum_key, utils::TIMEOUT};
 
 pub async fn deploy_erc20_representation(
     args: DeployErc20RepresentationOpts,
@@ -18,7 +18,7 @@ pub async fn deploy_erc20_representation(
 ) -> Result<(), GravityError> {
     let grpc_url = args.cosmos_grpc;
     let ethereum_rpc = args.ethereum_rpc;
-    let ethereum_key = args.ethereum_key;
+    let ethereum_key = ethereum_key(&args.ethereum)?;
     let denom = args.cosmos_denom;
 
     let connections =
@@ -1,20 +1,54 @@
-use ethereum_gravity::{send_to_cosmos::send_to_cosmos, utils::get_valset_nonce};
+use ethereum_gravity::{
+    erc20_approval::{approve_erc20_amount, get_erc20_allowance, Erc20Approval},
+    offline::generate_send_to_cosmos_txs,
+    send_to_cosmos::send_to_cosmos,
+    utils::get_valset_nonce,
+    weth::wrap_eth,
+};
 use gravity_utils::{
+    clarity::Uint256,
     connection_prep::{check_for_eth, create_rpc_connections},
     error::GravityError,
-    num_conversion::fraction_to_exponent,
+    num_conversion::{format_token_amount, get_erc20_decimals},
+    web30::amm::WETH_CONTRACT_ADDRESS,
 };
 
-use crate::{args::EthToCosmosOpts, utils::TIMEOUT};
+use crate::{
+    args::{ApprovalMode, EthToCosmosOpts},
+    keys::derive::optional_ethereum_key,
+    tx::offline::write_json,
+    utils::TIMEOUT,
+};
 
 pub async fn eth_to_cosmos(args: EthToCosmosOpts, prefix: String) -> Result<(), GravityError> {
     let gravity_address = args.gravity_contract_address;
-    let erc20_address = args.token_contract_address;
+    let erc20_address = match (args.eth, args.token_contract_address) {
+        (true, _) => *WETH_CONTRACT_ADDRESS,
+        (false, Some(token)) => token,
+        (false, None) => {
+            return Err(GravityError::UnrecoverableError(
+                "Either --token-contract-address or --eth is required".into(),
+            ))
+        }
+    };
     let cosmos_dest = args.destination;
-    let ethereum_key = args.ethereum_key;
-    let ethereum_public_key = ethereum_key.to_address();
+    let ethereum_key = optional_ethereum_key(&args.ethereum)?;
+    let ethereum_public_key = match (&args.generate_only, args.from, ethereum_key) {
+        (Some(_), Some(from), _) => from,
+        (_, _, Some(key)) => key.to_address(),
+        _ => {
+            return Err(GravityError::UnrecoverableError(
+                "--ethereum-key or --ethereum-mnemonic is required unless --generate-only and --from are used".into(),
+            ))
+        }
+    };
     let ethereum_rpc = args.ethereum_rpc;
     let amount = args.amount;
+    let approval = match args.approve {
+        ApprovalMode::Max => Erc20Approval::Max,
+        ApprovalMode::Exact => Erc20Approval::Exact,
+        ApprovalMode::Existing => Erc20Approval::Existing,
+    };
 
     let connections = create_rpc_connections(prefix, None, Some(ethereum_rpc), TIMEOUT).await;
 
@@ -26,33 +60,100 @@ pub async fn eth_to_cosmos(args: EthToCosmosOpts, prefix: String) -> Result<(),
 
     check_for_eth(ethereum_public_key, &web3).await?;
 
-    let res = web3
-        .get_erc20_decimals(erc20_address, ethereum_public_key)
-        .await
-        .expect("Failed to query ERC20 contract");
-    let decimals: u8 = res.to_string().parse().unwrap();
-    let amount = fraction_to_exponent(amount, decimals);
+    let decimals = get_erc20_decimals(&web3, erc20_address, ethereum_public_key).await?;
+    let amount = amount.to_base_units(decimals)?;
 
-    let erc20_balance = web3
-        .get_erc20_balance(erc20_address, ethereum_public_key)
-        .await
-        .expect("Failed to get balance, check ERC20 contract address");
+    if let Some(path) = args.generate_only {
+        let txs = generate_send_to_cosmos_txs(
+            erc20_address,
+            gravity_address,
+            amount,
+            cosmos_dest,
+            ethereum_public_key,
+            args.eth,
+            approval,
+            args.revoke,
+            &web3,
+        )
+        .await?;
+        write_json(&path, &txs)?;
+        info!(
+            "Wrote {} unsigned transactions from {} to {:?}",
+            txs.len(),
+            ethereum_public_key,
+            path
+        );
+        info!("They must be signed and submitted in order, each one uses the next nonce");
+        return Ok(());
+    }
+    let ethereum_key = ethereum_key.unwrap();
 
-    if erc20_balance.is_zero() {
-        return Err(GravityError::UnrecoverableError(format!(
-            "You have zero {} tokens, please double check your sender and erc20 addresses!",
-            erc20_address
-        )));
-    } else if amount > erc20_balance {
+    // the balance and allowance are checked before wrapping so that nothing is sent if the
+    // transfer can not be made, the ETH balance is checked instead when wrapping
+    if !args.eth {
+        let erc20_balance = web3
+            .get_erc20_balance(erc20_address, ethereum_public_key)
+            .await
+            .expect("Failed to get balance, check ERC20 contract address");
+
+        if erc20_balance.is_zero() {
+            return Err(GravityError::UnrecoverableError(format!(
+                "You have zero {} tokens, please double check your sender and erc20 addresses!",
+                erc20_address
+            )));
+        } else if amount > erc20_balance {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Insufficient balance {} > {}",
+                format_token_amount(amount, decimals),
+                format_token_amount(erc20_balance, decimals)
+            )));
+        }
+    }
+
+    let allowance =
+        get_erc20_allowance(erc20_address, ethereum_public_key, gravity_address, &web3).await?;
+    info!(
+        "The Gravity contract is currently allowed to transfer {} of your tokens",
+        format_token_amount(allowance, decimals)
+    );
+    if allowance < amount && approval == Erc20Approval::Existing {
         return Err(GravityError::UnrecoverableError(format!(
-            "Insufficient balance {} > {}",
-            amount, erc20_balance
+            "The current allowance is less than the {} tokens being sent, use a different --approve mode",
+            format_token_amount(amount, decimals)
         )));
     }
 
+    if args.eth {
+        let eth_balance = web3.eth_get_balance(ethereum_public_key).await?;
+        if amount >= eth_balance {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Insufficient ETH balance {} to wrap {} and pay for gas",
+                format_token_amount(eth_balance, decimals),
+                format_token_amount(amount, decimals)
+            )));
+        }
+        info!(
+            "Wrapping {} ETH into WETH {}",
+            format_token_amount(amount, decimals),
+            erc20_address
+        );
+        match wrap_eth(amount, ethereum_key, TIMEOUT, &web3, vec![]).await {
+            Ok(tx_id) => info!("Wrapped ETH txid: {:#066x}", tx_id),
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Failed to wrap ETH, check your WETH balance before retrying! {:?}",
+                    e
+                )))
+            }
+        }
+    }
+
     info!(
         "Sending {} / {} to Cosmos from {} to {}",
-        amount, erc20_address, ethereum_public_key, cosmos_dest
+        format_token_amount(amount, decimals),
+        erc20_address,
+        ethereum_public_key,
+        cosmos_dest
     );
     // we send some erc20 tokens to the gravity contract to register a deposit
     let res = send_to_cosmos(
@@ -64,10 +165,17 @@ pub async fn eth_to_cosmos(args: EthToCosmosOpts, prefix: String) -> Result<(),
         TIMEOUT,
         &web3,
         vec![],
+        approval,
     )
     .await;
     match res {
         Ok(tx_id) => info!("Send to Cosmos txid: {:#066x}", tx_id),
+        Err(e) if args.eth => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to send tokens! Your ETH has been wrapped and is still held as WETH by {}, retry with `--token-contract-address {}` instead of `--eth` to send it. {:?}",
+                ethereum_public_key, erc20_address, e
+            )))
+        }
         Err(e) => {
             return Err(GravityError::UnrecoverableError(format!(
                 "Failed to send tokens! {:?}",
@@ -75,6 +183,30 @@ pub async fn eth_to_cosmos(args: EthToCosmosOpts, prefix: String) -> Result<(),
             )))
         }
     }
+    if args.revoke {
+        match approve_erc20_amount(
+            erc20_address,
+            gravity_address,
+            Uint256::from_u64(0),
+            ethereum_key,
+            TIMEOUT,
+            &web3,
+            vec![],
+        )
+        .await
+        {
+            Ok(tx_id) => info!(
+                "Revoked the Gravity contract allowance txid: {:#066x}",
+                tx_id
+            ),
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Your tokens were sent but the allowance could not be revoked! {:?}",
+                    e
+                )))
+            }
+        }
+    }
     info!(
         "Your tokens should show up in the account {} on Gravity Bridge within 10 minutes",
         cosmos_dest
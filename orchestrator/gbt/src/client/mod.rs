@@ -1,3 +1,8 @@
+pub mod bulk;
+pub mod bump_fee;
+pub mod cancel_send_to_eth;
 pub mod cosmos_to_eth;
 pub mod deploy_erc20_representation;
 pub mod eth_to_cosmos;
+pub mod request_batch;
+pub mod track;
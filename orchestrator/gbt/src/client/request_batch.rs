@@ -0,0 +1,80 @@
+use cosmos_gravity::{
+    query::{get_denom_to_erc20, get_pending_batch_fees},
+    send::send_request_batch,
+};
+use gravity_utils::{
+    clarity::{Address as EthAddress, Uint256},
+    connection_prep::{check_for_fee, create_rpc_connections},
+    error::GravityError,
+    web30::client::Web3,
+};
+
+use crate::{
+    args::RequestBatchOpts,
+    utils::{AmountFormatter, TIMEOUT},
+};
+
+pub async fn request_batch(
+    args: RequestBatchOpts,
+    address_prefix: String,
+) -> Result<(), GravityError> {
+    let cosmos_key = args.cosmos_phrase;
+    let cosmos_grpc = args.cosmos_grpc;
+    let denom = args.denom;
+    let fee = args.fee;
+
+    let cosmos_address = cosmos_key.to_address(&address_prefix).unwrap();
+    let connections =
+        create_rpc_connections(address_prefix, Some(cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    let erc20 = match get_denom_to_erc20(&mut grpc, denom.clone()).await {
+        Ok(v) => v.erc20.parse::<EthAddress>()?,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Could not lookup denom is it valid? {:?}",
+                e
+            )));
+        }
+    };
+
+    // batch fees are keyed by the ERC20 the denom is represented by
+    let batch_fees = get_pending_batch_fees(&mut grpc).await?.batch_fees;
+    let fees = batch_fees
+        .iter()
+        .find(|f| f.token.parse::<EthAddress>().ok() == Some(erc20));
+    match fees {
+        Some(fees) => {
+            let total_fees = Uint256::from_dec_or_hex_str_restricted(&fees.total_fees)?;
+            let web3 = Web3::new(&args.ethereum_rpc, TIMEOUT);
+            info!(
+                "{} has {} sends waiting to be batched with a total of {} {} in fees",
+                denom,
+                fees.tx_count,
+                AmountFormatter::new(web3).format(erc20, total_fees).await,
+                denom
+            )
+        }
+        None => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "{} has no sends waiting to be batched",
+                denom
+            )));
+        }
+    }
+
+    check_for_fee(&fee, cosmos_address, &contact).await?;
+    info!("Requesting a batch for {} / {}", denom, erc20);
+    match send_request_batch(cosmos_key, denom, Some(fee), &contact).await {
+        Ok(tx_id) => info!("Request batch txid {}", tx_id.txhash),
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to request batch! {:?}",
+                e
+            )))
+        }
+    }
+    info!("The batch will be created once the validators have signed it, it can then be relayed to Ethereum");
+    Ok(())
+}
@@ -0,0 +1,563 @@
+//! Follows a transfer across the bridge, reporting which step it has reached. A send to Cosmos
+//! goes from an Ethereum deposit, to validator attestations, to being credited on Cosmos. A send
+//! to Ethereum goes from the batch pool, to a batch, to batch signatures, to execution on Ethereum
+
+use cosmos_gravity::{
+    query::{
+        get_attestations, get_current_valset, get_denom_to_erc20, get_erc20_to_denom,
+        get_gravity_params, get_latest_transaction_batches, get_pending_send_to_eth,
+        get_transaction_batch_signatures,
+    },
+    utils::AttestationClaim,
+};
+use ethereum_gravity::pending_calls::decode_batch_transfers;
+use gravity_proto::{
+    cosmos_sdk_proto::cosmos::tx::v1beta1::{
+        service_client::ServiceClient as TxServiceClient, GetTxsEventRequest,
+    },
+    gravity::{query_client::QueryClient as GravityQueryClient, Attestation, MsgSendToEth, Params},
+};
+use gravity_utils::{
+    clarity::{
+        utils::{bytes_to_hex_str, hex_str_to_bytes},
+        Address as EthAddress, Uint256,
+    },
+    connection_prep::create_rpc_connections,
+    deep_space::{Address as CosmosAddress, Contact},
+    error::GravityError,
+    types::{
+        event_signatures::{SENT_TO_COSMOS_EVENT_SIG, TRANSACTION_BATCH_EXECUTED_EVENT_SIG},
+        gravity_power_to_percent, SendToCosmosEvent, TransactionBatch, Valset,
+        GRAVITY_POWER_TO_PASS,
+    },
+    web30::{client::Web3, jsonrpc::client::HttpClient, types::Log},
+};
+use prost::Message;
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use tonic::transport::Channel;
+
+use crate::{args::TrackOpts, utils::TIMEOUT};
+
+const SEND_TO_ETH_TYPE_URL: &str = "/gravity.v1.MsgSendToEth";
+const CANCEL_SEND_TO_ETH_TYPE_URL: &str = "/gravity.v1.MsgCancelSendToEth";
+
+pub async fn track(args: TrackOpts, address_prefix: String) -> Result<(), GravityError> {
+    let connections = create_rpc_connections(
+        address_prefix,
+        Some(args.cosmos_grpc.clone()),
+        Some(args.ethereum_rpc),
+        TIMEOUT,
+    )
+    .await;
+    let contact = connections.contact.unwrap();
+    let web3 = connections.web3.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    let params = get_gravity_params(&mut grpc).await?;
+    let gravity_contract_address = match args.gravity_contract_address {
+        Some(c) => c,
+        None => match params.bridge_ethereum_address.parse() {
+            Ok(c) => c,
+            Err(_) => {
+                return Err(GravityError::UnrecoverableError(
+                    "The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into(),
+                ))
+            }
+        },
+    };
+
+    match (args.eth_tx, args.send_id, args.sender) {
+        (Some(eth_tx), _, _) => {
+            track_send_to_cosmos(&web3, &contact, &mut grpc, gravity_contract_address, eth_tx).await
+        }
+        (None, Some(send_id), Some(sender)) => {
+            track_send_to_eth(
+                &web3,
+                &mut grpc,
+                &args.cosmos_grpc,
+                &params,
+                gravity_contract_address,
+                sender,
+                send_id,
+            )
+            .await
+        }
+        _ => Err(GravityError::UnrecoverableError(
+            "Either --eth-tx or --send-id and --sender are required".into(),
+        )),
+    }
+}
+
+/// Returns the hex encoded topic of an event signature, as it appears in JSON-RPC logs
+fn event_topic(signature: &str) -> String {
+    format!(
+        "0x{}",
+        bytes_to_hex_str(&Keccak256::digest(signature.as_bytes()))
+    )
+}
+
+/// Pads an address to the 32 byte topic it is indexed as
+fn address_topic(address: EthAddress) -> String {
+    format!("0x{:0>64}", &address.to_string().to_lowercase()[2..])
+}
+
+/// Gets the send to Cosmos claim for `event_nonce` from the latest attestations, along with the
+/// lowest event nonce found. Attestations for older events are pruned, so a missing
+/// attestation with a nonce below the lowest one has already been processed
+fn find_send_to_cosmos_attestation(
+    attestations: &[Attestation],
+    event_nonce: u64,
+) -> (Option<&Attestation>, Option<u64>) {
+    let mut lowest_nonce = None;
+    let mut found = None;
+    for attestation in attestations {
+        let claim = match attestation
+            .claim
+            .as_ref()
+            .and_then(AttestationClaim::decode)
+        {
+            Some(AttestationClaim::SendToCosmos(claim)) => claim,
+            _ => continue,
+        };
+        if lowest_nonce.map_or(true, |n| claim.event_nonce < n) {
+            lowest_nonce = Some(claim.event_nonce);
+        }
+        if claim.event_nonce == event_nonce {
+            found = Some(attestation);
+        }
+    }
+    (found, lowest_nonce)
+}
+
+async fn track_send_to_cosmos(
+    web3: &Web3,
+    contact: &Contact,
+    grpc: &mut GravityQueryClient<Channel>,
+    gravity_contract_address: EthAddress,
+    eth_tx: String,
+) -> Result<(), GravityError> {
+    let receipt: Value = HttpClient::new(&web3.get_url())
+        .request_method("eth_getTransactionReceipt", json!([eth_tx]), TIMEOUT)
+        .await?;
+    if receipt.is_null() {
+        info!(
+            "Transaction {} has not been included in a block yet",
+            eth_tx
+        );
+        return Ok(());
+    }
+    if receipt["status"] == "0x0" {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Transaction {} failed on Ethereum, no tokens were sent",
+            eth_tx
+        )));
+    }
+
+    let topic = event_topic(SENT_TO_COSMOS_EVENT_SIG);
+    let mut events = Vec::new();
+    for log in receipt["logs"].as_array().cloned().unwrap_or_default() {
+        let address: Option<EthAddress> = log["address"].as_str().and_then(|a| a.parse().ok());
+        if address != Some(gravity_contract_address) || log["topics"][0] != topic.as_str() {
+            continue;
+        }
+        let log: Log = match serde_json::from_value(log) {
+            Ok(log) => log,
+            Err(e) => {
+                return Err(GravityError::ValidationError(format!(
+                    "Failed to parse Ethereum log {:?}",
+                    e
+                )))
+            }
+        };
+        events.push(SendToCosmosEvent::from_log(&log)?);
+    }
+    if events.is_empty() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Transaction {} did not send any tokens to Cosmos through {}",
+            eth_tx, gravity_contract_address
+        )));
+    }
+
+    let valset = get_current_valset(grpc).await?;
+    let attestations = get_attestations(grpc, None).await?;
+    for event in events {
+        info!(
+            "Send to Cosmos of {} {} from {} to {} with event nonce {} in block {}",
+            event.amount,
+            event.erc20,
+            event.sender,
+            event.destination,
+            event.event_nonce,
+            event.block_height
+        );
+        let destination = match event.validated_destination {
+            Some(d) => d,
+            None => {
+                warn!(
+                    "The destination {} is not a valid Cosmos address, it can not be credited to an account",
+                    event.destination
+                );
+                continue;
+            }
+        };
+
+        match find_send_to_cosmos_attestation(&attestations, event.event_nonce) {
+            (Some(attestation), _) => {
+                info!(
+                    "{} of {} validators have attested to this send",
+                    attestation.votes.len(),
+                    valset.members.len()
+                );
+                if attestation.observed {
+                    info!("The send has been observed and credited to {}", destination);
+                    display_balance(contact, grpc, destination, event.erc20).await?;
+                } else {
+                    info!("Waiting for more validators to attest to the send");
+                }
+            }
+            (None, Some(lowest_nonce)) if event.event_nonce < lowest_nonce => {
+                info!(
+                    "The send is older than the latest attestations, it has been credited to {}",
+                    destination
+                );
+                display_balance(contact, grpc, destination, event.erc20).await?;
+            }
+            (None, _) => info!(
+                "No validator has attested to this send yet, validators wait for a number of block confirmations first"
+            ),
+        }
+    }
+    Ok(())
+}
+
+async fn display_balance(
+    contact: &Contact,
+    grpc: &mut GravityQueryClient<Channel>,
+    address: CosmosAddress,
+    erc20: EthAddress,
+) -> Result<(), GravityError> {
+    let denom = get_erc20_to_denom(grpc, erc20).await?.denom;
+    match contact.get_balance(address, denom.clone()).await? {
+        Some(balance) => info!("{} has a balance of {}{}", address, balance.amount, denom),
+        None => info!("{} has no {}", address, denom),
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn track_send_to_eth(
+    web3: &Web3,
+    grpc: &mut GravityQueryClient<Channel>,
+    cosmos_grpc: &str,
+    params: &Params,
+    gravity_contract_address: EthAddress,
+    sender: CosmosAddress,
+    send_id: u64,
+) -> Result<(), GravityError> {
+    let pending = get_pending_send_to_eth(grpc, sender).await?;
+    if pending.unbatched_transfers.iter().any(|t| t.id == send_id) {
+        info!(
+            "Send {} is waiting to be included in a batch, batches are requested once the fees of the waiting sends are enough to pay for relaying",
+            send_id
+        );
+        return Ok(());
+    }
+    if !pending.transfers_in_batches.iter().any(|t| t.id == send_id) {
+        return track_completed_send_to_eth(
+            web3,
+            grpc,
+            cosmos_grpc,
+            gravity_contract_address,
+            sender,
+            send_id,
+        )
+        .await;
+    }
+
+    let batches = get_latest_transaction_batches(grpc).await?;
+    let batch = match batches
+        .into_iter()
+        .find(|b| b.transactions.iter().any(|t| t.id == send_id))
+    {
+        Some(batch) => batch,
+        None => {
+            info!("Send {} is in a batch that is no longer pending", send_id);
+            return Ok(());
+        }
+    };
+    info!(
+        "Send {} is in batch {} for token {}",
+        send_id, batch.nonce, batch.token_contract
+    );
+
+    let valset = get_current_valset(grpc).await?;
+    let signatures =
+        get_transaction_batch_signatures(grpc, batch.nonce, batch.token_contract).await?;
+    let signed_power: u64 = signatures
+        .iter()
+        .map(|s| valset_power(&valset, s.ethereum_signer))
+        .sum();
+    info!(
+        "Batch {} has been signed by {:.2}% of the validator power, {:.2}% is required",
+        batch.nonce,
+        gravity_power_to_percent(signed_power),
+        gravity_power_to_percent(GRAVITY_POWER_TO_PASS)
+    );
+
+    match find_batch_execution(web3, gravity_contract_address, &batch, params).await? {
+        Some(tx_hash) => info!(
+            "Batch {} was executed on Ethereum in transaction {}, waiting for the validators to attest",
+            batch.nonce, tx_hash
+        ),
+        None => {
+            let latest_block = web3.eth_block_number().await?;
+            if latest_block > Uint256::from_u64(batch.batch_timeout) {
+                info!(
+                    "Batch {} has timed out, its sends will be returned to the batch pool",
+                    batch.nonce
+                );
+            } else {
+                info!(
+                    "Batch {} is waiting to be relayed to Ethereum, it times out at block {}",
+                    batch.nonce, batch.batch_timeout
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn valset_power(valset: &Valset, signer: EthAddress) -> u64 {
+    valset.get_bridge_validator_power(signer).unwrap_or(0)
+}
+
+/// Searches for the TransactionBatchExecutedEvent of `batch`, returning the hash of the transaction
+/// that executed it. A batch can only be executed before its timeout, so only the blocks between
+/// its creation and its timeout are searched
+async fn find_batch_execution(
+    web3: &Web3,
+    gravity_contract_address: EthAddress,
+    batch: &TransactionBatch,
+    params: &Params,
+) -> Result<Option<String>, GravityError> {
+    let timeout_blocks = params
+        .target_batch_timeout
+        .checked_div(params.average_ethereum_block_time)
+        .unwrap_or(0);
+    let from_block = batch.batch_timeout.saturating_sub(timeout_blocks);
+    get_batch_execution_tx(
+        web3,
+        gravity_contract_address,
+        batch.nonce,
+        batch.token_contract,
+        format!("{:#x}", from_block),
+        "latest".into(),
+    )
+    .await
+}
+
+/// Gets the hash of the transaction that emitted the TransactionBatchExecutedEvent of batch `nonce`
+/// of `token_contract` between `from_block` and `to_block`
+async fn get_batch_execution_tx(
+    web3: &Web3,
+    gravity_contract_address: EthAddress,
+    nonce: u64,
+    token_contract: EthAddress,
+    from_block: String,
+    to_block: String,
+) -> Result<Option<String>, GravityError> {
+    let logs: Value = HttpClient::new(&web3.get_url())
+        .request_method(
+            "eth_getLogs",
+            json!([{
+                "fromBlock": from_block,
+                "toBlock": to_block,
+                "address": gravity_contract_address.to_string(),
+                "topics": [
+                    event_topic(TRANSACTION_BATCH_EXECUTED_EVENT_SIG),
+                    format!("{:#066x}", nonce),
+                    address_topic(token_contract),
+                ],
+            }]),
+            TIMEOUT,
+        )
+        .await?;
+    Ok(logs
+        .as_array()
+        .and_then(|logs| logs.first())
+        .and_then(|log| log.get("transactionHash"))
+        .and_then(Value::as_str)
+        .map(String::from))
+}
+
+/// How a send to Ethereum that is no longer pending left the pool, as found in the Cosmos
+/// transactions of its sender
+enum SendToEthOutcome {
+    /// Cancelled in the Cosmos transaction with this hash
+    Cancelled(String),
+    /// Not cancelled, so it was paid out by a batch
+    Sent(MsgSendToEth),
+    /// The transactions could not be found, the node may have pruned them
+    Unknown,
+}
+
+/// Searches the transactions of `sender` for the messages that created and cancelled `send_id`.
+/// Both messages emit the id of the send, the message logs relate the id to a message
+async fn find_send_to_eth_outcome(
+    cosmos_grpc: &str,
+    sender: CosmosAddress,
+    send_id: u64,
+) -> Result<SendToEthOutcome, GravityError> {
+    let mut client = match TxServiceClient::connect(cosmos_grpc.to_string()).await {
+        Ok(client) => client,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to connect to {} {:?}",
+                cosmos_grpc, e
+            )))
+        }
+    };
+    let response = client
+        .get_txs_event(GetTxsEventRequest {
+            events: vec![
+                format!("message.outgoing_tx_id='{}'", send_id),
+                format!("message.sender='{}'", sender),
+            ],
+            pagination: None,
+            order_by: 0,
+        })
+        .await?
+        .into_inner();
+
+    let id = send_id.to_string();
+    let mut send = None;
+    for (tx, tx_response) in response.txs.iter().zip(response.tx_responses.iter()) {
+        let messages = match &tx.body {
+            Some(body) => &body.messages,
+            None => continue,
+        };
+        for log in tx_response.logs.iter() {
+            let has_id = log
+                .events
+                .iter()
+                .flat_map(|e| e.attributes.iter())
+                .any(|a| a.key == "outgoing_tx_id" && a.value == id);
+            let msg = match messages.get(log.msg_index as usize) {
+                Some(msg) if has_id => msg,
+                _ => continue,
+            };
+            match msg.type_url.as_str() {
+                CANCEL_SEND_TO_ETH_TYPE_URL => {
+                    return Ok(SendToEthOutcome::Cancelled(tx_response.txhash.clone()))
+                }
+                SEND_TO_ETH_TYPE_URL => send = MsgSendToEth::decode(msg.value.as_slice()).ok(),
+                _ => {}
+            }
+        }
+    }
+    Ok(match send {
+        Some(send) => SendToEthOutcome::Sent(send),
+        None => SendToEthOutcome::Unknown,
+    })
+}
+
+/// Reports where a send to Ethereum that is no longer pending went, either cancelled or paid out
+/// by an executed batch along with the Ethereum transaction that executed it
+async fn track_completed_send_to_eth(
+    web3: &Web3,
+    grpc: &mut GravityQueryClient<Channel>,
+    cosmos_grpc: &str,
+    gravity_contract_address: EthAddress,
+    sender: CosmosAddress,
+    send_id: u64,
+) -> Result<(), GravityError> {
+    let send = match find_send_to_eth_outcome(cosmos_grpc, sender, send_id).await? {
+        SendToEthOutcome::Cancelled(tx_hash) => {
+            info!(
+                "Send {} was cancelled in Cosmos transaction {}, the tokens were returned to {}",
+                send_id, tx_hash, sender
+            );
+            return Ok(());
+        }
+        SendToEthOutcome::Sent(send) => send,
+        SendToEthOutcome::Unknown => {
+            info!(
+                "Send {} from {} is no longer pending and its transaction could not be found, it has either been executed on Ethereum or was cancelled",
+                send_id, sender
+            );
+            return Ok(());
+        }
+    };
+    let (amount, fee) = match (send.amount, send.bridge_fee) {
+        (Some(amount), Some(fee)) => (amount, fee),
+        _ => {
+            return Err(GravityError::ValidationError(format!(
+                "The message that created send {} has no amount or bridge fee",
+                send_id
+            )))
+        }
+    };
+    let destination: EthAddress = send.eth_dest.parse()?;
+    let token_contract: EthAddress = get_denom_to_erc20(grpc, amount.denom.clone())
+        .await?
+        .erc20
+        .parse()?;
+    let amount_value = Uint256::from_dec_or_hex_str_restricted(&amount.amount)?;
+    let fee_value = Uint256::from_dec_or_hex_str_restricted(&fee.amount)?;
+
+    // batches on Ethereum don't include send ids, so the executed batches of the token are
+    // searched for a transfer with the destination, amount and fee of the send
+    let attestations = get_attestations(grpc, None).await?;
+    for attestation in attestations.iter().filter(|a| a.observed) {
+        let claim = match attestation
+            .claim
+            .as_ref()
+            .and_then(AttestationClaim::decode)
+        {
+            Some(AttestationClaim::BatchSendToEth(claim)) => claim,
+            _ => continue,
+        };
+        if claim.token_contract.parse::<EthAddress>().ok() != Some(token_contract) {
+            continue;
+        }
+        let block = format!("{:#x}", claim.block_height);
+        let tx_hash = match get_batch_execution_tx(
+            web3,
+            gravity_contract_address,
+            claim.batch_nonce,
+            token_contract,
+            block.clone(),
+            block,
+        )
+        .await?
+        {
+            Some(tx_hash) => tx_hash,
+            None => continue,
+        };
+        let tx: Value = HttpClient::new(&web3.get_url())
+            .request_method("eth_getTransactionByHash", json!([tx_hash]), TIMEOUT)
+            .await?;
+        let transfers = tx
+            .get("input")
+            .and_then(Value::as_str)
+            .and_then(|input| hex_str_to_bytes(input).ok())
+            .and_then(|input| decode_batch_transfers(&input))
+            .unwrap_or_default();
+        if transfers
+            .iter()
+            .any(|t| t.destination == destination && t.amount == amount_value && t.fee == fee_value)
+        {
+            info!(
+                "Send {} of {}{} to {} was paid out by batch {} in Ethereum transaction {}",
+                send_id, amount.amount, amount.denom, destination, claim.batch_nonce, tx_hash
+            );
+            return Ok(());
+        }
+    }
+    info!(
+        "Send {} of {}{} to {} was included in a batch that has been executed on Ethereum, the batch is older than the attestations kept by the chain",
+        send_id, amount.amount, amount.denom, destination
+    );
+    Ok(())
+}
@@ -3,14 +3,21 @@
 use std::{
     fs::{self, create_dir},
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use gravity_utils::{
+    clarity::PrivateKey as EthPrivateKey,
+    deep_space::{Coin, PrivateKey as CosmosPrivateKey},
     error::GravityError,
-    types::{GravityBridgeToolsConfig, TomlGravityBridgeToolsConfig},
+    types::{
+        ConnectionConfig, GravityBridgeToolsConfig, KeysConfig, RelayerConfig,
+        TomlGravityBridgeToolsConfig,
+    },
 };
+use toml::{value::Table, Value};
 
-use crate::args::InitOpts;
+use crate::{args::InitOpts, config_commands::to_inline_toml, init_wizard::run_init_wizard};
 
 /// The name of the config file, this file is copied
 /// from default-config.toml when generated so that we
@@ -18,34 +25,198 @@ use crate::args::InitOpts;
 pub const CONFIG_NAME: &str = "config.toml";
 /// The folder name for the config
 pub const CONFIG_FOLDER: &str = ".gbt";
+/// The address prefix used when none is configured
+pub const DEFAULT_ADDRESS_PREFIX: &str = "gravity";
+/// The Cosmos gRPC server used when none is configured
+pub const DEFAULT_COSMOS_GRPC: &str = "http://localhost:9090";
+/// The Ethereum RPC server used when none is configured
+pub const DEFAULT_ETHEREUM_RPC: &str = "http://localhost:8545";
 
 /// Creates the config directory and default config file if it does
-/// not already exist
-pub fn init_config(_init_ops: InitOpts, home_dir: PathBuf) -> Result<(), GravityError> {
-    if home_dir.exists() {
+/// not already exist, with --interactive the config is filled in by the init wizard
+pub async fn init_config(init_opts: InitOpts, home_dir: PathBuf) -> Result<(), GravityError> {
+    if home_dir.exists() && !init_opts.force {
         warn!(
             "The Gravity bridge tools config folder {} already exists!",
             home_dir.to_str().unwrap()
         );
-        warn!("You can delete this folder and run init again, you will lose any keys or other config data!");
-        Err(GravityError::ValidationError(
+        warn!("Run init again with --force to write a new config, the values of the existing config are kept");
+        return Err(GravityError::ValidationError(
             "Directory already exists".into(),
-        ))
-    } else {
+        ));
+    }
+    if !home_dir.exists() {
         create_dir(home_dir.clone()).expect("Failed to create config directory!");
+    }
+
+    let config_file = get_config_path(&home_dir);
+    let existing: Option<(Value, TomlGravityBridgeToolsConfig)> = if config_file.exists() {
+        let raw = fs::read_to_string(&config_file)
+            .ok()
+            .and_then(|c| toml::from_str::<Value>(&c).ok());
+        match raw.map(|raw| (raw.clone(), raw.try_into())) {
+            Some((raw, Ok(existing))) => Some((raw, existing)),
+            _ => {
+                warn!(
+                    "{:?} could not be read, none of its values are carried over",
+                    config_file
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // every value of an existing config is carried over so that --force never loses any of
+    // them, the answers to the init wizard are then set over them
+    let mut contents = get_default_config();
+    if let Some((raw, _)) = &existing {
+        contents = set_existing_values(&contents, raw);
+    }
+    if init_opts.interactive {
+        let settings = run_init_wizard(existing.as_ref().map(|(_, e)| e)).await?;
+        for (section, key, value) in settings.iter() {
+            contents = set_config_value(&contents, section, key, &to_inline_toml(value));
+        }
+    }
+    // check that the orchestrator can load the config that is written
+    let config = match toml::from_str::<TomlGravityBridgeToolsConfig>(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "The generated config is invalid {}",
+                e
+            )))
+        }
+    };
+    // a validation error exits with success, so it is reported as unrecoverable
+    if let Err(e) = config.validate() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "The generated config is invalid {}",
+            e
+        )));
+    }
+    if let (Some((_, existing)), false) = (&existing, init_opts.interactive) {
+        if *existing != config {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Could not carry over every value of {:?}, it has been left as it is",
+                config_file
+            )));
+        }
+    }
 
-        fs::write(home_dir.join(CONFIG_NAME), get_default_config())
-            .expect("Unable to write config file");
+    if config_file.exists() {
+        let backup = backup_config(&config_file)?;
+        info!("Kept the previous config as {:?}", backup);
+    }
+    fs::write(&config_file, contents).expect("Unable to write config file");
+    info!("Wrote {:?}", config_file);
+    Ok(())
+}
 
-        Ok(())
+/// Copies the config file to a backup named after the current time, so that earlier backups
+/// are never overwritten. Returns the path of the backup
+pub fn backup_config(config_file: &Path) -> Result<PathBuf, GravityError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut backup = config_file.with_extension(format!("toml.{}.bak", now));
+    let mut i = 1;
+    while backup.exists() {
+        backup = config_file.with_extension(format!("toml.{}-{}.bak", now, i));
+        i += 1;
+    }
+    match fs::copy(config_file, &backup) {
+        Ok(_) => Ok(backup),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "Failed to back up {:?} to {:?} {:?}",
+            config_file, backup, e
+        ))),
     }
 }
 
+/// Sets every value of the untyped config `raw` in `contents`, nested tables such as
+/// [relayer.valset_relaying_mode] are set as sections of their own while arrays of tables
+/// are written inline
+fn set_existing_values(contents: &str, raw: &Value) -> String {
+    fn set_table(contents: String, section: &str, table: &Table) -> String {
+        let mut contents = contents;
+        for (key, value) in table.iter() {
+            contents = match value {
+                Value::Table(nested) => {
+                    set_table(contents, &format!("{}.{}", section, key), nested)
+                }
+                value => set_config_value(&contents, section, key, &to_inline_toml(value)),
+            };
+        }
+        contents
+    }
+    let mut contents = contents.to_string();
+    for (section, value) in raw.as_table().into_iter().flatten() {
+        match value {
+            Value::Table(table) => contents = set_table(contents, section, table),
+            _ => warn!("{} is not in a section and is not carried over", section),
+        }
+    }
+    contents
+}
+
+/// Returns true if a config file line sets `key`, the line may be commented out
+fn is_config_key(line: &str, key: &str) -> bool {
+    line.split('=').next().unwrap().trim() == key
+}
+
+/// Sets `key` in `section` of a config file while keeping its comments. A commented out example
+/// of the key is replaced by the value and a missing key is added below the section header,
+/// `value` must already be formatted as toml
+pub fn set_config_value(contents: &str, section: &str, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = contents.lines().map(|l| l.to_string()).collect();
+    let header = format!("[{}]", section);
+    let line = format!("{} = {}", key, value);
+    let start = match lines
+        .iter()
+        .position(|l| l.split('#').next().unwrap().trim() == header)
+    {
+        Some(start) => start + 1,
+        None => {
+            lines.push(String::new());
+            lines.push(header);
+            lines.push(line);
+            return lines.join("\n") + "\n";
+        }
+    };
+    let end = lines[start..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map(|i| start + i)
+        .unwrap_or_else(|| lines.len());
+    let section_lines = &lines[start..end];
+
+    let set = section_lines
+        .iter()
+        .position(|l| !l.trim_start().starts_with('#') && is_config_key(l, key));
+    // commented examples are only replaced above the first commented out table, below it they
+    // are examples of another table or of an alternative to this one
+    let example = section_lines
+        .iter()
+        .take_while(|l| !l.trim_start().starts_with("# ["))
+        .position(|l| match l.trim_start().strip_prefix('#') {
+            Some(l) => is_config_key(l, key),
+            None => false,
+        });
+    match set.or(example) {
+        Some(i) => lines[start + i] = line,
+        None => lines.insert(start, line),
+    }
+    lines.join("\n") + "\n"
+}
+
 /// Loads the default config from the default-config.toml file
 /// done at compile time and is included in the binary
 /// This is done so that we can have hand edited and annotated
 /// config
-fn get_default_config() -> String {
+pub fn get_default_config() -> String {
     include_str!("default-config.toml").to_string()
 }
 
@@ -61,10 +232,99 @@ pub fn get_home_dir(home_arg: Option<PathBuf>) -> Result<PathBuf, GravityError>
     }
 }
 
+/// Reads a key file referenced by the [keys] section of the config
+fn read_key_file(path: &Path) -> Result<String, GravityError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.trim().to_string()),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "Failed to read key file {:?} {:?}",
+            path, e
+        ))),
+    }
+}
+
+/// Returns the Cosmos key given with --cosmos-phrase or GBT_COSMOS_PHRASE, otherwise the key
+/// read from `cosmos_phrase_file`
+pub fn resolve_cosmos_key(
+    key: Option<CosmosPrivateKey>,
+    config: &KeysConfig,
+) -> Result<Option<CosmosPrivateKey>, GravityError> {
+    match (key, &config.cosmos_phrase_file) {
+        (Some(key), _) => Ok(Some(key)),
+        (None, Some(path)) => match read_key_file(path)?.parse() {
+            Ok(key) => Ok(Some(key)),
+            Err(e) => Err(GravityError::UnrecoverableError(format!(
+                "{:?} does not hold a valid Cosmos phrase {:?}",
+                path, e
+            ))),
+        },
+        (None, None) => Ok(None),
+    }
+}
+
+/// Returns the Ethereum keys given with flags or their GBT_* environment variables, otherwise the
+/// keys read from `ethereum_key_file`
+pub fn resolve_ethereum_keys(
+    keys: Vec<EthPrivateKey>,
+    config: &KeysConfig,
+) -> Result<Vec<EthPrivateKey>, GravityError> {
+    if !keys.is_empty() {
+        return Ok(keys);
+    }
+    let path = match &config.ethereum_key_file {
+        Some(path) => path,
+        None => return Ok(Vec::new()),
+    };
+    let mut keys = Vec::new();
+    for line in read_key_file(path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.parse() {
+            Ok(key) => keys.push(key),
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "{:?} holds an invalid Ethereum private key {:?}",
+                    path, e
+                )))
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Returns the fees given with --fees or GBT_FEES, otherwise the fees in the config
+pub fn resolve_fees(
+    fees: Option<Coin>,
+    config: &ConnectionConfig,
+) -> Result<Option<Coin>, GravityError> {
+    match (fees, &config.fees) {
+        (Some(fees), _) => Ok(Some(fees)),
+        (None, Some(fees)) => match fees.parse() {
+            Ok(fees) => Ok(Some(fees)),
+            Err(e) => Err(GravityError::UnrecoverableError(format!(
+                "Invalid fees {} in the config {:?}",
+                fees, e
+            ))),
+        },
+        (None, None) => Ok(None),
+    }
+}
+
+/// The path of the config file in the config folder
+pub fn get_config_path(home_dir: &Path) -> PathBuf {
+    home_dir.join(CONFIG_NAME)
+}
+
 /// Load the config file, this operates at runtime
 pub fn load_config(home_dir: &Path) -> Result<GravityBridgeToolsConfig, GravityError> {
-    let config_file = home_dir.join(CONFIG_FOLDER).with_file_name(CONFIG_NAME);
+    let config_file = get_config_path(home_dir);
     if !config_file.exists() {
+        info!(
+            "No config found at {:?}, using the default config. Run `gbt init` to create one",
+            config_file
+        );
         return Ok(GravityBridgeToolsConfig::default());
     }
 
@@ -72,7 +332,13 @@ pub fn load_config(home_dir: &Path) -> Result<GravityBridgeToolsConfig, GravityE
         fs::read_to_string(config_file).expect("Could not find config file! Run `gbt init`");
     let val: Result<TomlGravityBridgeToolsConfig, _> = toml::from_str(&config);
     match val {
-        Ok(v) => Ok(v.into()),
+        Ok(v) => match v.validate() {
+            Ok(()) => Ok(v.into()),
+            Err(e) => Err(GravityError::UnrecoverableError(format!(
+                "Invalid config! {:?}",
+                e
+            ))),
+        },
         Err(e) => Err(GravityError::UnrecoverableError(format!(
             "Invalid config! {:?}",
             e
@@ -80,8 +346,36 @@ pub fn load_config(home_dir: &Path) -> Result<GravityBridgeToolsConfig, GravityE
     }
 }
 
+/// Loads the [relayer] section of the config file while the relayer is running, returning an
+/// error rather than panicking if the section is invalid
+pub fn load_relayer_config(home_dir: &Path) -> Result<RelayerConfig, GravityError> {
+    let config_file = get_config_path(home_dir);
+    let config = match fs::read_to_string(&config_file) {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read {:?} {:?}",
+                config_file, e
+            )))
+        }
+    };
+    let val: TomlGravityBridgeToolsConfig = match toml::from_str(&config) {
+        Ok(val) => val,
+        Err(e) => {
+            return Err(GravityError::ValidationError(format!(
+                "Invalid config! {:?}",
+                e
+            )))
+        }
+    };
+    val.relayer.validate()?;
+    Ok(val.relayer.into())
+}
+
 #[cfg(test)]
 mod tests {
+    use gravity_utils::types::{BatchRelayingMode, BatchRequestMode, ValsetRelayingMode};
+
     use super::*;
 
     /// Test that the config is both valid toml for the struct and that it's values are
@@ -93,4 +387,161 @@ mod tests {
         let res: GravityBridgeToolsConfig = res.into();
         assert_eq!(res, GravityBridgeToolsConfig::default());
     }
+
+    /// Test parsing of the token allowlist, denylist and per token overrides
+    #[test]
+    fn test_token_overrides() {
+        let config = r#"
+        [relayer]
+        token_allowlist = ["0x6Bd41fCdF129297c3524395d669c0865b3CA85B2", "0x0000000000000000000000000000000000000001"]
+        token_denylist = ["0x0000000000000000000000000000000000000001"]
+
+        [[relayer.token_overrides]]
+        token = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+        margin = 1.5
+        batch_request_mode = "EveryBatch"
+        "#;
+        let res: TomlGravityBridgeToolsConfig = toml::from_str(config).unwrap();
+        let res: GravityBridgeToolsConfig = res.into();
+        let allowed = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+            .parse()
+            .unwrap();
+        let denied = "0x0000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let unlisted = "0x0000000000000000000000000000000000000002"
+            .parse()
+            .unwrap();
+
+        assert!(res.relayer.is_token_allowed(allowed));
+        assert!(!res.relayer.is_token_allowed(denied));
+        assert!(!res.relayer.is_token_allowed(unlisted));
+        assert_eq!(
+            res.relayer.get_batch_request_mode(allowed),
+            BatchRequestMode::EveryBatch
+        );
+        assert_eq!(
+            res.relayer.get_batch_request_mode(denied),
+            BatchRequestMode::None
+        );
+        assert_eq!(
+            res.relayer.get_batch_relaying_mode(allowed),
+            BatchRelayingMode::ProfitableOnly { margin: 1.5 }
+        );
+    }
+
+    /// Test parsing of the connection and keys sections and that flags take precedence over them
+    #[test]
+    fn test_connection_config() {
+        let config = r#"
+        [connection]
+        cosmos_grpc = "http://grpc.example.com:9090"
+        fees = "100ugraviton"
+        gravity_contract_address = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+
+        [keys]
+        cosmos_phrase_file = "/run/secrets/cosmos_phrase"
+        "#;
+        let res: TomlGravityBridgeToolsConfig = toml::from_str(config).unwrap();
+        let res: GravityBridgeToolsConfig = res.into();
+        assert_eq!(
+            res.connection.cosmos_grpc,
+            Some("http://grpc.example.com:9090".to_string())
+        );
+        assert_eq!(res.connection.ethereum_rpc, None);
+        assert_eq!(
+            res.keys.cosmos_phrase_file,
+            Some(PathBuf::from("/run/secrets/cosmos_phrase"))
+        );
+        assert_eq!(res.keys.ethereum_key_file, None);
+
+        let fees = resolve_fees(None, &res.connection).unwrap().unwrap();
+        assert_eq!(fees.denom, "ugraviton");
+        let flag: Coin = "5stake".parse().unwrap();
+        let fees = resolve_fees(Some(flag), &res.connection).unwrap().unwrap();
+        assert_eq!(fees.denom, "stake");
+    }
+
+    /// Test that values set into the default config are parsed and keep the config valid
+    #[test]
+    fn test_set_config_value() {
+        let mut contents = get_default_config();
+        contents = set_config_value(
+            &contents,
+            "connection",
+            "cosmos_grpc",
+            "\"http://grpc.example.com:9090\"",
+        );
+        contents = set_config_value(
+            &contents,
+            "relayer.valset_relaying_mode",
+            "mode",
+            "\"ProfitableOnly\"",
+        );
+        contents = set_config_value(&contents, "relayer.valset_relaying_mode", "margin", "1.5");
+        contents = set_config_value(&contents, "metrics", "metrics_enabled", "true");
+        let res: TomlGravityBridgeToolsConfig = toml::from_str(&contents).unwrap();
+        res.validate().unwrap();
+        let res: GravityBridgeToolsConfig = res.into();
+        assert_eq!(
+            res.connection.cosmos_grpc,
+            Some("http://grpc.example.com:9090".to_string())
+        );
+        assert_eq!(
+            res.relayer.valset_relaying_mode,
+            ValsetRelayingMode::ProfitableOnly { margin: 1.5 }
+        );
+        assert!(res.metrics.metrics_enabled);
+    }
+
+    /// Test that every value of an existing config, including tables that are commented out in
+    /// the default config, is carried over to a new config
+    #[test]
+    fn test_set_existing_values() {
+        let config = r#"
+        [connection]
+        cosmos_grpc = "http://grpc.example.com:9090"
+        fees = "100ugraviton"
+
+        [keys]
+        ethereum_key_file = "/run/secrets/ethereum_key"
+
+        [orchestrator]
+        relayer_enabled = true
+
+        [relayer]
+        batch_request_mode = "EveryBatch"
+        max_relay_jitter = 12
+        token_allowlist = ["0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"]
+
+        [relayer.valset_relaying_mode]
+        mode = "ProfitableOnly"
+        margin = 1.5
+
+        [relayer.batch_relaying_mode]
+        mode = "ProfitableWithWhitelist"
+        margin = 1.2
+        [[relayer.batch_relaying_mode.whitelist]]
+        token = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+        amount = "50000"
+
+        [[relayer.token_overrides]]
+        token = "0x6Bd41fCdF129297c3524395d669c0865b3CA85B2"
+        margin = 2.0
+
+        [relayer.budget]
+        daily_limit = "1000000000000000000"
+        max_consecutive_losses = 5
+
+        [metrics]
+        metrics_enabled = true
+        metrics_bind = "0.0.0.0:6631"
+        "#;
+        let existing: TomlGravityBridgeToolsConfig = toml::from_str(config).unwrap();
+        let raw: Value = toml::from_str(config).unwrap();
+        let contents = set_existing_values(&get_default_config(), &raw);
+        let res: TomlGravityBridgeToolsConfig = toml::from_str(&contents).unwrap();
+        res.validate().unwrap();
+        assert_eq!(res, existing);
+    }
 }
@@ -0,0 +1,399 @@
+//! The `gbt config` commands. These read the config file themselves rather than through
+//! `load_config` so that they can point out what is wrong with a config that does not load
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use gravity_utils::{error::GravityError, types::TomlGravityBridgeToolsConfig};
+use toml::Value;
+use url::Url;
+
+use crate::{
+    args::ConfigUpgradeOpts,
+    config::{
+        backup_config, get_config_path, get_default_config, resolve_fees, DEFAULT_ADDRESS_PREFIX,
+        DEFAULT_COSMOS_GRPC, DEFAULT_ETHEREUM_RPC,
+    },
+};
+
+/// The keys of the [connection] section along with the environment variable that overrides
+/// each of them and the default used when neither is set
+const CONNECTION_KEYS: [(&str, &str, Option<&str>); 5] = [
+    (
+        "address_prefix",
+        "GBT_ADDRESS_PREFIX",
+        Some(DEFAULT_ADDRESS_PREFIX),
+    ),
+    ("cosmos_grpc", "GBT_COSMOS_GRPC", Some(DEFAULT_COSMOS_GRPC)),
+    (
+        "ethereum_rpc",
+        "GBT_ETHEREUM_RPC",
+        Some(DEFAULT_ETHEREUM_RPC),
+    ),
+    ("fees", "GBT_FEES", None),
+    (
+        "gravity_contract_address",
+        "GBT_GRAVITY_CONTRACT_ADDRESS",
+        None,
+    ),
+];
+
+/// The sections shown by `gbt config show` after the [connection] section
+const SECTIONS: [&str; 4] = ["keys", "orchestrator", "relayer", "metrics"];
+
+/// Reads the config file as an untyped toml value, along with its path and contents
+fn read_config(home_dir: &Path) -> Result<(PathBuf, String, Value), GravityError> {
+    let config_file = get_config_path(home_dir);
+    if !config_file.exists() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "No config found at {:?}, run `gbt init` to create one",
+            config_file
+        )));
+    }
+    let contents = match fs::read_to_string(&config_file) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read {:?} {:?}",
+                config_file, e
+            )))
+        }
+    };
+    match toml::from_str(&contents) {
+        Ok(raw) => Ok((config_file, contents, raw)),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "{:?} is not valid toml {}",
+            config_file, e
+        ))),
+    }
+}
+
+/// Converts the untyped config into the config struct, reporting values of the wrong type
+fn parse_config(
+    config_file: &Path,
+    raw: Value,
+) -> Result<TomlGravityBridgeToolsConfig, GravityError> {
+    match raw.try_into() {
+        Ok(config) => Ok(config),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "{:?} has an invalid value {}",
+            config_file, e
+        ))),
+    }
+}
+
+/// Formats a toml value on a single line, tables are written as inline tables
+pub fn to_inline_toml(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        Value::Integer(i) => i.to_string(),
+        // debug formatting keeps the decimal point of whole numbers, so they remain floats
+        Value::Float(f) => format!("{:?}", f),
+        Value::Boolean(b) => b.to_string(),
+        Value::Datetime(d) => d.to_string(),
+        Value::Array(array) => format!(
+            "[{}]",
+            array
+                .iter()
+                .map(to_inline_toml)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Table(table) if table.is_empty() => "{}".to_string(),
+        Value::Table(table) => format!(
+            "{{ {} }}",
+            table
+                .iter()
+                .map(|(k, v)| format!("{} = {}", k, to_inline_toml(v)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Returns true if the untyped config sets `key` in `section`
+fn has_key(raw: &Value, section: &str, key: &str) -> bool {
+    raw.get(section).and_then(|s| s.get(key)).is_some()
+}
+
+/// Lists the sections and keys of the config file that are not part of the config struct,
+/// serde ignores these so a misspelled key silently falls back to its default
+fn unknown_keys(raw: &Value, config: &TomlGravityBridgeToolsConfig) -> Vec<String> {
+    // every key the config struct recognized is serialized back, other than unset options
+    // which can not have been in the file
+    let known = Value::try_from(config).expect("The config can always be serialized");
+    let mut unknown = Vec::new();
+    let raw = match raw.as_table() {
+        Some(raw) => raw,
+        None => return unknown,
+    };
+    for (section, value) in raw.iter() {
+        let known_section = match known.get(section) {
+            Some(known_section) => known_section,
+            None => {
+                unknown.push(section.clone());
+                continue;
+            }
+        };
+        if let (Some(table), Some(known_table)) = (value.as_table(), known_section.as_table()) {
+            for key in table.keys() {
+                if !known_table.contains_key(key) {
+                    unknown.push(format!("{}.{}", section, key));
+                }
+            }
+        }
+    }
+    unknown
+}
+
+/// Checks that an endpoint in the [connection] section is a url
+fn check_url(name: &str, url: &Option<String>) -> Result<(), GravityError> {
+    match url {
+        Some(url) => match Url::parse(url) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GravityError::ValidationError(format!(
+                "{} {} is not a valid url {:?}",
+                name, url, e
+            ))),
+        },
+        None => Ok(()),
+    }
+}
+
+pub fn validate_config(home_dir: &Path) -> Result<(), GravityError> {
+    let (config_file, _, raw) = read_config(home_dir)?;
+    let config = parse_config(&config_file, raw.clone())?;
+
+    for key in unknown_keys(&raw, &config) {
+        warn!("{} is not a known config key and is ignored", key);
+    }
+    // key files are often mounted secrets that only exist where the orchestrator runs
+    for path in [
+        &config.keys.cosmos_phrase_file,
+        &config.keys.ethereum_key_file,
+    ]
+    .iter()
+    .filter_map(|p| p.as_ref())
+    {
+        if !path.exists() {
+            warn!("The key file {:?} does not exist on this machine", path);
+        }
+    }
+
+    let checks = vec![
+        config.relayer.validate(),
+        config.metrics.validate(),
+        check_url("cosmos_grpc", &config.connection.cosmos_grpc),
+        check_url("ethereum_rpc", &config.connection.ethereum_rpc),
+        resolve_fees(None, &config.connection).map(|_| ()),
+    ];
+    let errors: Vec<GravityError> = checks.into_iter().filter_map(Result::err).collect();
+    for e in errors.iter() {
+        error!("{:?}", e);
+    }
+    if !errors.is_empty() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{:?} has {} invalid values",
+            config_file,
+            errors.len()
+        )));
+    }
+    info!("{:?} is valid", config_file);
+    Ok(())
+}
+
+pub fn show_config(home_dir: &Path, address_prefix: Option<String>) -> Result<(), GravityError> {
+    let config_file = get_config_path(home_dir);
+    let (raw, config) = if config_file.exists() {
+        let (_, _, raw) = read_config(home_dir)?;
+        let config = parse_config(&config_file, raw.clone())?;
+        (raw, config)
+    } else {
+        warn!("No config found at {:?}, showing the defaults", config_file);
+        (
+            Value::Table(Default::default()),
+            TomlGravityBridgeToolsConfig::default(),
+        )
+    };
+    info!("Effective config for {:?}", config_file);
+
+    info!("[connection]");
+    for (key, env_var, default) in CONNECTION_KEYS.iter() {
+        let from_env = match (*key, &address_prefix) {
+            // --address-prefix is a global flag, so it is the only flag that applies here
+            ("address_prefix", Some(prefix)) if env::var(env_var).ok().as_ref() != Some(prefix) => {
+                Some((prefix.clone(), "--address-prefix".to_string()))
+            }
+            _ => env::var(env_var).ok().map(|v| (v, env_var.to_string())),
+        };
+        match (
+            from_env,
+            raw.get("connection").and_then(|c| c.get(*key)),
+            default,
+        ) {
+            (Some((value, origin)), _, _) => info!(
+                "{} = {} # {}",
+                key,
+                to_inline_toml(&Value::String(value)),
+                origin
+            ),
+            (None, Some(value), _) => info!("{} = {} # config", key, to_inline_toml(value)),
+            (None, None, Some(default)) => info!(
+                "{} = {} # default",
+                key,
+                to_inline_toml(&Value::String(default.to_string()))
+            ),
+            (None, None, None) => info!("{} is not set", key),
+        }
+    }
+
+    let effective = Value::try_from(&config).expect("The config can always be serialized");
+    for section in SECTIONS.iter() {
+        info!("[{}]", section);
+        let table = match effective.get(*section).and_then(Value::as_table) {
+            Some(table) => table,
+            None => continue,
+        };
+        for (key, value) in table.iter() {
+            let origin = if has_key(&raw, section, key) {
+                "config"
+            } else {
+                "default"
+            };
+            info!("{} = {} # {}", key, to_inline_toml(value), origin);
+        }
+    }
+    Ok(())
+}
+
+/// Adds every key of the default config that `contents` is missing, keys of existing sections
+/// are inserted below the section header so that the rest of the file is left as it is.
+/// Returns the new contents along with the keys that were added
+fn add_missing_keys(contents: &str, raw: &Value) -> Result<(String, Vec<String>), GravityError> {
+    let defaults: Value =
+        toml::from_str(&get_default_config()).expect("The default config is valid toml");
+    let mut lines: Vec<String> = contents.lines().map(|l| l.to_string()).collect();
+    let mut added = Vec::new();
+
+    for (section, default_section) in defaults.as_table().unwrap().iter() {
+        let default_section = match default_section.as_table() {
+            Some(default_section) => default_section,
+            None => continue,
+        };
+        let missing: Vec<String> = default_section
+            .iter()
+            .filter(|(key, _)| !has_key(raw, section, key))
+            .map(|(key, value)| format!("{} = {}", key, to_inline_toml(value)))
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+        added.extend(
+            default_section
+                .keys()
+                .filter(|key| !has_key(raw, section, key))
+                .map(|key| format!("{}.{}", section, key)),
+        );
+
+        let header = format!("[{}]", section);
+        let mut new_lines = vec!["# Added by gbt config upgrade".to_string()];
+        new_lines.extend(missing);
+        match lines
+            .iter()
+            .position(|l| l.split('#').next().unwrap().trim() == header)
+        {
+            Some(i) => {
+                lines.splice(i + 1..i + 1, new_lines);
+            }
+            None => {
+                lines.push(String::new());
+                lines.push(header);
+                lines.extend(new_lines);
+            }
+        }
+    }
+
+    let upgraded = lines.join("\n") + "\n";
+    if let Err(e) = toml::from_str::<TomlGravityBridgeToolsConfig>(&upgraded) {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Could not add {} to the config automatically, add them by hand {}",
+            added.join(", "),
+            e
+        )));
+    }
+    Ok((upgraded, added))
+}
+
+pub fn upgrade_config(opts: ConfigUpgradeOpts, home_dir: &Path) -> Result<(), GravityError> {
+    let (config_file, contents, raw) = read_config(home_dir)?;
+    let (upgraded, added) = add_missing_keys(&contents, &raw)?;
+    if added.is_empty() {
+        info!("{:?} already has every config key", config_file);
+        return Ok(());
+    }
+    for key in added.iter() {
+        info!("Adding {} with its default value", key);
+    }
+    if opts.dry_run {
+        return Ok(());
+    }
+
+    let backup = backup_config(&config_file)?;
+    if let Err(e) = fs::write(&config_file, upgraded) {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Failed to write {:?} {:?}",
+            config_file, e
+        )));
+    }
+    info!(
+        "Upgraded {:?}, the previous config was kept as {:?}",
+        config_file, backup
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gravity_utils::types::{BatchRequestMode, GravityBridgeToolsConfig, RelayerConfig};
+
+    use super::*;
+
+    /// Test that upgrading an old config adds the missing keys with their defaults and keeps
+    /// the values that were already set
+    #[test]
+    fn test_add_missing_keys() {
+        let config = r#"
+[relayer]
+batch_request_mode = "EveryBatch"
+unknown_key = 5
+
+[metrics]
+metrics_enabled = true
+"#;
+        let raw: Value = toml::from_str(config).unwrap();
+        let parsed: TomlGravityBridgeToolsConfig = toml::from_str(config).unwrap();
+        assert_eq!(unknown_keys(&raw, &parsed), vec!["relayer.unknown_key"]);
+
+        let (upgraded, added) = add_missing_keys(config, &raw).unwrap();
+        assert!(added.contains(&"relayer.max_relay_jitter".to_string()));
+        assert!(added.contains(&"relayer.valset_relaying_mode".to_string()));
+        assert!(added.contains(&"orchestrator.relayer_enabled".to_string()));
+        assert!(added.contains(&"metrics.metrics_bind".to_string()));
+        assert!(!added.contains(&"metrics.metrics_enabled".to_string()));
+
+        let res: TomlGravityBridgeToolsConfig = toml::from_str(&upgraded).unwrap();
+        let res: GravityBridgeToolsConfig = res.into();
+        assert_eq!(res.relayer.batch_request_mode, BatchRequestMode::EveryBatch);
+        assert_eq!(
+            res.relayer.batch_relaying_mode,
+            RelayerConfig::default().batch_relaying_mode
+        );
+        assert!(res.metrics.metrics_enabled);
+
+        let raw: Value = toml::from_str(&upgraded).unwrap();
+        let (_, added) = add_missing_keys(&upgraded, &raw).unwrap();
+        assert!(added.is_empty());
+    }
+}
@@ -0,0 +1,491 @@
+//! Builds airdrop proposals from a CSV file of recipients and verifies existing airdrop proposals
+//! against one, so that the recipient list of a proposal can be audited before voting on it
+
+use std::{collections::HashMap, fs, path::Path};
+
+use cosmos_gravity::proposals::{
+    decode_airdrop_recipients, AirdropProposalJsonUnparsed, AIRDROP_PROPOSAL_TYPE_URL,
+};
+use gravity_proto::gravity::AirdropProposal;
+use gravity_utils::{
+    clarity::Uint256,
+    connection_prep::create_rpc_connections,
+    deep_space::{Address as CosmosAddress, Contact},
+    error::GravityError,
+};
+use prost::Message;
+
+use crate::{
+    args::{AirdropBuildOpts, AirdropVerifyOpts},
+    tx::offline::write_json,
+    utils::TIMEOUT,
+};
+
+/// Parses the `address,amount` rows of an airdrop CSV, checking that every address is a valid
+/// address of this chain and every amount is a positive integer. Returns every invalid row as an
+/// error
+fn parse_airdrop_csv(
+    contents: &str,
+    prefix: &str,
+) -> Result<Vec<(CosmosAddress, u64)>, Vec<String>> {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields[0].eq_ignore_ascii_case("address") {
+            continue;
+        }
+        if fields.len() != 2 {
+            errors.push(format!("Line {}: expected address and amount", line_number));
+            continue;
+        }
+        let address = match fields[0].parse::<CosmosAddress>() {
+            Ok(address) if fields[0].starts_with(&format!("{}1", prefix)) => address,
+            Ok(_) => {
+                errors.push(format!(
+                    "Line {}: {} is not a {} address",
+                    line_number, fields[0], prefix
+                ));
+                continue;
+            }
+            Err(e) => {
+                errors.push(format!("Line {}: {} {:?}", line_number, fields[0], e));
+                continue;
+            }
+        };
+        match fields[1].parse::<u64>() {
+            Ok(amount) if amount > 0 => rows.push((address, amount)),
+            _ => errors.push(format!(
+                "Line {}: {} is not a positive integer amount",
+                line_number, fields[1]
+            )),
+        }
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Reads the rows of an airdrop CSV file, see `parse_airdrop_csv`
+fn read_airdrop_csv(path: &Path, prefix: &str) -> Result<Vec<(CosmosAddress, u64)>, GravityError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read {:?} {:?}",
+                path, e
+            )))
+        }
+    };
+    match parse_airdrop_csv(&contents, prefix) {
+        Ok(rows) => Ok(rows),
+        Err(errors) => {
+            for error in errors.iter() {
+                error!("{}", error);
+            }
+            Err(GravityError::UnrecoverableError(format!(
+                "{:?} has {} invalid rows",
+                path,
+                errors.len()
+            )))
+        }
+    }
+}
+
+/// Merges the amounts of duplicate recipients, keeping the order in which each recipient first
+/// appears. Returns the merged recipients and the number of duplicates
+fn merge_recipients(
+    recipients: Vec<(CosmosAddress, u64)>,
+) -> Result<(Vec<(CosmosAddress, u64)>, usize), GravityError> {
+    let mut merged: Vec<(CosmosAddress, u64)> = Vec::new();
+    let mut index = HashMap::new();
+    let mut duplicates = 0;
+    for (address, amount) in recipients {
+        match index.get(&address.to_string()).copied() {
+            Some(i) => {
+                let total = &mut merged[i].1;
+                *total = match total.checked_add(amount) {
+                    Some(total) => total,
+                    None => {
+                        return Err(GravityError::ValidationError(format!(
+                            "The total amount for {} overflows",
+                            address
+                        )))
+                    }
+                };
+                duplicates += 1;
+            }
+            None => {
+                index.insert(address.to_string(), merged.len());
+                merged.push((address, amount));
+            }
+        }
+    }
+    Ok((merged, duplicates))
+}
+
+fn total_amount(recipients: &[(CosmosAddress, u64)]) -> u128 {
+    recipients.iter().map(|(_, amount)| *amount as u128).sum()
+}
+
+pub async fn build_airdrop(opts: AirdropBuildOpts, prefix: String) -> Result<(), GravityError> {
+    let rows = read_airdrop_csv(&opts.csv, &prefix)?;
+    if rows.is_empty() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{:?} has no recipients",
+            opts.csv
+        )));
+    }
+    let (recipients, duplicates) = merge_recipients(rows)?;
+    if duplicates > 0 {
+        warn!(
+            "Merged {} duplicate rows, each recipient receives the sum of their rows",
+            duplicates
+        );
+    }
+
+    let total = total_amount(&recipients);
+    let mut amounts: Vec<u64> = recipients.iter().map(|(_, amount)| *amount).collect();
+    amounts.sort_unstable();
+    info!("Recipients: {}", recipients.len());
+    info!("Total: {}{}", total, opts.denom);
+    info!(
+        "Smallest: {}{} Median: {}{} Largest: {}{}",
+        amounts[0],
+        opts.denom,
+        amounts[amounts.len() / 2],
+        opts.denom,
+        amounts[amounts.len() - 1],
+        opts.denom
+    );
+
+    let connections = create_rpc_connections(prefix, Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let pool = contact.query_community_pool().await?;
+    let available = pool
+        .iter()
+        .find(|c| c.denom == opts.denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(|| Uint256::from_u64(0));
+    info!("The community pool holds {}{}", available, opts.denom);
+    if Uint256::from_u128(total) > available {
+        return Err(GravityError::UnrecoverableError(format!(
+            "The airdrop total of {}{} is more than the community pool holds, the proposal would fail when executed",
+            total, opts.denom
+        )));
+    }
+
+    let proposal = AirdropProposalJsonUnparsed {
+        title: opts.title,
+        denom: opts.denom,
+        description: opts.description,
+        amounts: recipients.iter().map(|(_, amount)| *amount).collect(),
+        recipients: recipients.iter().map(|(a, _)| a.to_string()).collect(),
+    };
+    write_json(&opts.output, &proposal)?;
+    info!(
+        "Wrote the proposal to {:?}, submit it with `gbt gov submit airdrop`",
+        opts.output
+    );
+    Ok(())
+}
+
+/// Finds an airdrop proposal on chain by id, the proposal must be in its voting period or have
+/// passed. Returns the denom and recipients of the airdrop
+async fn get_airdrop_proposal(
+    contact: &Contact,
+    proposal_id: u64,
+    prefix: &str,
+) -> Result<(String, Vec<(CosmosAddress, u64)>), GravityError> {
+    let mut proposals = contact
+        .get_governance_proposals_in_voting_period()
+        .await?
+        .proposals;
+    proposals.extend(contact.get_passed_governance_proposals().await?.proposals);
+    let content = match proposals
+        .into_iter()
+        .find(|p| p.proposal_id == proposal_id)
+        .and_then(|p| p.content)
+    {
+        Some(content) => content,
+        None => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Proposal {} is not in its voting period and has not passed",
+                proposal_id
+            )))
+        }
+    };
+    if content.type_url != AIRDROP_PROPOSAL_TYPE_URL {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Proposal {} is a {} not an airdrop",
+            proposal_id, content.type_url
+        )));
+    }
+    let airdrop = match AirdropProposal::decode(content.value.as_slice()) {
+        Ok(airdrop) => airdrop,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to decode proposal {} {:?}",
+                proposal_id, e
+            )))
+        }
+    };
+    info!("Verifying proposal {} {}", proposal_id, airdrop.title);
+    let recipients = decode_airdrop_recipients(&airdrop, prefix)?;
+    Ok((airdrop.denom, recipients))
+}
+
+/// Reads a local airdrop proposal.json, returning the denom and recipients of the airdrop
+fn read_airdrop_proposal(path: &Path) -> Result<(String, Vec<(CosmosAddress, u64)>), GravityError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read {:?} {:?}",
+                path, e
+            )))
+        }
+    };
+    let proposal: AirdropProposalJsonUnparsed = match serde_json::from_str(&contents) {
+        Ok(proposal) => proposal,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to deserialize {:?}, check the contents! {:?}",
+                path, e
+            )))
+        }
+    };
+    if proposal.recipients.len() != proposal.amounts.len() {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{:?} has {} recipients but {} amounts",
+            path,
+            proposal.recipients.len(),
+            proposal.amounts.len()
+        )));
+    }
+    info!("Verifying {:?} {}", path, proposal.title);
+    let mut recipients = Vec::new();
+    for (recipient, amount) in proposal.recipients.iter().zip(proposal.amounts) {
+        recipients.push((recipient.parse()?, amount));
+    }
+    Ok((proposal.denom, recipients))
+}
+
+/// Logs every difference between the recipients listed in the CSV and those of the proposal,
+/// returning the number of differences
+fn compare_recipients(
+    expected: &[(CosmosAddress, u64)],
+    actual: &[(CosmosAddress, u64)],
+    denom: &str,
+) -> usize {
+    let mut differences = 0;
+    let proposed: HashMap<String, u64> = actual
+        .iter()
+        .map(|(address, amount)| (address.to_string(), *amount))
+        .collect();
+    let listed: HashMap<String, u64> = expected
+        .iter()
+        .map(|(address, amount)| (address.to_string(), *amount))
+        .collect();
+    for (address, amount) in expected.iter() {
+        match proposed.get(&address.to_string()) {
+            Some(proposed) if proposed == amount => {}
+            Some(proposed) => {
+                differences += 1;
+                warn!(
+                    "{} receives {}{} in the proposal but {}{} in the CSV",
+                    address, proposed, denom, amount, denom
+                );
+            }
+            None => {
+                differences += 1;
+                warn!(
+                    "{} is missing from the proposal, the CSV gives it {}{}",
+                    address, amount, denom
+                );
+            }
+        }
+    }
+    for (address, amount) in actual.iter() {
+        if !listed.contains_key(&address.to_string()) {
+            differences += 1;
+            warn!(
+                "{} receives {}{} in the proposal but is not in the CSV",
+                address, amount, denom
+            );
+        }
+    }
+    differences
+}
+
+pub async fn verify_airdrop(opts: AirdropVerifyOpts, prefix: String) -> Result<(), GravityError> {
+    let (expected, _) = merge_recipients(read_airdrop_csv(&opts.csv, &prefix)?)?;
+    let (denom, recipients) = match (opts.proposal_id, opts.json) {
+        (Some(proposal_id), _) => {
+            let connections =
+                create_rpc_connections(prefix.clone(), Some(opts.cosmos_grpc), None, TIMEOUT).await;
+            let contact = connections.contact.unwrap();
+            get_airdrop_proposal(&contact, proposal_id, &prefix).await?
+        }
+        (None, Some(path)) => read_airdrop_proposal(&path)?,
+        (None, None) => {
+            return Err(GravityError::UnrecoverableError(
+                "Either --proposal-id or --json is required".into(),
+            ))
+        }
+    };
+    let (actual, duplicates) = merge_recipients(recipients)?;
+    if duplicates > 0 {
+        warn!(
+            "The proposal lists {} recipients more than once",
+            duplicates
+        );
+    }
+
+    let differences = compare_recipients(&expected, &actual, &denom);
+
+    info!(
+        "The proposal airdrops {}{} to {} recipients, the CSV lists {}{} to {} recipients",
+        total_amount(&actual),
+        denom,
+        actual.len(),
+        total_amount(&expected),
+        denom,
+        expected.len()
+    );
+    if differences > 0 {
+        return Err(GravityError::UnrecoverableError(format!(
+            "The proposal does not match the CSV, found {} differences",
+            differences
+        )));
+    }
+    info!("The proposal matches the CSV");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use gravity_utils::deep_space::PrivateKey as CosmosPrivateKey;
+
+    use super::*;
+
+    fn address(seed: u8, prefix: &str) -> CosmosAddress {
+        CosmosPrivateKey::from_secret(&[seed; 32])
+            .to_address(prefix)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_airdrop_csv() {
+        let (a, b) = (address(1, "gravity"), address(2, "gravity"));
+        let csv = format!("address,amount\n# a comment\n\n{},100\n {} , 200 \n", a, b);
+        let rows = parse_airdrop_csv(&csv, "gravity").unwrap();
+        assert_eq!(rows, vec![(a, 100), (b, 200)]);
+    }
+
+    #[test]
+    fn test_parse_airdrop_csv_errors() {
+        let a = address(1, "gravity");
+        let csv = format!(
+            "{a},100\n{a}\n{a},100,5\n{a},0\n{a},-5\n{a},1.5\nnotanaddress,100\n{},100\n",
+            address(2, "cosmos"),
+            a = a
+        );
+        let errors = parse_airdrop_csv(&csv, "gravity").unwrap_err();
+        assert_eq!(errors.len(), 7);
+        assert!(errors[0].starts_with("Line 2: expected address and amount"));
+        assert!(errors[1].starts_with("Line 3: expected address and amount"));
+        assert!(errors[2].starts_with("Line 4: 0 is not a positive integer"));
+        assert!(errors[3].starts_with("Line 5: -5 is not a positive integer"));
+        assert!(errors[4].starts_with("Line 6: 1.5 is not a positive integer"));
+        assert!(errors[5].starts_with("Line 7: notanaddress"));
+        assert!(errors[6].ends_with("is not a gravity address"));
+    }
+
+    #[test]
+    fn test_merge_recipients() {
+        let (a, b) = (address(1, "gravity"), address(2, "gravity"));
+        let (merged, duplicates) =
+            merge_recipients(vec![(a, 100), (b, 200), (a, 50), (a, 1)]).unwrap();
+        assert_eq!(merged, vec![(a, 151), (b, 200)]);
+        assert_eq!(duplicates, 2);
+        assert!(merge_recipients(vec![(a, u64::MAX), (a, 1)]).is_err());
+    }
+
+    #[test]
+    fn test_compare_recipients() {
+        let (a, b, c) = (
+            address(1, "gravity"),
+            address(2, "gravity"),
+            address(3, "gravity"),
+        );
+        let expected = vec![(a, 100), (b, 200)];
+        assert_eq!(
+            compare_recipients(&expected, &[(b, 200), (a, 100)], "ugraviton"),
+            0
+        );
+        // a different amount, a missing recipient and an extra recipient
+        assert_eq!(compare_recipients(&expected, &[(a, 101)], "ugraviton"), 2);
+        assert_eq!(
+            compare_recipients(&expected, &[(a, 100), (b, 200), (c, 1)], "ugraviton"),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_airdrop() {
+        let (a, b) = (address(1, "gravity"), address(2, "gravity"));
+        let dir = temp_dir().join(format!("gbt-airdrop-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("airdrop.csv");
+        let json = dir.join("proposal.json");
+        let verify = |csv: &Path, json: &Path| {
+            verify_airdrop(
+                AirdropVerifyOpts {
+                    cosmos_grpc: String::new(),
+                    csv: csv.to_path_buf(),
+                    proposal_id: None,
+                    json: Some(json.to_path_buf()),
+                },
+                "gravity".to_string(),
+            )
+        };
+        let write_proposal = |recipients: &[(CosmosAddress, u64)]| {
+            write_json(
+                &json,
+                &AirdropProposalJsonUnparsed {
+                    title: "Airdrop".into(),
+                    description: "Test airdrop".into(),
+                    denom: "ugraviton".into(),
+                    recipients: recipients.iter().map(|(a, _)| a.to_string()).collect(),
+                    amounts: recipients.iter().map(|(_, amount)| *amount).collect(),
+                },
+            )
+            .unwrap();
+        };
+
+        // duplicate rows in the CSV are merged before comparing
+        fs::write(&csv, format!("{},100\n{},200\n{},50\n", a, b, a)).unwrap();
+        write_proposal(&[(b, 200), (a, 150)]);
+        verify(&csv, &json).await.unwrap();
+
+        write_proposal(&[(a, 150)]);
+        assert!(verify(&csv, &json).await.is_err());
+
+        // a CSV with an address of another chain is rejected
+        fs::write(&csv, format!("{},100\n", address(1, "cosmos"))).unwrap();
+        write_proposal(&[(a, 100)]);
+        assert!(verify(&csv, &json).await.is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,312 @@
+//! Builds IBC metadata proposals from the IBC denom trace of a token. The exponent of the display
+//! unit becomes the decimals of the ERC20 deployed by `deploy_erc20_representation`, so the
+//! metadata is validated before the proposal is written
+
+use cosmos_gravity::proposals::{
+    submit_ibc_metadata_proposal, DenomUnitJson, IbcMetadataProposalJson, MetadataJson,
+};
+use gravity_proto::{
+    cosmos_sdk_proto::ibc::applications::transfer::v1::{
+        query_client::QueryClient as IbcTransferQueryClient, DenomTrace, QueryDenomTraceRequest,
+    },
+    gravity::QueryDenomToErc20Request,
+};
+use gravity_utils::{connection_prep::create_rpc_connections, error::GravityError};
+
+use crate::{args::IbcMetadataBuildOpts, tx::offline::write_json, utils::TIMEOUT};
+
+/// The largest sensible exponent of a display unit, ERC20 tooling generally assumes at most 18
+/// decimals
+const MAX_EXPONENT: u32 = 18;
+
+/// Proposes a display unit and exponent from a base denom using the common Cosmos unit prefixes,
+/// uatom is displayed as atom with an exponent of 6 and aevmos as evmos with an exponent of 18.
+/// This is only a guess from the name of the denom
+fn derive_display_unit(base_denom: &str) -> Option<(String, u32)> {
+    let exponent = match base_denom.chars().next()? {
+        'u' => 6,
+        'a' => 18,
+        _ => return None,
+    };
+    match &base_denom[1..] {
+        "" => None,
+        display => Some((display.to_string(), exponent)),
+    }
+}
+
+/// Checks that the metadata of an IBC token can be used to deploy an ERC20, the base unit must be
+/// the IBC denom with an exponent of zero and the display unit must exist with a sensible exponent
+fn validate_ibc_metadata(metadata: &MetadataJson, ibc_denom: &str) -> Result<(), GravityError> {
+    if metadata.base != ibc_denom {
+        return Err(GravityError::ValidationError(format!(
+            "The base unit {} must be the IBC denom {}",
+            metadata.base, ibc_denom
+        )));
+    }
+    if !metadata
+        .denom_units
+        .iter()
+        .any(|u| u.denom == metadata.base && u.exponent == 0)
+    {
+        return Err(GravityError::ValidationError(format!(
+            "The base unit {} must have an exponent of 0",
+            metadata.base
+        )));
+    }
+    match metadata
+        .denom_units
+        .iter()
+        .find(|u| u.denom == metadata.display)
+    {
+        Some(unit) if unit.exponent == 0 || unit.exponent > MAX_EXPONENT => {
+            Err(GravityError::ValidationError(format!(
+                "The display unit {} has an exponent of {}, it must be between 1 and {}",
+                unit.denom, unit.exponent, MAX_EXPONENT
+            )))
+        }
+        Some(_) => Ok(()),
+        None => Err(GravityError::ValidationError(format!(
+            "The display unit {} is not one of the denom units",
+            metadata.display
+        ))),
+    }
+}
+
+/// Gets the path and base denom of an IBC denom of the form ibc/<hash>
+async fn get_denom_trace(grpc_url: &str, ibc_denom: &str) -> Result<DenomTrace, GravityError> {
+    let hash = match ibc_denom.strip_prefix("ibc/") {
+        Some(hash) => hash,
+        None => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "{} is not an IBC denom, IBC denoms are of the form ibc/<hash>",
+                ibc_denom
+            )))
+        }
+    };
+    let mut client = match IbcTransferQueryClient::connect(grpc_url.to_string()).await {
+        Ok(client) => client,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to connect to {} {:?}",
+                grpc_url, e
+            )))
+        }
+    };
+    let res = client
+        .denom_trace(QueryDenomTraceRequest {
+            hash: hash.to_string(),
+        })
+        .await?;
+    match res.into_inner().denom_trace {
+        Some(trace) => Ok(trace),
+        None => Err(GravityError::UnrecoverableError(format!(
+            "No denom trace found for {}",
+            ibc_denom
+        ))),
+    }
+}
+
+pub async fn build_ibc_metadata(
+    opts: IbcMetadataBuildOpts,
+    prefix: String,
+) -> Result<(), GravityError> {
+    let ibc_denom = opts.ibc_denom;
+    let trace = get_denom_trace(&opts.cosmos_grpc, &ibc_denom).await?;
+    info!(
+        "{} is {} received over {}",
+        ibc_denom, trace.base_denom, trace.path
+    );
+
+    let connections = create_rpc_connections(prefix, Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    match contact.get_denom_metadata(ibc_denom.clone()).await {
+        Ok(Some(metadata)) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "{} already has metadata with display unit {}",
+                ibc_denom, metadata.display
+            )))
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Unable to make metadata request, check grpc {:?}",
+                e
+            )))
+        }
+    }
+    let res = grpc
+        .denom_to_erc20(QueryDenomToErc20Request {
+            denom: ibc_denom.clone(),
+        })
+        .await;
+    if let Ok(val) = res {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Asset {} already has ERC20 representation {}",
+            ibc_denom,
+            val.into_inner().erc20
+        )));
+    }
+
+    // the exponent becomes the decimals of the ERC20 and can not be changed once it is deployed,
+    // so a derived exponent is only suggested and must be confirmed with --exponent
+    let derived = derive_display_unit(&trace.base_denom);
+    let exponent = match (opts.exponent, &derived) {
+        (Some(exponent), _) => exponent,
+        (None, Some((_, derived_exponent))) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "The exponent of {} is probably {} from its prefix, check the decimals of the token on its origin chain and confirm with --exponent",
+                trace.base_denom, derived_exponent
+            )))
+        }
+        (None, None) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Could not derive a display unit from {}, specify --display and --exponent",
+                trace.base_denom
+            )))
+        }
+    };
+    let display = match (opts.display, derived) {
+        (Some(display), _) => display,
+        (None, Some((derived_display, _))) => derived_display,
+        (None, None) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Could not derive a display unit from {}, specify --display",
+                trace.base_denom
+            )))
+        }
+    };
+    let name = opts.name.unwrap_or_else(|| display.clone());
+    let symbol = opts.symbol.unwrap_or_else(|| display.to_uppercase());
+
+    let metadata = MetadataJson {
+        description: format!("{} received over IBC through {}", name, trace.path),
+        denom_units: vec![
+            DenomUnitJson {
+                denom: ibc_denom.clone(),
+                exponent: 0,
+                aliases: vec![trace.base_denom.clone()],
+            },
+            DenomUnitJson {
+                denom: display.clone(),
+                exponent,
+                aliases: Vec::new(),
+            },
+        ],
+        base: ibc_denom.clone(),
+        display: display.clone(),
+        name: name.clone(),
+        symbol: symbol.clone(),
+    };
+    if let Err(e) = validate_ibc_metadata(&metadata, &ibc_denom) {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Invalid metadata, check --display and --exponent {:?}",
+            e
+        )));
+    }
+    info!("Name: {} Symbol: {}", name, symbol);
+    info!(
+        "1 {} = 10^{} {}, the ERC20 will have {} decimals",
+        display, exponent, trace.base_denom, exponent
+    );
+
+    let proposal = IbcMetadataProposalJson {
+        title: format!("Set metadata for {}", symbol),
+        description: format!(
+            "Sets the metadata of {} ({} from {}) so that it can be sent to Ethereum as an ERC20 with {} decimals",
+            ibc_denom, trace.base_denom, trace.path, exponent
+        ),
+        metadata,
+        ibc_denom,
+    };
+    write_json(&opts.output, &proposal)?;
+    info!("Wrote the proposal to {:?}", opts.output);
+
+    if let (Some(key), Some(deposit), Some(fees)) = (opts.cosmos_phrase, opts.deposit, opts.fees) {
+        match submit_ibc_metadata_proposal(
+            proposal.into(),
+            deposit,
+            fees,
+            &contact,
+            key,
+            Some(TIMEOUT),
+        )
+        .await
+        {
+            Ok(r) => info!("Successfully submitted proposal with txid {}", r.txhash),
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Failed to submit proposal with {:?}",
+                    e
+                )))
+            }
+        }
+    } else {
+        info!("Submit it with `gbt gov submit ibc-metadata`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IBC_DENOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    fn metadata(base: &str, display: &str, units: &[(&str, u32)]) -> MetadataJson {
+        MetadataJson {
+            description: "Atom from the Cosmos Hub".into(),
+            denom_units: units
+                .iter()
+                .map(|(denom, exponent)| DenomUnitJson {
+                    denom: denom.to_string(),
+                    exponent: *exponent,
+                    aliases: vec![],
+                })
+                .collect(),
+            base: base.into(),
+            display: display.into(),
+            name: "Atom".into(),
+            symbol: "ATOM".into(),
+        }
+    }
+
+    #[test]
+    fn test_derive_display_unit() {
+        assert_eq!(derive_display_unit("uatom"), Some(("atom".into(), 6)));
+        assert_eq!(derive_display_unit("aevmos"), Some(("evmos".into(), 18)));
+        // no known prefix, or nothing after it
+        assert_eq!(derive_display_unit("stake"), None);
+        assert_eq!(derive_display_unit("u"), None);
+        assert_eq!(derive_display_unit("a"), None);
+        assert_eq!(derive_display_unit(""), None);
+    }
+
+    #[test]
+    fn test_validate_ibc_metadata() {
+        let valid = metadata(IBC_DENOM, "atom", &[(IBC_DENOM, 0), ("atom", 6)]);
+        validate_ibc_metadata(&valid, IBC_DENOM).unwrap();
+        let max = metadata(IBC_DENOM, "atom", &[(IBC_DENOM, 0), ("atom", 18)]);
+        validate_ibc_metadata(&max, IBC_DENOM).unwrap();
+
+        // the base unit is not the IBC denom
+        let wrong_base = metadata("uatom", "atom", &[("uatom", 0), ("atom", 6)]);
+        assert!(validate_ibc_metadata(&wrong_base, IBC_DENOM).is_err());
+        // the base unit is missing or has a non zero exponent
+        let no_base_unit = metadata(IBC_DENOM, "atom", &[("atom", 6)]);
+        assert!(validate_ibc_metadata(&no_base_unit, IBC_DENOM).is_err());
+        let base_exponent = metadata(IBC_DENOM, "atom", &[(IBC_DENOM, 6), ("atom", 6)]);
+        assert!(validate_ibc_metadata(&base_exponent, IBC_DENOM).is_err());
+        // the display unit is missing
+        let no_display = metadata(IBC_DENOM, "atom", &[(IBC_DENOM, 0), ("matom", 3)]);
+        assert!(validate_ibc_metadata(&no_display, IBC_DENOM).is_err());
+        // the display unit has an exponent of 0 or more than 18
+        let zero = metadata(IBC_DENOM, "atom", &[(IBC_DENOM, 0), ("atom", 0)]);
+        assert!(validate_ibc_metadata(&zero, IBC_DENOM).is_err());
+        let display_is_base = metadata(IBC_DENOM, IBC_DENOM, &[(IBC_DENOM, 0)]);
+        assert!(validate_ibc_metadata(&display_is_base, IBC_DENOM).is_err());
+        let too_large = metadata(IBC_DENOM, "atom", &[(IBC_DENOM, 0), ("atom", 19)]);
+        assert!(validate_ibc_metadata(&too_large, IBC_DENOM).is_err());
+    }
+}
@@ -1,2 +1,5 @@
+pub mod airdrop;
+pub mod ibc_metadata;
+pub mod oracle_unhalt;
 pub mod proposals;
 pub mod queries;
@@ -0,0 +1,238 @@
+//! Builds oracle unhalt proposals. The oracle halts when validators attest to conflicting claims for
+//! the same event nonce and neither claim reaches consensus, resetting the oracle to the last event
+//! that reached consensus lets every orchestrator attest to the following events again
+
+use std::collections::HashMap;
+
+use cosmos_gravity::{
+    proposals::UnhaltBridgeProposalJson,
+    query::{
+        get_attestations, get_current_valset, get_delegate_keys_by_eth,
+        get_last_event_nonce_for_validator,
+    },
+    utils::AttestationClaim,
+};
+use gravity_utils::{
+    connection_prep::create_rpc_connections,
+    deep_space::Address as CosmosAddress,
+    error::GravityError,
+    types::{gravity_power_to_percent, GRAVITY_POWER_TO_PASS},
+};
+
+use crate::{args::OracleUnhaltBuildOpts, tx::offline::write_json, utils::TIMEOUT};
+
+/// A claim attested to by validators, several claims with the same event nonce are a dispute
+struct Claim {
+    kind: &'static str,
+    event_nonce: u64,
+    eth_block_height: u64,
+    observed: bool,
+    /// The power of the validators that voted for the claim
+    power: u64,
+}
+
+/// A validator of the current validator set along with the last event it attested to
+struct Oracle {
+    validator: String,
+    power: u64,
+    last_event_nonce: u64,
+}
+
+/// Selects the event the oracle should be reset to, which is the last event that reached
+/// consensus. The event after it must have at least two conflicting claims, otherwise the oracle
+/// is not halted and validators that are behind only need to catch up. Returns the last observed
+/// claim and the claims for the disputed event
+fn select_reset_claim(claims: &[Claim]) -> Result<(&Claim, Vec<&Claim>), GravityError> {
+    let last_observed = match last_observed_claim(claims) {
+        Some(claim) => claim,
+        None => {
+            return Err(GravityError::UnrecoverableError(
+                "No observed events were found in the latest attestations, the reset nonce must be determined manually".into(),
+            ))
+        }
+    };
+    let disputed_nonce = last_observed.event_nonce + 1;
+    let disputed: Vec<&Claim> = claims
+        .iter()
+        .filter(|c| c.event_nonce == disputed_nonce)
+        .collect();
+    if disputed.len() < 2 {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Event {} has {} claims, the oracle is not halted by conflicting claims",
+            disputed_nonce,
+            disputed.len()
+        )));
+    }
+    Ok((last_observed, disputed))
+}
+
+fn last_observed_claim(claims: &[Claim]) -> Option<&Claim> {
+    claims
+        .iter()
+        .filter(|c| c.observed)
+        .max_by_key(|c| c.event_nonce)
+}
+
+pub async fn build_oracle_unhalt(
+    opts: OracleUnhaltBuildOpts,
+    prefix: String,
+) -> Result<(), GravityError> {
+    let connections =
+        create_rpc_connections(prefix.clone(), Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let mut grpc = connections.grpc.unwrap();
+
+    let valset = get_current_valset(&mut grpc).await?;
+    let mut oracles = Vec::new();
+    for member in valset.members.iter() {
+        let keys = get_delegate_keys_by_eth(&mut grpc, member.eth_address).await?;
+        let orchestrator: CosmosAddress = keys.orchestrator_address.parse()?;
+        let last_event_nonce =
+            get_last_event_nonce_for_validator(&mut grpc, orchestrator, prefix.clone()).await?;
+        oracles.push(Oracle {
+            validator: keys.validator_address,
+            power: member.power,
+            last_event_nonce,
+        });
+    }
+    let powers: HashMap<&str, u64> = oracles
+        .iter()
+        .map(|o| (o.validator.as_str(), o.power))
+        .collect();
+
+    let mut claims = Vec::new();
+    for attestation in get_attestations(&mut grpc, None).await? {
+        let claim = match attestation
+            .claim
+            .as_ref()
+            .and_then(AttestationClaim::decode)
+        {
+            Some(claim) => claim,
+            None => continue,
+        };
+        claims.push(Claim {
+            kind: claim.kind(),
+            event_nonce: claim.event_nonce(),
+            eth_block_height: claim.block_height(),
+            observed: attestation.observed,
+            power: attestation
+                .votes
+                .iter()
+                .filter_map(|v| powers.get(v.as_str()))
+                .sum(),
+        });
+    }
+
+    for oracle in oracles.iter() {
+        info!(
+            "Validator {} with {:.2}% of the power last attested to event {}",
+            oracle.validator,
+            gravity_power_to_percent(oracle.power),
+            oracle.last_event_nonce
+        );
+    }
+    let (last_observed, disputed) = match select_reset_claim(&claims) {
+        Ok(selected) => selected,
+        Err(e) => {
+            if let Some(last_observed) = last_observed_claim(&claims) {
+                let next_nonce = last_observed.event_nonce + 1;
+                let waiting_power: u64 = oracles
+                    .iter()
+                    .filter(|o| o.last_event_nonce < next_nonce)
+                    .map(|o| o.power)
+                    .sum();
+                warn!(
+                    "{:.2}% of the validator power has yet to attest to event {}, an unhalt proposal will not help validators that are behind",
+                    gravity_power_to_percent(waiting_power),
+                    next_nonce
+                );
+            }
+            return Err(e);
+        }
+    };
+    let target_nonce = last_observed.event_nonce;
+    let disputed_nonce = target_nonce + 1;
+    info!(
+        "The last event to reach consensus is {} {} from Ethereum block {}",
+        last_observed.kind, target_nonce, last_observed.eth_block_height
+    );
+    for claim in disputed.iter() {
+        info!(
+            "Event {} has a {} claim from Ethereum block {} attested to by {:.2}% of the validator power, {:.2}% is required",
+            disputed_nonce,
+            claim.kind,
+            claim.eth_block_height,
+            gravity_power_to_percent(claim.power),
+            gravity_power_to_percent(GRAVITY_POWER_TO_PASS)
+        );
+    }
+    let max_nonce = claims
+        .iter()
+        .map(|c| c.event_nonce)
+        .max()
+        .unwrap_or(disputed_nonce);
+
+    let proposal = UnhaltBridgeProposalJson {
+        title: format!("Reset the oracle to event {}", target_nonce),
+        description: format!(
+            "Validators attested to {} conflicting claims for event {} and none reached consensus. This resets the oracle to event {}, the last event that reached consensus, so that validators attest to event {} and onwards again",
+            disputed.len(),
+            disputed_nonce,
+            target_nonce,
+            disputed_nonce
+        ),
+        target_nonce,
+    };
+    write_json(&opts.output, &proposal)?;
+    info!("Wrote the proposal to {:?}", opts.output);
+    info!(
+        "Once it passes every validator's last event nonce is reset to {} and the unobserved events {} to {} are rolled back",
+        target_nonce, disputed_nonce, max_nonce
+    );
+    info!(
+        "Each orchestrator's oracle detects the reset when its last event nonce goes backwards, it then searches Ethereum for the block of event {} and replays every event after it",
+        target_nonce
+    );
+    info!("Validators that submitted a wrong claim must fix their Ethereum node before the proposal passes or they will attest to the same claim again");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(event_nonce: u64, eth_block_height: u64, observed: bool) -> Claim {
+        Claim {
+            kind: "send to Cosmos",
+            event_nonce,
+            eth_block_height,
+            observed,
+            power: 0,
+        }
+    }
+
+    #[test]
+    fn test_select_reset_claim() {
+        // event 3 is disputed by two claims from different blocks
+        let claims = vec![
+            claim(1, 100, true),
+            claim(2, 101, true),
+            claim(3, 102, false),
+            claim(3, 103, false),
+            claim(4, 104, false),
+        ];
+        let (last_observed, disputed) = select_reset_claim(&claims).unwrap();
+        assert_eq!(last_observed.event_nonce, 2);
+        assert_eq!(disputed.len(), 2);
+        assert!(disputed.iter().all(|c| c.event_nonce == 3));
+
+        // a single claim for the next event is not a dispute
+        let claims = vec![claim(1, 100, true), claim(2, 101, false)];
+        assert!(select_reset_claim(&claims).is_err());
+        // nothing after the last observed event
+        let claims = vec![claim(1, 100, true), claim(2, 101, true)];
+        assert!(select_reset_claim(&claims).is_err());
+        // no observed event to reset to
+        let claims = vec![claim(1, 100, false), claim(1, 101, false)];
+        assert!(select_reset_claim(&claims).is_err());
+    }
+}
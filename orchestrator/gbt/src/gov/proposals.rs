@@ -1,20 +1,49 @@
 use std::{convert::TryInto, fs};
 
-use cosmos_gravity::proposals::{
-    submit_airdrop_proposal, submit_ibc_metadata_proposal, submit_pause_bridge_proposal,
-    submit_unhalt_bridge_proposal, AirdropProposalJsonUnparsed, IbcMetadataProposalJson,
-    PauseBridgeProposalJson, UnhaltBridgeProposalJson,
+use cosmos_gravity::{
+    offline::encode_gov_proposal,
+    proposals::{
+        current_param_value, encode_airdrop_proposal, gravity_param_change_proposal,
+        pause_bridge_proposal, submit_airdrop_proposal, submit_ibc_metadata_proposal,
+        submit_parameter_change_proposal, submit_pause_bridge_proposal,
+        submit_unhalt_bridge_proposal, AirdropProposalJsonUnparsed, IbcMetadataProposalJson,
+        ParamChangeProposalJson, PauseBridgeProposalJson, UnhaltBridgeProposalJson,
+        IBC_METADATA_PROPOSAL_TYPE_URL, PARAMETER_CHANGE_PROPOSAL_TYPE_URL,
+        UNHALT_BRIDGE_PROPOSAL_TYPE_URL,
+    },
+    query::get_gravity_params,
+};
+use gravity_proto::gravity::{IbcMetadataProposal, UnhaltBridgeProposal};
+use gravity_utils::{
+    connection_prep::create_rpc_connections,
+    deep_space::{utils::encode_any, Coin, Contact, PrivateKey as CosmosPrivateKey},
+    error::GravityError,
 };
-use gravity_utils::{connection_prep::create_rpc_connections, error::GravityError};
+use prost_types::Any;
 
 use crate::{
     args::{
-        AirdropProposalOpts, EmergencyBridgeHaltProposalOpts, IbcMetadataProposalOpts,
-        OracleUnhaltProposalOpts,
+        AirdropProposalOpts, EmergencyBridgeHaltProposalOpts, GenerateOnlyOpts,
+        IbcMetadataProposalOpts, OracleUnhaltProposalOpts, ParamChangeProposalOpts,
     },
+    tx::offline::{sender_address, write_unsigned_tx},
     utils::TIMEOUT,
 };
 
+/// Writes an unsigned transaction submitting a proposal of `content`, used with --generate-only
+async fn write_unsigned_proposal(
+    contact: &Contact,
+    content: Any,
+    deposit: Coin,
+    fees: Coin,
+    cosmos_phrase: Option<CosmosPrivateKey>,
+    offline: &GenerateOnlyOpts,
+) -> Result<(), GravityError> {
+    let proposer = sender_address(cosmos_phrase, offline, &contact.get_prefix())?;
+    let msg = encode_gov_proposal(content, deposit, proposer);
+    write_unsigned_tx(contact, offline, vec![msg], fees).await
+}
+
 pub async fn submit_ibc_metadata(
     opts: IbcMetadataProposalOpts,
     prefix: String,
@@ -27,12 +56,27 @@ pub async fn submit_ibc_metadata(
             let proposal: Result<IbcMetadataProposalJson, _> = serde_json::from_str(&file_contents);
             match proposal {
                 Ok(proposal_json) => {
+                    if opts.offline.generate_only.is_some() {
+                        let content = encode_any(
+                            IbcMetadataProposal::from(proposal_json),
+                            IBC_METADATA_PROPOSAL_TYPE_URL.to_string(),
+                        );
+                        return write_unsigned_proposal(
+                            &contact,
+                            content,
+                            opts.deposit,
+                            opts.fees,
+                            opts.cosmos_phrase,
+                            &opts.offline,
+                        )
+                        .await;
+                    }
                     let res = submit_ibc_metadata_proposal(
                         proposal_json.into(),
                         opts.deposit,
                         opts.fees,
                         &contact,
-                        opts.cosmos_phrase,
+                        opts.cosmos_phrase.unwrap(),
                         Some(TIMEOUT),
                     )
                     .await;
@@ -74,6 +118,22 @@ pub async fn submit_airdrop(opts: AirdropProposalOpts, prefix: String) -> Result
                 serde_json::from_str(&file_contents);
             match proposal {
                 Ok(proposal_json) => {
+                    if opts.offline.generate_only.is_some() {
+                        let content = encode_airdrop_proposal(
+                            proposal_json
+                                .try_into()
+                                .expect("Invalid address in proposal.json"),
+                        );
+                        return write_unsigned_proposal(
+                            &contact,
+                            content,
+                            opts.deposit,
+                            opts.fees,
+                            opts.cosmos_phrase,
+                            &opts.offline,
+                        )
+                        .await;
+                    }
                     let res = submit_airdrop_proposal(
                         proposal_json
                             .try_into()
@@ -81,7 +141,7 @@ pub async fn submit_airdrop(opts: AirdropProposalOpts, prefix: String) -> Result
                         opts.deposit,
                         opts.fees,
                         &contact,
-                        opts.cosmos_phrase,
+                        opts.cosmos_phrase.unwrap(),
                         Some(TIMEOUT),
                     )
                     .await;
@@ -125,12 +185,27 @@ pub async fn submit_emergency_bridge_halt(
             let proposal: Result<PauseBridgeProposalJson, _> = serde_json::from_str(&file_contents);
             match proposal {
                 Ok(proposal_json) => {
+                    if opts.offline.generate_only.is_some() {
+                        let content = encode_any(
+                            pause_bridge_proposal(proposal_json),
+                            PARAMETER_CHANGE_PROPOSAL_TYPE_URL.to_string(),
+                        );
+                        return write_unsigned_proposal(
+                            &contact,
+                            content,
+                            opts.deposit,
+                            opts.fees,
+                            opts.cosmos_phrase,
+                            &opts.offline,
+                        )
+                        .await;
+                    }
                     let res = submit_pause_bridge_proposal(
                         proposal_json,
                         opts.deposit,
                         opts.fees,
                         &contact,
-                        opts.cosmos_phrase,
+                        opts.cosmos_phrase.unwrap(),
                         Some(TIMEOUT),
                     )
                     .await;
@@ -175,12 +250,27 @@ pub async fn submit_oracle_unhalt(
                 serde_json::from_str(&file_contents);
             match proposal {
                 Ok(proposal_json) => {
+                    if opts.offline.generate_only.is_some() {
+                        let content = encode_any(
+                            UnhaltBridgeProposal::from(proposal_json),
+                            UNHALT_BRIDGE_PROPOSAL_TYPE_URL.to_string(),
+                        );
+                        return write_unsigned_proposal(
+                            &contact,
+                            content,
+                            opts.deposit,
+                            opts.fees,
+                            opts.cosmos_phrase,
+                            &opts.offline,
+                        )
+                        .await;
+                    }
                     let res = submit_unhalt_bridge_proposal(
                         proposal_json.into(),
                         opts.deposit,
                         opts.fees,
                         &contact,
-                        opts.cosmos_phrase,
+                        opts.cosmos_phrase.unwrap(),
                         Some(TIMEOUT),
                     )
                     .await;
@@ -211,3 +301,90 @@ pub async fn submit_oracle_unhalt(
     }
     Ok(())
 }
+
+pub async fn submit_param_change(
+    opts: ParamChangeProposalOpts,
+    prefix: String,
+) -> Result<(), GravityError> {
+    let connections = create_rpc_connections(prefix, Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let mut grpc = connections.grpc.unwrap();
+
+    let file_contents = match fs::read_to_string(opts.json) {
+        Ok(file_contents) => file_contents,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read your proposal.json check the file path! {:?}",
+                e
+            )))
+        }
+    };
+    let proposal_json: ParamChangeProposalJson = match serde_json::from_str(&file_contents) {
+        Ok(proposal_json) => proposal_json,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to deserialize your proposal.json, check the contents! {:?}",
+                e
+            )))
+        }
+    };
+    let proposal = match gravity_param_change_proposal(proposal_json) {
+        Ok(proposal) => proposal,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Invalid parameter change in your proposal.json! {:?}",
+                e
+            )))
+        }
+    };
+
+    let params = get_gravity_params(&mut grpc).await?;
+    info!(
+        "Proposal {} changes the following parameters",
+        proposal.title
+    );
+    for change in proposal.changes.iter() {
+        match current_param_value(&params, &change.key) {
+            Some(current) if current == change.value => {
+                warn!("{} is already {}", change.key, current)
+            }
+            Some(current) => info!("{}: {} -> {}", change.key, current, change.value),
+            None => info!("{}: unknown -> {}", change.key, change.value),
+        }
+    }
+    if opts.dry_run {
+        return Ok(());
+    }
+
+    if opts.offline.generate_only.is_some() {
+        let content = encode_any(proposal, PARAMETER_CHANGE_PROPOSAL_TYPE_URL.to_string());
+        return write_unsigned_proposal(
+            &contact,
+            content,
+            opts.deposit,
+            opts.fees,
+            opts.cosmos_phrase,
+            &opts.offline,
+        )
+        .await;
+    }
+    match submit_parameter_change_proposal(
+        proposal,
+        opts.deposit,
+        opts.fees,
+        &contact,
+        opts.cosmos_phrase.unwrap(),
+        Some(TIMEOUT),
+    )
+    .await
+    {
+        Ok(r) => info!("Successfully submitted proposal with txid {}", r.txhash),
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to submit proposal with {:?}",
+                e
+            )))
+        }
+    }
+    Ok(())
+}
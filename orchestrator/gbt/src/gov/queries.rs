@@ -1,11 +1,19 @@
-use cosmos_gravity::send::TIMEOUT;
-use gravity_proto::gravity::AirdropProposal;
-use gravity_utils::{
-    connection_prep::create_rpc_connections, deep_space::Address, error::GravityError,
+use cosmos_gravity::{
+    proposals::{
+        decode_airdrop_recipients, AIRDROP_PROPOSAL_TYPE_URL, GRAVITY_PARAMS_SUBSPACE,
+        IBC_METADATA_PROPOSAL_TYPE_URL, PARAMETER_CHANGE_PROPOSAL_TYPE_URL,
+        UNHALT_BRIDGE_PROPOSAL_TYPE_URL,
+    },
+    send::TIMEOUT,
 };
+use gravity_proto::{
+    cosmos_sdk_proto::cosmos::{gov::v1beta1::Proposal, params::v1beta1::ParameterChangeProposal},
+    gravity::{AirdropProposal, IbcMetadataProposal, UnhaltBridgeProposal},
+};
+use gravity_utils::{connection_prep::create_rpc_connections, error::GravityError};
 use prost::{bytes::BytesMut, Message};
 
-use crate::args::AirdropQueryOpts;
+use crate::args::{AirdropQueryOpts, ProposalQueryOpts};
 
 pub async fn query_airdrops(opts: AirdropQueryOpts, prefix: String) -> Result<(), GravityError> {
     let connections =
@@ -30,37 +38,7 @@ pub async fn query_airdrops(opts: AirdropQueryOpts, prefix: String) -> Result<()
                     let res = AirdropProposal::decode(buf);
                     if let Ok(airdrop) = res {
                         found = true;
-
-                        info!("Found Airdrop proposal");
-                        info!("Title: {}", airdrop.title);
-                        info!("Description: {}", airdrop.description);
-                        info!("Number of Participants: {}", airdrop.amounts.len());
-                        let mut sum = 0;
-                        for amount in airdrop.amounts.iter() {
-                            sum += amount;
-                        }
-                        info!("Total value: {}{}", sum, airdrop.denom);
-
-                        if airdrop.amounts.len() < 100 || opts.full_list {
-                            info!("Participants list");
-
-                            let mut unpacked = Vec::new();
-                            // each address is 20 bytes
-                            assert_eq!(airdrop.recipients.len() / 20, airdrop.amounts.len());
-                            for i in 0..(airdrop.recipients.len() / 20) {
-                                let mut buf = [0; 20];
-                                let addr_bytes = &airdrop.recipients[(i * 20)..((i * 20) + 20)];
-                                buf.copy_from_slice(addr_bytes);
-                                let addr = Address::from_bytes(buf, prefix.clone()).unwrap();
-                                unpacked.push(addr);
-                            }
-                            assert_eq!(unpacked.len(), airdrop.amounts.len());
-                            for (i, _) in unpacked.iter().enumerate() {
-                                info!("{} {}{}", unpacked[i], airdrop.amounts[i], airdrop.denom)
-                            }
-                        } else {
-                            info!("Participants list is greater than 100 addresses, use --full-list to display it");
-                        }
+                        display_airdrop(&airdrop, &prefix, opts.full_list);
                     }
                 }
             }
@@ -77,3 +55,140 @@ pub async fn query_airdrops(opts: AirdropQueryOpts, prefix: String) -> Result<()
     }
     Ok(())
 }
+
+fn display_airdrop(airdrop: &AirdropProposal, prefix: &str, full_list: bool) {
+    info!("Found Airdrop proposal");
+    info!("Title: {}", airdrop.title);
+    info!("Description: {}", airdrop.description);
+    info!("Number of Participants: {}", airdrop.amounts.len());
+    let mut sum = 0;
+    for amount in airdrop.amounts.iter() {
+        sum += amount;
+    }
+    info!("Total value: {}{}", sum, airdrop.denom);
+
+    if airdrop.amounts.len() < 100 || full_list {
+        info!("Participants list");
+        match decode_airdrop_recipients(airdrop, prefix) {
+            Ok(recipients) => {
+                for (address, amount) in recipients {
+                    info!("{} {}{}", address, amount, airdrop.denom)
+                }
+            }
+            Err(e) => warn!("Failed to decode the participants {:?}", e),
+        }
+    } else {
+        info!("Participants list is greater than 100 addresses, use --full-list to display it");
+    }
+}
+
+/// Lists the Gravity proposals in the voting period followed by those that have passed
+pub async fn query_proposals(opts: ProposalQueryOpts, prefix: String) -> Result<(), GravityError> {
+    let connections =
+        create_rpc_connections(prefix.clone(), Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+
+    let pending = contact.get_governance_proposals_in_voting_period().await;
+    let passed = contact.get_passed_governance_proposals().await;
+    let (pending, passed) = match (pending, passed) {
+        (Ok(pending), Ok(passed)) => (pending.proposals, passed.proposals),
+        (Err(e), _) | (_, Err(e)) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to get proposals, check your cosmos gRPC {:?}",
+                e
+            )))
+        }
+    };
+
+    for (status, proposals) in [("In voting period", pending), ("Passed", passed)] {
+        let mut found = false;
+        for proposal in proposals {
+            found |= display_gravity_proposal(&proposal, status, &prefix, opts.full_list);
+        }
+        if !found {
+            info!("No Gravity proposals {}", status.to_lowercase());
+        }
+    }
+    Ok(())
+}
+
+/// Decodes and displays `proposal` if it is one of the Gravity proposal types, returning
+/// false for any other proposal
+fn display_gravity_proposal(
+    proposal: &Proposal,
+    status: &str,
+    prefix: &str,
+    full_list: bool,
+) -> bool {
+    let content = match &proposal.content {
+        Some(content) => content,
+        None => return false,
+    };
+    let value = content.value.as_slice();
+    match content.type_url.as_str() {
+        AIRDROP_PROPOSAL_TYPE_URL => match AirdropProposal::decode(value) {
+            Ok(airdrop) => {
+                info!("{} proposal {}", status, proposal.proposal_id);
+                display_airdrop(&airdrop, prefix, full_list);
+            }
+            Err(_) => return false,
+        },
+        UNHALT_BRIDGE_PROPOSAL_TYPE_URL => match UnhaltBridgeProposal::decode(value) {
+            Ok(unhalt) => {
+                info!("{} proposal {}", status, proposal.proposal_id);
+                info!("Found Oracle Unhalt proposal");
+                info!("Title: {}", unhalt.title);
+                info!("Description: {}", unhalt.description);
+                info!("Target nonce: {}", unhalt.target_nonce);
+            }
+            Err(_) => return false,
+        },
+        IBC_METADATA_PROPOSAL_TYPE_URL => match IbcMetadataProposal::decode(value) {
+            Ok(ibc) => {
+                info!("{} proposal {}", status, proposal.proposal_id);
+                info!("Found IBC Metadata proposal");
+                info!("Title: {}", ibc.title);
+                info!("Description: {}", ibc.description);
+                info!("IBC denom: {}", ibc.ibc_denom);
+                if let Some(metadata) = ibc.metadata {
+                    let decimals = metadata.denom_units.iter().map(|u| u.exponent).max();
+                    info!(
+                        "Name: {} Symbol: {} Display: {} Decimals: {}",
+                        metadata.name,
+                        metadata.symbol,
+                        metadata.display,
+                        decimals.unwrap_or(0)
+                    );
+                }
+            }
+            Err(_) => return false,
+        },
+        PARAMETER_CHANGE_PROPOSAL_TYPE_URL => {
+            let params = match ParameterChangeProposal::decode(value) {
+                Ok(params) => params,
+                Err(_) => return false,
+            };
+            if !params
+                .changes
+                .iter()
+                .any(|c| c.subspace == GRAVITY_PARAMS_SUBSPACE)
+            {
+                return false;
+            }
+            info!("{} proposal {}", status, proposal.proposal_id);
+            // halting the bridge is a parameter change of BridgeActive alone
+            if params.changes.len() == 1 && params.changes[0].key == "BridgeActive" {
+                info!("Found Emergency Bridge Halt proposal");
+            } else {
+                info!("Found Parameter Change proposal");
+            }
+            info!("Title: {}", params.title);
+            info!("Description: {}", params.description);
+            for change in params.changes {
+                info!("{}/{} = {}", change.subspace, change.key, change.value);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
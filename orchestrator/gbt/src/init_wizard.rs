@@ -0,0 +1,341 @@
+//! The `gbt init --interactive` wizard. It asks for the endpoints and relaying preferences of an
+//! orchestrator, checks the endpoints against the chain and returns the values to write into the
+//! default config, so that the comments of the default config are kept
+
+use std::io::{stdin, stdout, Write};
+
+use cosmos_gravity::query::get_gravity_params;
+use gravity_utils::{
+    clarity::{constants::ZERO_ADDRESS, Address as EthAddress},
+    connection_prep::try_connect_grpc,
+    deep_space::Coin,
+    error::GravityError,
+    types::{KeysConfig, TomlGravityBridgeToolsConfig},
+    web30::client::Web3,
+};
+use toml::Value;
+
+use crate::{
+    config::{DEFAULT_ADDRESS_PREFIX, DEFAULT_COSMOS_GRPC, DEFAULT_ETHEREUM_RPC},
+    utils::TIMEOUT,
+};
+
+/// A value to set in the config, as the section, the key and the value
+pub type ConfigSetting = (&'static str, &'static str, Value);
+
+/// The margin suggested for the profitable relaying modes
+const DEFAULT_MARGIN: f64 = 1.1;
+
+/// What the operator wants the relayer of the orchestrator to do
+const GOALS: [&str; 4] = [
+    "Only sign and attest, do not relay",
+    "Keep the bridge running, relay valsets that are needed at my own cost",
+    "Relay for profit",
+    "Relay everything regardless of cost, for testnets",
+];
+
+/// Asks a question and returns the answer, or the default if the answer is empty
+fn prompt(question: &str, default: Option<&str>) -> Result<String, GravityError> {
+    match default {
+        Some(default) => print!("{} [{}]: ", question, default),
+        None => print!("{} (leave empty to skip): ", question),
+    }
+    stdout().flush().expect("Unable to write to stdout");
+    let mut answer = String::new();
+    match stdin().read_line(&mut answer) {
+        Ok(0) => Err(GravityError::UnrecoverableError(
+            "Input closed before the init wizard finished".into(),
+        )),
+        Ok(_) => {
+            let answer = answer.trim();
+            if answer.is_empty() {
+                Ok(default.unwrap_or_default().to_string())
+            } else {
+                Ok(answer.to_string())
+            }
+        }
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "Failed to read the answer {:?}",
+            e
+        ))),
+    }
+}
+
+fn prompt_yes_no(question: &str, default: bool) -> Result<bool, GravityError> {
+    let default = if default { "y" } else { "n" };
+    loop {
+        match prompt(question, Some(default))?.to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n"),
+        }
+    }
+}
+
+/// Asks for one of `options` and returns its index
+fn prompt_choice(question: &str, options: &[&str], default: usize) -> Result<usize, GravityError> {
+    println!("{}", question);
+    for (i, option) in options.iter().enumerate() {
+        println!("  {}) {}", i + 1, option);
+    }
+    loop {
+        match prompt("Choose", Some(&(default + 1).to_string()))?.parse::<usize>() {
+            Ok(choice) if choice >= 1 && choice <= options.len() => return Ok(choice - 1),
+            _ => println!("Please choose a number between 1 and {}", options.len()),
+        }
+    }
+}
+
+fn prompt_margin(question: &str) -> Result<f64, GravityError> {
+    loop {
+        match prompt(question, Some(&DEFAULT_MARGIN.to_string()))?.parse::<f64>() {
+            Ok(margin) if margin.is_finite() && margin > 0.0 => return Ok(margin),
+            _ => println!("Please enter a positive number, 1.1 requires a 10% profit"),
+        }
+    }
+}
+
+/// Returns the key files of a config as settings
+fn get_keys_settings(keys: &KeysConfig) -> Vec<ConfigSetting> {
+    let mut settings = Vec::new();
+    if let Some(path) = &keys.cosmos_phrase_file {
+        settings.push((
+            "keys",
+            "cosmos_phrase_file",
+            Value::String(path.to_string_lossy().to_string()),
+        ));
+    }
+    if let Some(path) = &keys.ethereum_key_file {
+        settings.push((
+            "keys",
+            "ethereum_key_file",
+            Value::String(path.to_string_lossy().to_string()),
+        ));
+    }
+    settings
+}
+
+/// Connects to the endpoints and returns the Gravity contract address set as a chain parameter,
+/// returns an error if either endpoint can not be reached
+async fn probe_endpoints(
+    cosmos_grpc: &str,
+    ethereum_rpc: &str,
+) -> Result<Option<EthAddress>, GravityError> {
+    let mut grpc = try_connect_grpc(cosmos_grpc).await?;
+    let web3 = Web3::new(ethereum_rpc, TIMEOUT);
+
+    let params = get_gravity_params(&mut grpc).await?;
+    match web3.net_version().await {
+        Ok(chain_id) if params.bridge_chain_id != 0 && chain_id != params.bridge_chain_id => {
+            warn!(
+                "The Ethereum node is on chain {} but the bridge is on chain {}",
+                chain_id, params.bridge_chain_id
+            )
+        }
+        Ok(chain_id) => info!("The Ethereum node is on chain {}", chain_id),
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Could not connect to the Ethereum node {} {:?}",
+                ethereum_rpc, e
+            )))
+        }
+    }
+    info!("Connected to a chain with gravity id {}", params.gravity_id);
+    match params.bridge_ethereum_address.parse() {
+        Ok(address) if address != ZERO_ADDRESS => Ok(Some(address)),
+        _ => Ok(None),
+    }
+}
+
+/// Asks for the relaying preferences of the operator and suggests the relaying modes for them
+fn relayer_settings() -> Result<Vec<ConfigSetting>, GravityError> {
+    let goal = prompt_choice(
+        "What should the relayer of this orchestrator do?",
+        &GOALS,
+        0,
+    )?;
+    let mut settings = vec![("orchestrator", "relayer_enabled", Value::Boolean(goal != 0))];
+    let (valset_mode, batch_request_mode, batch_mode) = match goal {
+        0 => return Ok(settings),
+        1 => {
+            println!("Valsets are relayed when the bridge needs them, batches are relayed only when profitable and never requested");
+            ("Altruistic", "None", "ProfitableOnly")
+        }
+        2 => {
+            println!("Valsets and batches are requested and relayed only when the reward covers the cost times the margin");
+            ("ProfitableOnly", "ProfitableOnly", "ProfitableOnly")
+        }
+        _ => {
+            println!(
+                "Every valset and batch is requested and relayed, this costs ETH on every relay"
+            );
+            ("EveryValset", "EveryBatch", "EveryBatch")
+        }
+    };
+    settings.push((
+        "relayer",
+        "batch_request_mode",
+        Value::String(batch_request_mode.into()),
+    ));
+    settings.push((
+        "relayer.valset_relaying_mode",
+        "mode",
+        Value::String(valset_mode.into()),
+    ));
+    if valset_mode == "ProfitableOnly" {
+        let margin = prompt_margin("Margin for valset relaying")?;
+        settings.push((
+            "relayer.valset_relaying_mode",
+            "margin",
+            Value::Float(margin),
+        ));
+    }
+    settings.push((
+        "relayer.batch_relaying_mode",
+        "mode",
+        Value::String(batch_mode.into()),
+    ));
+    if batch_mode == "ProfitableOnly" {
+        let margin = prompt_margin("Margin for batch relaying")?;
+        settings.push((
+            "relayer.batch_relaying_mode",
+            "margin",
+            Value::Float(margin),
+        ));
+    }
+    Ok(settings)
+}
+
+/// Runs the init wizard, the values of an existing config are offered as the defaults
+pub async fn run_init_wizard(
+    existing: Option<&TomlGravityBridgeToolsConfig>,
+) -> Result<Vec<ConfigSetting>, GravityError> {
+    let connection = existing.map(|e| e.connection.clone()).unwrap_or_default();
+    let keys = existing.map(|e| e.keys.clone()).unwrap_or_default();
+    let mut settings = Vec::new();
+
+    println!("Network and endpoints");
+    let (address_prefix, cosmos_grpc, ethereum_rpc, chain_contract) = loop {
+        let address_prefix = prompt(
+            "Address prefix of the Cosmos chain",
+            Some(
+                connection
+                    .address_prefix
+                    .as_deref()
+                    .unwrap_or(DEFAULT_ADDRESS_PREFIX),
+            ),
+        )?;
+        let cosmos_grpc = prompt(
+            "Cosmos gRPC endpoint",
+            Some(
+                connection
+                    .cosmos_grpc
+                    .as_deref()
+                    .unwrap_or(DEFAULT_COSMOS_GRPC),
+            ),
+        )?;
+        let ethereum_rpc = prompt(
+            "Ethereum RPC endpoint",
+            Some(
+                connection
+                    .ethereum_rpc
+                    .as_deref()
+                    .unwrap_or(DEFAULT_ETHEREUM_RPC),
+            ),
+        )?;
+        match probe_endpoints(&cosmos_grpc, &ethereum_rpc).await {
+            Ok(chain_contract) => {
+                break (address_prefix, cosmos_grpc, ethereum_rpc, chain_contract)
+            }
+            Err(e) => {
+                warn!("{:?}", e);
+                if !prompt_yes_no("Use these endpoints anyway?", false)? {
+                    continue;
+                }
+                break (address_prefix, cosmos_grpc, ethereum_rpc, None);
+            }
+        }
+    };
+    settings.push((
+        "connection",
+        "address_prefix",
+        Value::String(address_prefix),
+    ));
+    settings.push(("connection", "cosmos_grpc", Value::String(cosmos_grpc)));
+    settings.push(("connection", "ethereum_rpc", Value::String(ethereum_rpc)));
+
+    // the chain parameter is the default since that is the contract the orchestrator would use
+    let default_contract = chain_contract
+        .or(connection.gravity_contract_address)
+        .map(|c| c.to_string());
+    loop {
+        let contract = prompt("Gravity contract address", default_contract.as_deref())?;
+        if contract.is_empty() {
+            break;
+        }
+        match contract.parse::<EthAddress>() {
+            Ok(contract) => {
+                settings.push((
+                    "connection",
+                    "gravity_contract_address",
+                    Value::String(contract.to_string()),
+                ));
+                break;
+            }
+            Err(e) => println!("{} is not an Ethereum address {:?}", contract, e),
+        }
+    }
+
+    loop {
+        let fees = prompt(
+            "Fees paid for each Cosmos transaction, eg. 100ugraviton",
+            connection.fees.as_deref(),
+        )?;
+        if fees.is_empty() {
+            break;
+        }
+        match fees.parse::<Coin>() {
+            Ok(_) => {
+                settings.push(("connection", "fees", Value::String(fees)));
+                break;
+            }
+            Err(e) => println!("{} is not an amount and denom {:?}", fees, e),
+        }
+    }
+
+    println!("Keys, these are files holding the keys such as mounted secrets. Keys can also be given with flags or GBT_* environment variables");
+    let cosmos_phrase_file = keys
+        .cosmos_phrase_file
+        .map(|p| p.to_string_lossy().to_string());
+    let cosmos_phrase_file = prompt(
+        "File holding the Cosmos phrase",
+        cosmos_phrase_file.as_deref(),
+    )?;
+    let ethereum_key_file = keys
+        .ethereum_key_file
+        .map(|p| p.to_string_lossy().to_string());
+    let ethereum_key_file = prompt(
+        "File holding the Ethereum private key",
+        ethereum_key_file.as_deref(),
+    )?;
+    settings.extend(get_keys_settings(&KeysConfig {
+        cosmos_phrase_file: Some(cosmos_phrase_file)
+            .filter(|p| !p.is_empty())
+            .map(Into::into),
+        ethereum_key_file: Some(ethereum_key_file)
+            .filter(|p| !p.is_empty())
+            .map(Into::into),
+    }));
+
+    println!("Relaying");
+    settings.extend(relayer_settings()?);
+
+    println!("Metrics");
+    let metrics_enabled = prompt_yes_no("Serve Prometheus metrics", false)?;
+    settings.push((
+        "metrics",
+        "metrics_enabled",
+        Value::Boolean(metrics_enabled),
+    ));
+    Ok(settings)
+}
@@ -0,0 +1,117 @@
+//! Derives Cosmos and Ethereum keys from BIP39 mnemonics along BIP44 HD paths, so that a single
+//! mnemonic can back both the Cosmos delegate key and the Ethereum key of an orchestrator
+
+use gravity_utils::{
+    clarity::PrivateKey as EthPrivateKey, deep_space::PrivateKey as CosmosPrivateKey,
+    error::GravityError,
+};
+
+use crate::args::EthereumKeyOpts;
+
+/// The coin type of the Cosmos Hub, used by most Cosmos chains and assumed by --cosmos-phrase
+pub const DEFAULT_COSMOS_COIN_TYPE: u32 = 118;
+/// The coin type of Ethereum
+pub const ETHEREUM_COIN_TYPE: u32 = 60;
+
+/// The HD path of the first Cosmos account of a mnemonic for the given coin type
+pub fn cosmos_hd_path(coin_type: u32) -> String {
+    format!("m/44'/{}'/0'/0/0", coin_type)
+}
+
+/// The HD path of the Ethereum account at `index`, the same path used by most Ethereum wallets
+pub fn ethereum_hd_path(index: u32) -> String {
+    format!("m/44'/{}'/0'/0/{}", ETHEREUM_COIN_TYPE, index)
+}
+
+/// Derives the private key at `hd_path` from a mnemonic
+pub fn derive_cosmos_key(phrase: &str, hd_path: &str) -> Result<CosmosPrivateKey, GravityError> {
+    match CosmosPrivateKey::from_hd_wallet_path(hd_path, phrase, "") {
+        Ok(key) => Ok(key),
+        Err(e) => Err(GravityError::ValidationError(format!(
+            "Failed to derive a key at {} from the mnemonic {:?}",
+            hd_path, e
+        ))),
+    }
+}
+
+/// Derives the Ethereum private key at `hd_path` from a mnemonic, both chains use secp256k1 keys so
+/// only the path differs from a Cosmos key
+pub fn derive_ethereum_key(phrase: &str, hd_path: &str) -> Result<EthPrivateKey, GravityError> {
+    let key = derive_cosmos_key(phrase, hd_path)?;
+    match EthPrivateKey::from_slice(&key.to_bytes()) {
+        Ok(key) => Ok(key),
+        Err(e) => Err(GravityError::ValidationError(format!(
+            "The key at {} is not a valid Ethereum key {:?}",
+            hd_path, e
+        ))),
+    }
+}
+
+/// Returns every Ethereum key given with --ethereum-key followed by the keys derived with
+/// --ethereum-mnemonic at each --hd-path
+pub fn ethereum_keys(opts: &EthereumKeyOpts) -> Result<Vec<EthPrivateKey>, GravityError> {
+    let mut keys = opts.ethereum_key.clone();
+    if let Some(phrase) = &opts.ethereum_mnemonic {
+        if opts.hd_path.is_empty() {
+            keys.push(derive_ethereum_key(phrase, &ethereum_hd_path(0))?);
+        }
+        for hd_path in opts.hd_path.iter() {
+            keys.push(derive_ethereum_key(phrase, hd_path)?);
+        }
+    }
+    Ok(keys)
+}
+
+/// Returns the Ethereum key given with --ethereum-key or derived with --ethereum-mnemonic and
+/// --hd-path, if either was given, for commands that use a single key
+pub fn optional_ethereum_key(
+    opts: &EthereumKeyOpts,
+) -> Result<Option<EthPrivateKey>, GravityError> {
+    let keys = ethereum_keys(opts)?;
+    if keys.len() > 1 {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{} Ethereum keys were given, only one can be used. Keys are also read from GBT_ETHEREUM_KEY and GBT_ETHEREUM_MNEMONIC",
+            keys.len()
+        )));
+    }
+    Ok(keys.first().copied())
+}
+
+/// Returns the Ethereum key given with --ethereum-key or derived with --ethereum-mnemonic and
+/// --hd-path, for commands that can not run without one
+pub fn ethereum_key(opts: &EthereumKeyOpts) -> Result<EthPrivateKey, GravityError> {
+    match optional_ethereum_key(opts)? {
+        Some(key) => Ok(key),
+        None => Err(GravityError::UnrecoverableError(
+            "Either --ethereum-key or --ethereum-mnemonic is required".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gravity_utils::clarity::Address as EthAddress;
+
+    use super::*;
+
+    /// The standard test mnemonic, its first accounts are the default accounts of Hardhat and
+    /// Anvil
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn test_derive_ethereum_key() {
+        assert_eq!(ethereum_hd_path(0), "m/44'/60'/0'/0/0");
+        let key = derive_ethereum_key(TEST_MNEMONIC, &ethereum_hd_path(0)).unwrap();
+        let expected: EthAddress = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+            .parse()
+            .unwrap();
+        assert_eq!(key.to_address(), expected);
+        let key = derive_ethereum_key(TEST_MNEMONIC, &ethereum_hd_path(1)).unwrap();
+        let expected: EthAddress = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+            .parse()
+            .unwrap();
+        assert_eq!(key.to_address(), expected);
+
+        assert!(derive_ethereum_key("not a mnemonic", &ethereum_hd_path(0)).is_err());
+    }
+}
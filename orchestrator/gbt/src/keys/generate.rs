@@ -0,0 +1,125 @@
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use gravity_utils::{
+    clarity::utils::bytes_to_hex_str, deep_space::mnemonic::Mnemonic, error::GravityError,
+    types::KeysConfig,
+};
+use rand::{rngs::OsRng, RngCore};
+
+use crate::{
+    args::KeysGenerateOpts,
+    keys::derive::{
+        cosmos_hd_path, derive_cosmos_key, derive_ethereum_key, ethereum_hd_path,
+        DEFAULT_COSMOS_COIN_TYPE,
+    },
+};
+
+/// The entropy of a generated mnemonic, 32 bytes produce a 24 word mnemonic
+const MNEMONIC_ENTROPY_BYTES: usize = 32;
+
+/// Writes a generated key to a new file that only its owner can read, an existing file is never
+/// overwritten since it may hold the only copy of another key
+fn write_key_file(path: &Path, contents: &str) -> Result<(), GravityError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let res = options
+        .open(path)
+        .and_then(|mut file| file.write_all(format!("{}\n", contents).as_bytes()));
+    match res {
+        Ok(()) => Ok(()),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "Failed to write key file {:?} {:?}",
+            path, e
+        ))),
+    }
+}
+
+pub fn generate_keys(
+    args: KeysGenerateOpts,
+    prefix: String,
+    keys_config: &KeysConfig,
+) -> Result<(), GravityError> {
+    let key_files: Vec<&Path> = [
+        &keys_config.cosmos_phrase_file,
+        &keys_config.ethereum_key_file,
+    ]
+    .iter()
+    .filter_map(|p| p.as_deref())
+    .collect();
+    if args.store {
+        if key_files.is_empty() {
+            return Err(GravityError::UnrecoverableError(
+                "--store requires cosmos_phrase_file or ethereum_key_file in the [keys] section of the config".into(),
+            ));
+        }
+        if keys_config.cosmos_phrase_file.is_some() && args.coin_type != DEFAULT_COSMOS_COIN_TYPE {
+            return Err(GravityError::UnrecoverableError(format!(
+                "cosmos_phrase_file derives the Cosmos key with coin type {}, it can not store a key with coin type {}",
+                DEFAULT_COSMOS_COIN_TYPE, args.coin_type
+            )));
+        }
+        if let Some(path) = key_files.iter().find(|p| p.exists()) {
+            return Err(GravityError::UnrecoverableError(format!(
+                "{:?} already exists, move it before storing new keys",
+                path
+            )));
+        }
+    }
+
+    let mut entropy = [0u8; MNEMONIC_ENTROPY_BYTES];
+    OsRng.fill_bytes(&mut entropy);
+    let mnemonic = match Mnemonic::from_entropy(&entropy) {
+        Ok(mnemonic) => mnemonic.to_string(),
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to generate a mnemonic {:?}",
+                e
+            )))
+        }
+    };
+
+    let cosmos_path = cosmos_hd_path(args.coin_type);
+    let ethereum_path = ethereum_hd_path(args.ethereum_index);
+    let cosmos_key = derive_cosmos_key(&mnemonic, &cosmos_path)?;
+    let ethereum_key = derive_ethereum_key(&mnemonic, &ethereum_path)?;
+    let cosmos_address = cosmos_key.to_address(&prefix).unwrap();
+
+    // secrets are only printed to stdout, never through the logger
+    println!("Mnemonic: {}", mnemonic);
+    info!("Cosmos address {} at {}", cosmos_address, cosmos_path);
+    info!(
+        "Ethereum address {} at {}",
+        ethereum_key.to_address(),
+        ethereum_path
+    );
+    warn!("Write down the mnemonic and keep it safe, it is the only way to recover both keys");
+    if args.store {
+        if let Some(path) = &keys_config.cosmos_phrase_file {
+            write_key_file(path, &mnemonic)?;
+            info!("Wrote the mnemonic to {:?}", path);
+        }
+        if let Some(path) = &keys_config.ethereum_key_file {
+            write_key_file(
+                path,
+                &format!("0x{}", bytes_to_hex_str(&ethereum_key.to_bytes())),
+            )?;
+            info!("Wrote the Ethereum private key to {:?}", path);
+        }
+    }
+    if args.coin_type != DEFAULT_COSMOS_COIN_TYPE {
+        warn!(
+            "--cosmos-phrase derives keys with coin type {}, the Cosmos key at {} can only be used with tools that support custom HD paths",
+            DEFAULT_COSMOS_COIN_TYPE, cosmos_path
+        );
+    }
+    info!(
+        "Use the mnemonic with --ethereum-mnemonic and --hd-path {} wherever an Ethereum key is needed",
+        ethereum_path
+    );
+    Ok(())
+}
@@ -1 +1,4 @@
+pub mod derive;
+pub mod generate;
 pub mod register_orchestrator_address;
+pub mod rotate;
@@ -1,10 +1,19 @@
-use cosmos_gravity::send::set_gravity_delegate_addresses;
+use cosmos_gravity::{
+    offline::{EncodedMsg, SET_ORCHESTRATOR_ADDRESS_TYPE_URL},
+    send::set_gravity_delegate_addresses,
+};
+use gravity_proto::gravity::MsgSetOrchestratorAddress;
 use gravity_utils::{
     connection_prep::{check_for_fee, create_rpc_connections, wait_for_cosmos_node_ready},
     error::GravityError,
 };
 
-use crate::{args::RegisterOrchestratorAddressOpts, utils::TIMEOUT};
+use crate::{
+    args::RegisterOrchestratorAddressOpts,
+    keys::derive::ethereum_key,
+    tx::offline::{sender_address, write_unsigned_tx},
+    utils::TIMEOUT,
+};
 
 pub async fn register_orchestrator_address(
     args: RegisterOrchestratorAddressOpts,
@@ -13,26 +22,36 @@ pub async fn register_orchestrator_address(
     let fee = args.fees;
     let cosmos_grpc = args.cosmos_grpc;
     let validator_key = args.validator_phrase;
-    let ethereum_key = args.ethereum_key;
+    let ethereum_key = ethereum_key(&args.ethereum)?;
     let cosmos_key = args.cosmos_phrase;
 
     let connections = create_rpc_connections(prefix, Some(cosmos_grpc), None, TIMEOUT).await;
     let contact = connections.contact.unwrap();
     wait_for_cosmos_node_ready(&contact).await;
 
-    let validator_addr = validator_key
-        .to_address(&contact.get_prefix())
-        .expect("Failed to parse validator-phrase");
+    let validator_addr = sender_address(validator_key, &args.offline, &contact.get_prefix())?;
 
     check_for_fee(&fee, validator_addr, &contact).await?;
 
     let ethereum_address = ethereum_key.to_address();
     let cosmos_address = cosmos_key.to_address(&contact.get_prefix()).unwrap();
+    if args.offline.generate_only.is_some() {
+        let validator = validator_addr
+            .to_bech32(format!("{}valoper", contact.get_prefix()))
+            .unwrap();
+        let msg = MsgSetOrchestratorAddress {
+            validator: validator.to_string(),
+            orchestrator: cosmos_address.to_string(),
+            eth_address: ethereum_address.to_string(),
+        };
+        let msgs = vec![EncodedMsg::new(SET_ORCHESTRATOR_ADDRESS_TYPE_URL, msg)];
+        return write_unsigned_tx(&contact, &args.offline, msgs, fee).await;
+    }
     let res = set_gravity_delegate_addresses(
         &contact,
         ethereum_address,
         cosmos_address,
-        validator_key,
+        validator_key.unwrap(),
         fee.clone(),
     )
     .await
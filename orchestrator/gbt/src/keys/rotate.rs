@@ -0,0 +1,80 @@
+//! Reports what a delegate key rotation would involve. The Gravity module does not allow a
+//! validator to replace its registered delegate keys, `MsgSetOrchestratorAddress` is rejected
+//! with ErrResetDelegateKeys once keys are set, so this only reads the current registration and
+//! the signatures the registered keys still owe
+
+use cosmos_gravity::query::{
+    get_delegate_keys_by_validator, get_oldest_unsigned_logic_calls,
+    get_oldest_unsigned_transaction_batches, get_oldest_unsigned_valsets,
+};
+use gravity_utils::{
+    clarity::Address as EthAddress, connection_prep::create_rpc_connections,
+    deep_space::Address as CosmosAddress, error::GravityError,
+};
+
+use crate::{args::RotateKeysOpts, utils::TIMEOUT};
+
+const RESET_FORBIDDEN: &str = "The Gravity module rejects MsgSetOrchestratorAddress for a validator that already has delegate keys (ErrResetDelegateKeys), registered keys can not be replaced";
+
+pub async fn rotate_keys(args: RotateKeysOpts, prefix: String) -> Result<(), GravityError> {
+    if !args.check {
+        return Err(GravityError::UnrecoverableError(format!(
+            "{}. Use --check to report the current registration and the signatures it owes",
+            RESET_FORBIDDEN
+        )));
+    }
+    let connections =
+        create_rpc_connections(prefix.clone(), Some(args.cosmos_grpc), None, TIMEOUT).await;
+    let mut grpc = connections.grpc.unwrap();
+
+    let current =
+        match get_delegate_keys_by_validator(&mut grpc, args.validator_address.clone()).await {
+            Ok(current) => current,
+            Err(e) => {
+                return Err(GravityError::UnrecoverableError(format!(
+                    "Validator {} has no registered delegate keys, use `gbt keys register-orchestrator-address` instead {:?}",
+                    args.validator_address, e
+                )))
+            }
+        };
+    let eth_address: EthAddress = current.eth_address.parse()?;
+    let orchestrator: CosmosAddress = current.orchestrator_address.parse()?;
+    info!(
+        "Validator {} has registered Ethereum address {} and orchestrator address {}",
+        args.validator_address, eth_address, orchestrator
+    );
+
+    let valsets = get_oldest_unsigned_valsets(&mut grpc, orchestrator, prefix.clone()).await?;
+    let batches =
+        get_oldest_unsigned_transaction_batches(&mut grpc, orchestrator, prefix.clone()).await?;
+    let logic_calls = get_oldest_unsigned_logic_calls(&mut grpc, orchestrator, prefix).await?;
+    for valset in valsets.iter() {
+        warn!(
+            "The registered keys have not signed valset {}",
+            valset.nonce
+        );
+    }
+    for batch in batches.iter() {
+        warn!(
+            "The registered keys have not signed batch {} for {}",
+            batch.nonce, batch.token_contract
+        );
+    }
+    for logic_call in logic_calls.iter() {
+        warn!(
+            "The registered keys have not signed logic call {}",
+            logic_call.invalidation_nonce
+        );
+    }
+    let owed = valsets.len() + batches.len() + logic_calls.len();
+    if owed == 0 {
+        info!("The registered keys owe no signatures");
+    } else {
+        warn!(
+            "The registered keys owe {} signatures, the validator may be slashed if the orchestrator does not sign them",
+            owed
+        );
+    }
+    warn!("{}", RESET_FORBIDDEN);
+    Ok(())
+}
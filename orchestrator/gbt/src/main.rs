@@ -3,37 +3,60 @@ extern crate log;
 
 use std::process::exit;
 
-use args::{GovQuerySubcommand, GovSubcommand, GovSubmitSubcommand, Opts};
+use args::{
+    GovAirdropSubcommand, GovIbcMetadataSubcommand, GovOracleUnhaltSubcommand, GovQuerySubcommand,
+    GovSubcommand, GovSubmitSubcommand, Opts,
+};
 use clap::Parser;
 use client::{
+    bulk::bulk, bump_fee::bump_fee, cancel_send_to_eth::cancel_send_to_eth,
     cosmos_to_eth::cosmos_to_eth, deploy_erc20_representation::deploy_erc20_representation,
-    eth_to_cosmos::eth_to_cosmos,
+    eth_to_cosmos::eth_to_cosmos, request_batch::request_batch, track::track,
 };
-use config::{get_home_dir, load_config};
+use config::{get_home_dir, load_config, DEFAULT_ADDRESS_PREFIX};
+use config_commands::{show_config, upgrade_config, validate_config};
 use env_logger::Env;
 use gov::{
+    airdrop::{build_airdrop, verify_airdrop},
+    ibc_metadata::build_ibc_metadata,
+    oracle_unhalt::build_oracle_unhalt,
     proposals::{
         submit_airdrop, submit_emergency_bridge_halt, submit_ibc_metadata, submit_oracle_unhalt,
+        submit_param_change,
     },
-    queries::query_airdrops,
+    queries::{query_airdrops, query_proposals},
+};
+use gravity_utils::{error::GravityError, types::GravityBridgeToolsConfig};
+use keys::{
+    generate::generate_keys, register_orchestrator_address::register_orchestrator_address,
+    rotate::rotate_keys,
 };
-use gravity_utils::error::GravityError;
-use keys::register_orchestrator_address::register_orchestrator_address;
+use query::queries::query;
+use tx::offline::{broadcast, sign};
 
 use crate::{
-    args::{ClientSubcommand, KeysSubcommand, SubCommand},
+    args::{
+        ClientSubcommand, ConfigSubcommand, KeysSubcommand, OrchestratorSubcommand, SubCommand,
+        TxSubcommand,
+    },
     config::init_config,
     orchestrator::orchestrator,
+    preflight::orchestrator_check,
     relayer::relayer,
 };
 
 mod args;
 mod client;
 mod config;
+mod config_commands;
 mod gov;
+mod init_wizard;
 mod keys;
 mod orchestrator;
+mod preflight;
+mod query;
 mod relayer;
+mod tx;
 mod utils;
 
 #[tokio::main]
@@ -67,10 +90,20 @@ async fn run_gbt() -> Result<(), GravityError> {
     // parse the arguments
     let opts: Opts = Opts::parse();
 
-    // handle global config here
-    let address_prefix = opts.address_prefix;
+    // handle global config here, flags and GBT_* environment variables take precedence
+    // over the config
     let home_dir = get_home_dir(opts.home)?;
-    let config = load_config(&home_dir)?;
+    let config = match opts.subcmd {
+        // the config commands read the config themselves so that they can report what is
+        // wrong with a config that does not load
+        SubCommand::Config(_) => GravityBridgeToolsConfig::default(),
+        _ => load_config(&home_dir)?,
+    };
+    let address_prefix = opts
+        .address_prefix
+        .clone()
+        .or_else(|| config.connection.address_prefix.clone())
+        .unwrap_or_else(|| DEFAULT_ADDRESS_PREFIX.to_string());
 
     // control flow for the command structure
     match opts.subcmd {
@@ -84,19 +117,43 @@ async fn run_gbt() -> Result<(), GravityError> {
             ClientSubcommand::DeployErc20Representation(deploy_erc20_opts) => {
                 deploy_erc20_representation(deploy_erc20_opts, address_prefix).await
             }
+            ClientSubcommand::CancelSendToEth(cancel_opts) => {
+                cancel_send_to_eth(cancel_opts, address_prefix).await
+            }
+            ClientSubcommand::RequestBatch(request_batch_opts) => {
+                request_batch(request_batch_opts, address_prefix).await
+            }
+            ClientSubcommand::BumpFee(bump_fee_opts) => {
+                bump_fee(bump_fee_opts, address_prefix).await
+            }
+            ClientSubcommand::Track(track_opts) => track(track_opts, address_prefix).await,
+            ClientSubcommand::Bulk(bulk_opts) => bulk(bulk_opts, address_prefix).await,
         },
+        SubCommand::Query(query_opts) => query(query_opts, address_prefix).await,
         SubCommand::Keys(key_opts) => match key_opts.subcmd {
+            KeysSubcommand::Generate(generate_opts) => {
+                generate_keys(generate_opts, address_prefix, &config.keys)
+            }
             KeysSubcommand::RegisterOrchestratorAddress(set_orchestrator_address_opts) => {
                 register_orchestrator_address(set_orchestrator_address_opts, address_prefix).await
             }
+            KeysSubcommand::Rotate(rotate_opts) => rotate_keys(rotate_opts, address_prefix).await,
+        },
+        SubCommand::Orchestrator(orchestrator_opts) => match orchestrator_opts.subcmd {
+            Some(OrchestratorSubcommand::Check(check_opts)) => {
+                orchestrator_check(check_opts, address_prefix, &config).await
+            }
+            None => orchestrator(orchestrator_opts.setup, address_prefix, config, home_dir).await,
         },
-        SubCommand::Orchestrator(orchestrator_opts) => {
-            orchestrator(orchestrator_opts, address_prefix, config).await
-        }
         SubCommand::Relayer(relayer_opts) => {
-            relayer(relayer_opts, address_prefix, &config.relayer).await
+            relayer(relayer_opts, address_prefix, &config, home_dir).await
         }
-        SubCommand::Init(init_opts) => init_config(init_opts, home_dir),
+        SubCommand::Init(init_opts) => init_config(init_opts, home_dir).await,
+        SubCommand::Config(config_opts) => match config_opts.subcmd {
+            ConfigSubcommand::Validate(_) => validate_config(&home_dir),
+            ConfigSubcommand::Show(_) => show_config(&home_dir, opts.address_prefix),
+            ConfigSubcommand::Upgrade(upgrade_opts) => upgrade_config(upgrade_opts, &home_dir),
+        },
         SubCommand::Gov(gov_opts) => match gov_opts.subcmd {
             GovSubcommand::Submit(submit_opts) => match submit_opts {
                 GovSubmitSubcommand::IbcMetadata(opts) => {
@@ -109,10 +166,34 @@ async fn run_gbt() -> Result<(), GravityError> {
                 GovSubmitSubcommand::OracleUnhalt(opts) => {
                     submit_oracle_unhalt(opts, address_prefix).await
                 }
+                GovSubmitSubcommand::ParamChange(opts) => {
+                    submit_param_change(opts, address_prefix).await
+                }
             },
             GovSubcommand::Query(query_opts) => match query_opts {
                 GovQuerySubcommand::Airdrop(opts) => query_airdrops(opts, address_prefix).await,
+                GovQuerySubcommand::Proposals(opts) => query_proposals(opts, address_prefix).await,
+            },
+            GovSubcommand::Airdrop(airdrop_opts) => match airdrop_opts {
+                GovAirdropSubcommand::Build(opts) => build_airdrop(opts, address_prefix).await,
+                GovAirdropSubcommand::Verify(opts) => verify_airdrop(opts, address_prefix).await,
+            },
+            GovSubcommand::IbcMetadata(ibc_metadata_opts) => match ibc_metadata_opts {
+                GovIbcMetadataSubcommand::Build(opts) => {
+                    build_ibc_metadata(opts, address_prefix).await
+                }
             },
+            GovSubcommand::OracleUnhalt(oracle_unhalt_opts) => match oracle_unhalt_opts {
+                GovOracleUnhaltSubcommand::Build(opts) => {
+                    build_oracle_unhalt(opts, address_prefix).await
+                }
+            },
+        },
+        SubCommand::Tx(tx_opts) => match tx_opts.subcmd {
+            TxSubcommand::Sign(sign_opts) => sign(sign_opts),
+            TxSubcommand::Broadcast(broadcast_opts) => {
+                broadcast(broadcast_opts, address_prefix).await
+            }
         },
     }
 }
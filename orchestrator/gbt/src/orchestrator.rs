@@ -1,12 +1,14 @@
-use std::{cmp::min, time::Duration};
+use std::{cmp::min, path::PathBuf, time::Duration};
 
 use cosmos_gravity::query::get_gravity_params;
+use gravity_proto::gravity::Params;
 use gravity_utils::{
-    clarity::constants::ZERO_ADDRESS,
+    clarity::{constants::ZERO_ADDRESS, Address as EthAddress, PrivateKey as EthPrivateKey},
     connection_prep::{
         check_delegate_addresses, check_for_eth, check_for_fee, create_rpc_connections,
         wait_for_cosmos_node_ready,
     },
+    deep_space::{Coin, PrivateKey as CosmosPrivateKey},
     error::GravityError,
     types::{BatchRequestMode, GravityBridgeToolsConfig},
 };
@@ -15,18 +17,111 @@ use orchestrator::main_loop::{
     orchestrator_main_loop, ETH_ORACLE_LOOP_SPEED, ETH_SIGNER_LOOP_SPEED,
 };
 
-use crate::{args::OrchestratorOpts, utils::print_relaying_explanation};
+use crate::{
+    args::OrchestratorSetupOpts,
+    config::{
+        resolve_cosmos_key, resolve_ethereum_keys, resolve_fees, DEFAULT_COSMOS_GRPC,
+        DEFAULT_ETHEREUM_RPC,
+    },
+    keys::derive::optional_ethereum_key,
+    utils::{print_relaying_explanation, spawn_budget_resume_handler, spawn_config_reload_handler},
+};
+
+/// The keys and endpoints of the orchestrator, resolved from flags, the environment and the config
+pub struct OrchestratorSetup {
+    pub fee: Coin,
+    pub cosmos_grpc: String,
+    pub ethereum_rpc: String,
+    pub gravity_contract_address: Option<EthAddress>,
+    pub ethereum_key: EthPrivateKey,
+    pub cosmos_key: CosmosPrivateKey,
+}
+
+/// Resolves the keys and endpoints of the orchestrator, flags and GBT_* environment variables
+/// take precedence over the config
+pub fn resolve_setup(
+    args: OrchestratorSetupOpts,
+    config: &GravityBridgeToolsConfig,
+) -> Result<OrchestratorSetup, GravityError> {
+    let fee = match resolve_fees(args.fees, &config.connection)? {
+        Some(fee) => fee,
+        None => {
+            return Err(GravityError::UnrecoverableError(
+                "Fees are required, set --fees, GBT_FEES or `fees` in the config".into(),
+            ))
+        }
+    };
+    let cosmos_grpc = args
+        .cosmos_grpc
+        .or_else(|| config.connection.cosmos_grpc.clone())
+        .unwrap_or_else(|| DEFAULT_COSMOS_GRPC.to_string());
+    let ethereum_rpc = args
+        .ethereum_rpc
+        .or_else(|| config.connection.ethereum_rpc.clone())
+        .unwrap_or_else(|| DEFAULT_ETHEREUM_RPC.to_string());
+    let gravity_contract_address = args
+        .gravity_contract_address
+        .or(config.connection.gravity_contract_address);
+    let ethereum_keys = resolve_ethereum_keys(
+        optional_ethereum_key(&args.ethereum)?.into_iter().collect(),
+        &config.keys,
+    )?;
+    let ethereum_key = match ethereum_keys.first() {
+        Some(key) => *key,
+        None => {
+            return Err(GravityError::UnrecoverableError(
+                "An Ethereum key is required, set --ethereum-key, GBT_ETHEREUM_KEY or `ethereum_key_file` in the config".into(),
+            ))
+        }
+    };
+    let cosmos_key = match resolve_cosmos_key(args.cosmos_phrase, &config.keys)? {
+        Some(key) => key,
+        None => {
+            return Err(GravityError::UnrecoverableError(
+                "A Cosmos phrase is required, set --cosmos-phrase, GBT_COSMOS_PHRASE or `cosmos_phrase_file` in the config".into(),
+            ))
+        }
+    };
+    Ok(OrchestratorSetup {
+        fee,
+        cosmos_grpc,
+        ethereum_rpc,
+        gravity_contract_address,
+        ethereum_key,
+        cosmos_key,
+    })
+}
+
+/// Returns the address of the Gravity contract, by default the one set as a chain parameter
+pub fn get_contract_address(
+    gravity_contract_address: Option<EthAddress>,
+    params: &Params,
+) -> Result<EthAddress, GravityError> {
+    if let Some(c) = gravity_contract_address {
+        return Ok(c);
+    }
+    match params.bridge_ethereum_address.parse() {
+        Ok(v) if v != ZERO_ADDRESS => Ok(v),
+        _ => Err(GravityError::UnrecoverableError(
+            "The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into(),
+        )),
+    }
+}
 
 pub async fn orchestrator(
-    args: OrchestratorOpts,
+    args: OrchestratorSetupOpts,
     address_prefix: String,
     config: GravityBridgeToolsConfig,
+    home_dir: PathBuf,
 ) -> Result<(), GravityError> {
-    let fee = args.fees;
-    let cosmos_grpc = args.cosmos_grpc;
-    let ethereum_rpc = args.ethereum_rpc;
-    let ethereum_key = args.ethereum_key;
-    let cosmos_key = args.cosmos_phrase;
+    let OrchestratorSetup {
+        fee,
+        cosmos_grpc,
+        ethereum_rpc,
+        gravity_contract_address,
+        ethereum_key,
+        cosmos_key,
+    } = resolve_setup(args, &config)?;
 
     let timeout = min(
         min(ETH_SIGNER_LOOP_SPEED, ETH_ORACLE_LOOP_SPEED),
@@ -81,26 +176,7 @@ pub async fn orchestrator(
         .expect("Failed to get Gravity Bridge module parameters!");
 
     // get the gravity contract address, if not provided
-    let contract_address = if let Some(c) = args.gravity_contract_address {
-        c
-    } else {
-        let c = params.bridge_ethereum_address.parse();
-        match c {
-            Ok(v) => {
-                if v == ZERO_ADDRESS {
-                    return Err(GravityError::UnrecoverableError(
-                        "The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into(),
-                    ));
-                }
-                c.unwrap()
-            }
-            Err(_) => {
-                return Err(GravityError::UnrecoverableError(
-                    "The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into(),
-                ));
-            }
-        }
-    };
+    let contract_address = get_contract_address(gravity_contract_address, &params)?;
 
     if config.orchestrator.relayer_enabled {
         // setup and explain relayer settings
@@ -110,6 +186,8 @@ pub async fn orchestrator(
         } else {
             print_relaying_explanation(&config.relayer, false)
         }
+        spawn_budget_resume_handler();
+        spawn_config_reload_handler(home_dir, config.relayer.clone());
     }
 
     // Start monitiring if enabled on config.toml
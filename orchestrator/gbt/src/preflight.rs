@@ -0,0 +1,582 @@
+//! The preflight check run by `gbt orchestrator check`. The orchestrator stops or waits at the
+//! first problem it finds on startup, this instead runs every check it can and reports each one
+//! so that a validator, or the CI of their infrastructure, sees everything that needs fixing
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cosmos_gravity::query::{get_gravity_params, get_latest_valsets};
+use ethereum_gravity::utils::{get_gravity_id, get_valset_nonce};
+use gravity_proto::gravity::{
+    query_client::QueryClient as GravityQueryClient, Params,
+    QueryLastPendingBatchRequestByAddrRequest, QueryLastPendingLogicCallByAddrRequest,
+    QueryLastPendingValsetRequestByAddrRequest,
+};
+use gravity_utils::{
+    connection_prep::{check_delegate_addresses, check_for_fee},
+    deep_space::{client::ChainStatus, Address as CosmosAddress, Contact},
+    error::GravityError,
+    num_conversion::print_eth,
+    types::GravityBridgeToolsConfig,
+    web30::client::Web3,
+};
+use tonic::transport::Channel;
+
+use crate::{
+    args::{OrchestratorCheckOpts, PreflightThresholds},
+    orchestrator::{get_contract_address, resolve_setup, OrchestratorSetup},
+    utils::TIMEOUT,
+};
+
+/// An Ethereum node whose latest block is older than this, in seconds, is likely syncing
+const MAX_ETHEREUM_BLOCK_AGE: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+/// The result of every check run, in the order they were run
+#[derive(Default)]
+struct CheckReport {
+    results: Vec<(CheckStatus, &'static str, String)>,
+}
+
+impl CheckReport {
+    fn pass(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.results.push((CheckStatus::Pass, name, detail.into()));
+    }
+
+    fn warn(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.results.push((CheckStatus::Warn, name, detail.into()));
+    }
+
+    fn fail(&mut self, name: &'static str, detail: impl Into<String>) {
+        self.results.push((CheckStatus::Fail, name, detail.into()));
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.results.iter().filter(|(s, _, _)| *s == status).count()
+    }
+}
+
+/// Converts a large integer to a float for the rough estimates of this check
+fn to_f64(value: impl ToString) -> f64 {
+    value.to_string().parse().unwrap_or(0f64)
+}
+
+/// Checks that the Cosmos node is reachable and synced, returning the connections and the
+/// latest block height if it is
+async fn check_cosmos(
+    report: &mut CheckReport,
+    setup: &OrchestratorSetup,
+    prefix: &str,
+) -> Option<(Contact, GravityQueryClient<Channel>, u64)> {
+    let url = setup.cosmos_grpc.trim_end_matches('/').to_string();
+    let grpc = match GravityQueryClient::connect(url.clone()).await {
+        Ok(grpc) => grpc,
+        Err(e) => {
+            report.fail(
+                "cosmos grpc",
+                format!("Could not connect to {} {:?}", url, e),
+            );
+            return None;
+        }
+    };
+    let contact = match Contact::new(&url, TIMEOUT, prefix) {
+        Ok(contact) => contact,
+        Err(e) => {
+            report.fail("cosmos grpc", format!("Invalid url {} {:?}", url, e));
+            return None;
+        }
+    };
+    report.pass("cosmos grpc", format!("Connected to {}", url));
+
+    match contact.get_chain_status().await {
+        Ok(ChainStatus::Moving { block_height }) => {
+            report.pass("cosmos sync", format!("At block {}", block_height));
+            Some((contact, grpc, block_height))
+        }
+        Ok(ChainStatus::Syncing) => {
+            report.fail("cosmos sync", "The node is syncing");
+            None
+        }
+        Ok(ChainStatus::WaitingToStart) => {
+            report.fail("cosmos sync", "The node is waiting for the chain to start");
+            None
+        }
+        Err(e) => {
+            report.fail("cosmos sync", format!("Could not get the status {:?}", e));
+            None
+        }
+    }
+}
+
+/// Checks that the Ethereum node is reachable and synced, returning the connection if it is
+/// reachable
+async fn check_ethereum(report: &mut CheckReport, setup: &OrchestratorSetup) -> Option<Web3> {
+    let web3 = Web3::new(&setup.ethereum_rpc, TIMEOUT);
+    match web3.eth_block_number().await {
+        Ok(block) => report.pass(
+            "ethereum rpc",
+            format!("Connected to {} at block {}", setup.ethereum_rpc, block),
+        ),
+        Err(e) => {
+            report.fail(
+                "ethereum rpc",
+                format!("Could not connect to {} {:?}", setup.ethereum_rpc, e),
+            );
+            return None;
+        }
+    }
+
+    match web3.eth_get_latest_block().await {
+        Ok(block) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let age = now.saturating_sub(to_f64(&block.timestamp) as u64);
+            if age > MAX_ETHEREUM_BLOCK_AGE {
+                report.warn(
+                    "ethereum sync",
+                    format!(
+                        "The latest block is {} seconds old, the node may be syncing",
+                        age
+                    ),
+                )
+            } else {
+                report.pass(
+                    "ethereum sync",
+                    format!("The latest block is {} seconds old", age),
+                )
+            }
+        }
+        Err(e) => report.warn(
+            "ethereum sync",
+            format!("Could not get the latest block {:?}", e),
+        ),
+    }
+    Some(web3)
+}
+
+/// Checks that the Gravity contract belongs to this chain and is up to date with its valsets
+async fn check_contract(
+    report: &mut CheckReport,
+    setup: &OrchestratorSetup,
+    params: &Params,
+    grpc: &mut GravityQueryClient<Channel>,
+    web3: &Web3,
+) {
+    match web3.net_version().await {
+        Ok(chain_id) if params.bridge_chain_id == 0 => report.warn(
+            "ethereum chain id",
+            format!(
+                "The node is on chain {}, the chain does not set bridge_chain_id to compare with",
+                chain_id
+            ),
+        ),
+        Ok(chain_id) if chain_id == params.bridge_chain_id => {
+            report.pass("ethereum chain id", format!("Chain {}", chain_id))
+        }
+        Ok(chain_id) => report.fail(
+            "ethereum chain id",
+            format!(
+                "The node is on chain {} but the bridge is on chain {}",
+                chain_id, params.bridge_chain_id
+            ),
+        ),
+        Err(e) => report.fail(
+            "ethereum chain id",
+            format!("Could not get the chain id {:?}", e),
+        ),
+    }
+
+    let contract = match get_contract_address(setup.gravity_contract_address, params) {
+        Ok(contract) => contract,
+        Err(e) => {
+            report.fail("gravity contract", format!("{:?}", e));
+            return;
+        }
+    };
+    let caller = setup.ethereum_key.to_address();
+    match get_gravity_id(contract, caller, web3).await {
+        // the gravity id is stored as a bytes32, so it is padded with zeros
+        Ok(gravity_id) if gravity_id.trim_end_matches('\0') == params.gravity_id => report.pass(
+            "gravity id",
+            format!("{} has gravity id {}", contract, params.gravity_id),
+        ),
+        Ok(gravity_id) => report.fail(
+            "gravity id",
+            format!(
+                "{} has gravity id {} but the chain expects {}, check the contract address",
+                contract,
+                gravity_id.trim_end_matches('\0'),
+                params.gravity_id
+            ),
+        ),
+        Err(e) => report.fail(
+            "gravity id",
+            format!(
+                "Could not get the gravity id of {}, check the contract address {:?}",
+                contract, e
+            ),
+        ),
+    }
+
+    let contract_nonce = match get_valset_nonce(contract, caller, web3).await {
+        Ok(nonce) => nonce,
+        Err(e) => {
+            report.fail(
+                "valset nonce",
+                format!("Could not get the valset nonce of {} {:?}", contract, e),
+            );
+            return;
+        }
+    };
+    let latest_nonce = match get_latest_valsets(grpc).await {
+        Ok(valsets) => valsets.iter().map(|v| v.nonce).max().unwrap_or(0),
+        Err(e) => {
+            report.fail(
+                "valset nonce",
+                format!("Could not get the latest valsets {:?}", e),
+            );
+            return;
+        }
+    };
+    if contract_nonce == latest_nonce {
+        report.pass(
+            "valset nonce",
+            format!("The contract has the latest valset {}", latest_nonce),
+        )
+    } else if contract_nonce < latest_nonce {
+        report.warn(
+            "valset nonce",
+            format!(
+                "The contract has valset {} and the chain has valset {}, the updates are waiting to be relayed",
+                contract_nonce, latest_nonce
+            ),
+        )
+    } else {
+        report.fail(
+            "valset nonce",
+            format!(
+                "The contract has valset {} which the chain has not created, its latest is {}. Check the contract address",
+                contract_nonce, latest_nonce
+            ),
+        )
+    }
+}
+
+/// Checks that the Cosmos key can pay fees for a while and the Ethereum key has enough ETH
+async fn check_balances(
+    report: &mut CheckReport,
+    setup: &OrchestratorSetup,
+    config: &GravityBridgeToolsConfig,
+    thresholds: PreflightThresholds,
+    cosmos_address: CosmosAddress,
+    contact: &Contact,
+    web3: Option<&Web3>,
+) {
+    match check_for_fee(&setup.fee, cosmos_address, contact).await {
+        Err(e) => report.fail("cosmos balance", format!("{:?}", e)),
+        Ok(()) if setup.fee.amount.is_zero() => {
+            report.pass("cosmos balance", "Fees are zero and the account exists")
+        }
+        Ok(()) => {
+            let balance = match contact.get_balances(cosmos_address).await {
+                Ok(balances) => balances
+                    .into_iter()
+                    .find(|b| b.denom == setup.fee.denom)
+                    .map(|b| to_f64(b.amount))
+                    .unwrap_or(0f64),
+                Err(e) => {
+                    report.fail(
+                        "cosmos balance",
+                        format!("Could not get the balances of {} {:?}", cosmos_address, e),
+                    );
+                    return;
+                }
+            };
+            let days = balance / to_f64(&setup.fee.amount) / thresholds.daily_transactions as f64;
+            let detail = format!(
+                "{}{} pays fees of {}{} for about {:.0} days",
+                balance, setup.fee.denom, setup.fee.amount, setup.fee.denom, days
+            );
+            if days < thresholds.min_fee_days as f64 {
+                report.warn("cosmos balance", detail)
+            } else {
+                report.pass("cosmos balance", detail)
+            }
+        }
+    }
+
+    let web3 = match web3 {
+        Some(web3) => web3,
+        None => return,
+    };
+    let address = setup.ethereum_key.to_address();
+    let balance = match web3.eth_get_balance(address).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            report.fail(
+                "ethereum balance",
+                format!("Could not get the balance of {} {:?}", address, e),
+            );
+            return;
+        }
+    };
+    if balance.is_zero() {
+        report.fail(
+            "ethereum balance",
+            format!("{} has no ETH, the oracle needs at least dust", address),
+        );
+        return;
+    }
+    if !config.orchestrator.relayer_enabled {
+        report.pass(
+            "ethereum balance",
+            format!("{} has {} ETH", address, print_eth(balance)),
+        );
+        return;
+    }
+    match web3.eth_gas_price().await {
+        Ok(gas_price) => {
+            let relays = to_f64(&balance)
+                / (to_f64(&gas_price) * thresholds.valset_relay_gas as f64).max(1f64);
+            let detail = format!(
+                "{} has {} ETH, enough for about {:.0} valset relays at the current gas price",
+                address,
+                print_eth(balance),
+                relays
+            );
+            if relays < thresholds.min_relays as f64 {
+                report.warn("ethereum balance", detail)
+            } else {
+                report.pass("ethereum balance", detail)
+            }
+        }
+        Err(e) => report.warn(
+            "ethereum balance",
+            format!(
+                "{} has {} ETH, could not get the gas price to estimate relaying costs {:?}",
+                address,
+                print_eth(balance),
+                e
+            ),
+        ),
+    }
+}
+
+/// Reports the oldest item the orchestrator has not signed against the window it must be
+/// signed in, validators are slashed for items left unsigned past the window
+fn check_unsigned(
+    report: &mut CheckReport,
+    name: &'static str,
+    heights: Vec<u64>,
+    current_height: u64,
+    window: u64,
+) {
+    let oldest = match heights.iter().min() {
+        Some(oldest) => *oldest,
+        None => {
+            report.pass(name, "Nothing is waiting to be signed");
+            return;
+        }
+    };
+    let age = current_height.saturating_sub(oldest);
+    let detail = format!(
+        "{} waiting to be signed, the oldest is {} blocks old of a {} block window",
+        heights.len(),
+        age,
+        window
+    );
+    if age * 10 >= window * 9 {
+        report.fail(name, detail)
+    } else if age * 2 >= window {
+        report.warn(name, detail)
+    } else {
+        report.pass(name, detail)
+    }
+}
+
+async fn check_signing(
+    report: &mut CheckReport,
+    params: &Params,
+    grpc: &mut GravityQueryClient<Channel>,
+    orchestrator_address: String,
+    current_height: u64,
+) {
+    match grpc
+        .last_pending_valset_request_by_addr(QueryLastPendingValsetRequestByAddrRequest {
+            address: orchestrator_address.clone(),
+        })
+        .await
+    {
+        Ok(res) => check_unsigned(
+            report,
+            "unsigned valsets",
+            res.into_inner().valsets.iter().map(|v| v.height).collect(),
+            current_height,
+            params.signed_valsets_window,
+        ),
+        Err(e) => report.fail("unsigned valsets", format!("{:?}", e)),
+    }
+    match grpc
+        .last_pending_batch_request_by_addr(QueryLastPendingBatchRequestByAddrRequest {
+            address: orchestrator_address.clone(),
+        })
+        .await
+    {
+        Ok(res) => check_unsigned(
+            report,
+            "unsigned batches",
+            res.into_inner().batch.iter().map(|b| b.block).collect(),
+            current_height,
+            params.signed_batches_window,
+        ),
+        Err(e) => report.fail("unsigned batches", format!("{:?}", e)),
+    }
+    match grpc
+        .last_pending_logic_call_by_addr(QueryLastPendingLogicCallByAddrRequest {
+            address: orchestrator_address,
+        })
+        .await
+    {
+        Ok(res) => check_unsigned(
+            report,
+            "unsigned logic calls",
+            res.into_inner().call.iter().map(|c| c.block).collect(),
+            current_height,
+            params.signed_logic_calls_window,
+        ),
+        Err(e) => report.fail("unsigned logic calls", format!("{:?}", e)),
+    }
+}
+
+async fn run_checks(
+    report: &mut CheckReport,
+    setup: &OrchestratorSetup,
+    config: &GravityBridgeToolsConfig,
+    thresholds: PreflightThresholds,
+    prefix: &str,
+) {
+    let cosmos = check_cosmos(report, setup, prefix).await;
+    let web3 = check_ethereum(report, setup).await;
+    let (contact, mut grpc, height) = match cosmos {
+        Some(cosmos) => cosmos,
+        None => return,
+    };
+
+    let params = match get_gravity_params(&mut grpc).await {
+        Ok(params) if !params.bridge_active => {
+            report.warn("gravity params", "The bridge is not active");
+            params
+        }
+        Ok(params) => {
+            report.pass("gravity params", "The bridge is active");
+            params
+        }
+        Err(e) => {
+            report.fail(
+                "gravity params",
+                format!("Could not get the Gravity module parameters {:?}", e),
+            );
+            return;
+        }
+    };
+    if let Some(web3) = web3.as_ref() {
+        check_contract(report, setup, &params, &mut grpc, web3).await;
+    }
+
+    let eth_address = setup.ethereum_key.to_address();
+    let cosmos_address = match setup.cosmos_key.to_address(prefix) {
+        Ok(address) => address,
+        Err(e) => {
+            report.fail("delegate keys", format!("Invalid Cosmos key {:?}", e));
+            return;
+        }
+    };
+    match check_delegate_addresses(&mut grpc, eth_address, cosmos_address, prefix).await {
+        Ok(()) => report.pass(
+            "delegate keys",
+            format!(
+                "{} and {} are registered to the same validator",
+                eth_address, cosmos_address
+            ),
+        ),
+        Err(e) => report.fail("delegate keys", format!("{:?}", e)),
+    }
+
+    check_balances(
+        report,
+        setup,
+        config,
+        thresholds,
+        cosmos_address,
+        &contact,
+        web3.as_ref(),
+    )
+    .await;
+    check_signing(
+        report,
+        &params,
+        &mut grpc,
+        cosmos_address.to_bech32(prefix).unwrap().to_string(),
+        height,
+    )
+    .await;
+}
+
+pub async fn orchestrator_check(
+    args: OrchestratorCheckOpts,
+    address_prefix: String,
+    config: &GravityBridgeToolsConfig,
+) -> Result<(), GravityError> {
+    let mut report = CheckReport::default();
+    match resolve_setup(args.setup, config) {
+        Ok(setup) => {
+            report.pass("setup", "The keys, fees and endpoints are set");
+            run_checks(
+                &mut report,
+                &setup,
+                config,
+                args.thresholds,
+                &address_prefix,
+            )
+            .await;
+        }
+        Err(e) => report.fail("setup", format!("{:?}", e)),
+    }
+
+    // the report is printed to stdout so that it can be captured apart from the logs
+    for (status, name, detail) in report.results.iter() {
+        println!("{} {:<22} {}", status.label(), name, detail);
+    }
+    let failed = report.count(CheckStatus::Fail);
+    let warned = report.count(CheckStatus::Warn);
+    println!(
+        "{} passed, {} warnings, {} failed",
+        report.count(CheckStatus::Pass),
+        warned,
+        failed
+    );
+    if failed > 0 || (args.strict && warned > 0) {
+        return Err(GravityError::UnrecoverableError(format!(
+            "The preflight check found {} failures and {} warnings",
+            failed, warned
+        )));
+    }
+    Ok(())
+}
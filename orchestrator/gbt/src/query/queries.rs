@@ -0,0 +1,555 @@
+//! Queries against the Gravity module gRPC service. Results are displayed as human readable text
+//! through the logger, or printed to stdout as JSON with `--output json` for use in scripts
+
+use std::convert::TryFrom;
+
+use cosmos_gravity::{
+    proposals::{current_param_json, GRAVITY_PARAMS},
+    query::*,
+    send::TIMEOUT,
+    utils::AttestationClaim,
+};
+use gravity_proto::gravity::{query_client::QueryClient as GravityQueryClient, Params};
+use gravity_utils::{
+    clarity::{
+        utils::{bytes_to_hex_str, hex_str_to_bytes},
+        Address as EthAddress, Uint256,
+    },
+    connection_prep::create_rpc_connections,
+    error::GravityError,
+    types::{
+        BatchConfirmResponse, BatchTransaction, LogicCall, LogicCallConfirmResponse,
+        TransactionBatch, Valset, ValsetConfirmResponse,
+    },
+    web30::client::Web3,
+};
+use prost_types::Any;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tonic::transport::Channel;
+
+use crate::{
+    args::{
+        AttestationsQueryOpts, BatchQueryOpts, DelegateKeysQueryOpts, LogicCallQueryOpts,
+        OutputFormat, QueryOpts, QuerySubcommand,
+    },
+    utils::AmountFormatter,
+};
+
+pub async fn query(opts: QueryOpts, prefix: String) -> Result<(), GravityError> {
+    let connections =
+        create_rpc_connections(prefix.clone(), Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let mut grpc = connections.grpc.unwrap();
+    let output = opts.output;
+    let mut amounts = AmountFormatter::new(Web3::new(&opts.ethereum_rpc, TIMEOUT));
+
+    match opts.subcmd {
+        QuerySubcommand::Params => {
+            let params = get_gravity_params(&mut grpc).await?;
+            match output {
+                OutputFormat::Json => print_json(&params_to_json(&params)),
+                OutputFormat::Text => {
+                    display_params(&params);
+                    Ok(())
+                }
+            }
+        }
+        QuerySubcommand::CurrentValset => {
+            let valset = get_current_valset(&mut grpc).await?;
+            print_valsets(output, &[valset], &mut amounts).await
+        }
+        QuerySubcommand::Valset(opts) => match get_valset(&mut grpc, opts.nonce).await? {
+            Some(valset) => print_valsets(output, &[valset], &mut amounts).await,
+            None => Err(GravityError::ValidationError(format!(
+                "No validator set with nonce {}",
+                opts.nonce
+            ))),
+        },
+        QuerySubcommand::Valsets => {
+            let valsets = get_latest_valsets(&mut grpc).await?;
+            print_valsets(output, &valsets, &mut amounts).await
+        }
+        QuerySubcommand::ValsetConfirms(opts) => {
+            let confirms = get_all_valset_confirms(&mut grpc, opts.nonce).await?;
+            print_valset_confirms(output, &confirms)
+        }
+        QuerySubcommand::Batches => {
+            let batches = get_latest_transaction_batches(&mut grpc).await?;
+            print_batches(output, &batches, &mut amounts).await
+        }
+        QuerySubcommand::Batch(opts) => {
+            let batch =
+                get_transaction_batch_by_nonce(&mut grpc, opts.nonce, opts.token_contract_address)
+                    .await?;
+            print_batches(output, &[batch], &mut amounts).await
+        }
+        QuerySubcommand::BatchConfirms(opts) => query_batch_confirms(&mut grpc, opts, output).await,
+        QuerySubcommand::BatchFees => query_batch_fees(&mut grpc, output, &mut amounts).await,
+        QuerySubcommand::LogicCalls => {
+            let calls = get_latest_logic_calls(&mut grpc).await?;
+            print_logic_calls(output, &calls)
+        }
+        QuerySubcommand::LogicCallConfirms(opts) => {
+            query_logic_call_confirms(&mut grpc, opts, output).await
+        }
+        QuerySubcommand::Attestations(opts) => query_attestations(&mut grpc, opts, output).await,
+        QuerySubcommand::PendingSendToEth(opts) => {
+            let pending = get_pending_send_to_eth(&mut grpc, opts.address).await?;
+            let mut in_batches = Vec::new();
+            for transfer in pending.transfers_in_batches {
+                in_batches.push(BatchTransaction::try_from(transfer)?);
+            }
+            let mut unbatched = Vec::new();
+            for transfer in pending.unbatched_transfers {
+                unbatched.push(BatchTransaction::try_from(transfer)?);
+            }
+            match output {
+                OutputFormat::Json => print_json(&json!({
+                    "transfers_in_batches": in_batches,
+                    "unbatched_transfers": unbatched,
+                })),
+                OutputFormat::Text => {
+                    info!("{} transfers in batches", in_batches.len());
+                    for transfer in in_batches.iter() {
+                        display_transfer(transfer, &mut amounts).await;
+                    }
+                    info!("{} unbatched transfers", unbatched.len());
+                    for transfer in unbatched.iter() {
+                        display_transfer(transfer, &mut amounts).await;
+                    }
+                    Ok(())
+                }
+            }
+        }
+        QuerySubcommand::LastEventNonce(opts) => {
+            let nonce = get_last_event_nonce_for_validator(&mut grpc, opts.address, prefix).await?;
+            match output {
+                OutputFormat::Json => print_json(&json!({ "event_nonce": nonce })),
+                OutputFormat::Text => {
+                    info!("Last event nonce of {} is {}", opts.address, nonce);
+                    Ok(())
+                }
+            }
+        }
+        QuerySubcommand::DenomToErc20(opts) => {
+            let res = get_denom_to_erc20(&mut grpc, opts.denom.clone()).await?;
+            match output {
+                OutputFormat::Json => print_json(&json!({
+                    "erc20": res.erc20,
+                    "cosmos_originated": res.cosmos_originated,
+                })),
+                OutputFormat::Text => {
+                    info!(
+                        "{} is represented by ERC20 {} cosmos originated: {}",
+                        opts.denom, res.erc20, res.cosmos_originated
+                    );
+                    Ok(())
+                }
+            }
+        }
+        QuerySubcommand::Erc20ToDenom(opts) => {
+            let res = get_erc20_to_denom(&mut grpc, opts.erc20).await?;
+            match output {
+                OutputFormat::Json => print_json(&json!({
+                    "denom": res.denom,
+                    "cosmos_originated": res.cosmos_originated,
+                })),
+                OutputFormat::Text => {
+                    info!(
+                        "ERC20 {} is represented by {} cosmos originated: {}",
+                        opts.erc20, res.denom, res.cosmos_originated
+                    );
+                    Ok(())
+                }
+            }
+        }
+        QuerySubcommand::DelegateKeys(opts) => query_delegate_keys(&mut grpc, opts, output).await,
+    }
+}
+
+/// Prints `value` to stdout as JSON, this is kept separate from the log output
+/// so that it can be piped into other tools
+fn print_json<T: Serialize>(value: &T) -> Result<(), GravityError> {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{}", json);
+            Ok(())
+        }
+        Err(e) => Err(GravityError::ValidationError(format!(
+            "Failed to serialize query result {:?}",
+            e
+        ))),
+    }
+}
+
+/// The params keyed by their names in parameter change proposals, decimals such as the slashing
+/// fractions are shown as decimal strings
+fn params_to_json(params: &Params) -> Value {
+    let mut json = Map::new();
+    for (key, _) in GRAVITY_PARAMS.iter() {
+        if let Some(value) = current_param_json(params, key) {
+            json.insert(key.to_string(), value);
+        }
+    }
+    Value::Object(json)
+}
+
+fn display_params(params: &Params) {
+    let params = params_to_json(params);
+    if let Some(params) = params.as_object() {
+        for (key, value) in params {
+            info!("{}: {}", key, value);
+        }
+    }
+}
+
+async fn print_valsets(
+    output: OutputFormat,
+    valsets: &[Valset],
+    amounts: &mut AmountFormatter,
+) -> Result<(), GravityError> {
+    match output {
+        OutputFormat::Json => print_json(&valsets),
+        OutputFormat::Text => {
+            for valset in valsets {
+                let reward = match valset.reward_token {
+                    Some(token) => amounts.format(token, valset.reward_amount).await,
+                    None => valset.reward_amount.to_string(),
+                };
+                info!(
+                    "Valset {} with {} members and {} of {:?} as the reward",
+                    valset.nonce,
+                    valset.members.len(),
+                    reward,
+                    valset.reward_token
+                );
+                for member in valset.members.iter() {
+                    info!("{}", member);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_valset_confirms(
+    output: OutputFormat,
+    confirms: &[ValsetConfirmResponse],
+) -> Result<(), GravityError> {
+    match output {
+        OutputFormat::Json => print_json(&confirms),
+        OutputFormat::Text => {
+            info!("{} confirms", confirms.len());
+            for confirm in confirms {
+                info!(
+                    "Orchestrator {} Ethereum signer {}",
+                    confirm.orchestrator, confirm.eth_address
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn display_transfer(transfer: &BatchTransaction, amounts: &mut AmountFormatter) {
+    let token = transfer.erc20_token.token_contract_address;
+    info!(
+        "Transfer {} from {} to {} of {} with fee {} token {}",
+        transfer.id,
+        transfer.sender,
+        transfer.destination,
+        amounts.format(token, transfer.erc20_token.amount).await,
+        amounts
+            .format(
+                transfer.erc20_fee.token_contract_address,
+                transfer.erc20_fee.amount
+            )
+            .await,
+        token
+    );
+}
+
+async fn print_batches(
+    output: OutputFormat,
+    batches: &[TransactionBatch],
+    amounts: &mut AmountFormatter,
+) -> Result<(), GravityError> {
+    match output {
+        OutputFormat::Json => print_json(&batches),
+        OutputFormat::Text => {
+            for batch in batches {
+                info!(
+                    "Batch {} for token {} with {} transactions, total fee {} timeout {}",
+                    batch.nonce,
+                    batch.token_contract,
+                    batch.transactions.len(),
+                    amounts
+                        .format(batch.token_contract, batch.total_fee.amount)
+                        .await,
+                    batch.batch_timeout
+                );
+                for transfer in batch.transactions.iter() {
+                    display_transfer(transfer, amounts).await;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn query_batch_confirms(
+    grpc: &mut GravityQueryClient<Channel>,
+    opts: BatchQueryOpts,
+    output: OutputFormat,
+) -> Result<(), GravityError> {
+    let confirms: Vec<BatchConfirmResponse> =
+        get_transaction_batch_signatures(grpc, opts.nonce, opts.token_contract_address).await?;
+    match output {
+        OutputFormat::Json => print_json(&confirms),
+        OutputFormat::Text => {
+            info!("{} confirms", confirms.len());
+            for confirm in confirms.iter() {
+                info!(
+                    "Orchestrator {} Ethereum signer {}",
+                    confirm.orchestrator, confirm.ethereum_signer
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn query_batch_fees(
+    grpc: &mut GravityQueryClient<Channel>,
+    output: OutputFormat,
+    amounts: &mut AmountFormatter,
+) -> Result<(), GravityError> {
+    let fees = get_pending_batch_fees(grpc).await?.batch_fees;
+    match output {
+        OutputFormat::Json => print_json(
+            &fees
+                .iter()
+                .map(|f| {
+                    json!({
+                        "token": f.token,
+                        "total_fees": f.total_fees,
+                        "tx_count": f.tx_count,
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        ),
+        OutputFormat::Text => {
+            for fee in fees.iter() {
+                let total_fees = match (
+                    fee.token.parse::<EthAddress>(),
+                    Uint256::from_dec_or_hex_str_restricted(&fee.total_fees),
+                ) {
+                    (Ok(token), Ok(total_fees)) => amounts.format(token, total_fees).await,
+                    _ => fee.total_fees.clone(),
+                };
+                info!(
+                    "Token {} has {} unbatched transactions with {} in fees",
+                    fee.token, fee.tx_count, total_fees
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_logic_calls(output: OutputFormat, calls: &[LogicCall]) -> Result<(), GravityError> {
+    match output {
+        OutputFormat::Json => print_json(&calls),
+        OutputFormat::Text => {
+            for call in calls {
+                info!(
+                    "Logic call to {} invalidation id 0x{} nonce {} timeout {} with {} transfers and {} fees",
+                    call.logic_contract_address,
+                    bytes_to_hex_str(&call.invalidation_id),
+                    call.invalidation_nonce,
+                    call.timeout,
+                    call.transfers.len(),
+                    call.fees.len()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn query_logic_call_confirms(
+    grpc: &mut GravityQueryClient<Channel>,
+    opts: LogicCallQueryOpts,
+    output: OutputFormat,
+) -> Result<(), GravityError> {
+    let invalidation_id = match hex_str_to_bytes(&opts.invalidation_id) {
+        Ok(id) => id,
+        Err(e) => {
+            return Err(GravityError::ValidationError(format!(
+                "Invalid invalidation id {:?}",
+                e
+            )))
+        }
+    };
+    let confirms: Vec<LogicCallConfirmResponse> =
+        get_logic_call_signatures(grpc, invalidation_id, opts.nonce).await?;
+    match output {
+        OutputFormat::Json => print_json(&confirms),
+        OutputFormat::Text => {
+            info!("{} confirms", confirms.len());
+            for confirm in confirms.iter() {
+                info!(
+                    "Orchestrator {} Ethereum signer {}",
+                    confirm.orchestrator, confirm.ethereum_signer
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn query_attestations(
+    grpc: &mut GravityQueryClient<Channel>,
+    opts: AttestationsQueryOpts,
+    output: OutputFormat,
+) -> Result<(), GravityError> {
+    let attestations = get_attestations(grpc, opts.limit).await?;
+    match output {
+        OutputFormat::Json => print_json(
+            &attestations
+                .iter()
+                .map(|a| {
+                    json!({
+                        "observed": a.observed,
+                        "votes": a.votes,
+                        "height": a.height,
+                        "claim": a.claim.as_ref().map(claim_to_json),
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        ),
+        OutputFormat::Text => {
+            for attestation in attestations.iter() {
+                let claim = attestation.claim.as_ref().map(claim_to_json);
+                info!(
+                    "{} at height {} observed: {} with {} votes",
+                    claim
+                        .as_ref()
+                        .and_then(|c| c["type"].as_str())
+                        .unwrap_or("Unknown claim"),
+                    attestation.height,
+                    attestation.observed,
+                    attestation.votes.len()
+                );
+                if let Some(Value::Object(fields)) = claim {
+                    for (name, value) in fields.iter().filter(|(name, _)| *name != "type") {
+                        info!("    {}: {}", name, value);
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Decodes the claim of an attestation into its type, event nonce, Ethereum block height and
+/// the fields of the claim. Claims of an unknown type only include the type
+fn claim_to_json(claim: &Any) -> Value {
+    let decoded = match AttestationClaim::decode(claim) {
+        Some(decoded) => decoded,
+        None => return json!({ "type": claim.type_url }),
+    };
+    let mut fields = match &decoded {
+        AttestationClaim::SendToCosmos(c) => json!({
+            "token_contract": c.token_contract,
+            "amount": c.amount,
+            "ethereum_sender": c.ethereum_sender,
+            "cosmos_receiver": c.cosmos_receiver,
+            "orchestrator": c.orchestrator,
+        }),
+        AttestationClaim::BatchSendToEth(c) => json!({
+            "batch_nonce": c.batch_nonce,
+            "token_contract": c.token_contract,
+            "orchestrator": c.orchestrator,
+        }),
+        AttestationClaim::Erc20Deployed(c) => json!({
+            "cosmos_denom": c.cosmos_denom,
+            "token_contract": c.token_contract,
+            "name": c.name,
+            "symbol": c.symbol,
+            "decimals": c.decimals,
+            "orchestrator": c.orchestrator,
+        }),
+        AttestationClaim::LogicCallExecuted(c) => json!({
+            "invalidation_id": bytes_to_hex_str(&c.invalidation_id),
+            "invalidation_nonce": c.invalidation_nonce,
+            "orchestrator": c.orchestrator,
+        }),
+        AttestationClaim::ValsetUpdated(c) => json!({
+            "valset_nonce": c.valset_nonce,
+            "members": c
+                .members
+                .iter()
+                .map(|m| json!({ "power": m.power, "ethereum_address": m.ethereum_address }))
+                .collect::<Vec<Value>>(),
+            "reward_amount": c.reward_amount,
+            "reward_token": c.reward_token,
+            "orchestrator": c.orchestrator,
+        }),
+    };
+    fields["event_nonce"] = json!(decoded.event_nonce());
+    fields["block_height"] = json!(decoded.block_height());
+    fields["type"] = json!(claim.type_url);
+    fields
+}
+
+async fn query_delegate_keys(
+    grpc: &mut GravityQueryClient<Channel>,
+    opts: DelegateKeysQueryOpts,
+    output: OutputFormat,
+) -> Result<(), GravityError> {
+    let (validator, eth_address, orchestrator) =
+        match (
+            opts.validator_address,
+            opts.eth_address,
+            opts.orchestrator_address,
+        ) {
+            (Some(validator), _, _) => {
+                let res = get_delegate_keys_by_validator(grpc, validator.clone()).await?;
+                (validator, res.eth_address, res.orchestrator_address)
+            }
+            (None, Some(eth_address), _) => {
+                let res = get_delegate_keys_by_eth(grpc, eth_address).await?;
+                (
+                    res.validator_address,
+                    eth_address.to_string(),
+                    res.orchestrator_address,
+                )
+            }
+            (None, None, Some(orchestrator)) => {
+                let res = get_delegate_keys_by_orchestrator(grpc, orchestrator).await?;
+                (
+                    res.validator_address,
+                    res.eth_address,
+                    orchestrator.to_string(),
+                )
+            }
+            (None, None, None) => return Err(GravityError::ValidationError(
+                "One of --validator-address, --eth-address or --orchestrator-address is required"
+                    .into(),
+            )),
+        };
+    match output {
+        OutputFormat::Json => print_json(&json!({
+            "validator_address": validator,
+            "eth_address": eth_address,
+            "orchestrator_address": orchestrator,
+        })),
+        OutputFormat::Text => {
+            info!("Validator: {}", validator);
+            info!("Ethereum address: {}", eth_address);
+            info!("Orchestrator address: {}", orchestrator);
+            Ok(())
+        }
+    }
+}
@@ -1,25 +1,53 @@
+use std::path::PathBuf;
+
 use cosmos_gravity::query::get_gravity_params;
 use gravity_utils::{
-    clarity::constants::ZERO_ADDRESS,
     connection_prep::{
         check_for_eth, check_for_fee, create_rpc_connections, wait_for_cosmos_node_ready,
     },
     error::GravityError,
-    types::{BatchRequestMode, RelayerConfig},
+    types::{BatchRequestMode, GravityBridgeToolsConfig},
 };
 use relayer::main_loop::{relayer_main_loop, TIMEOUT};
 
-use crate::{args::RelayerOpts, utils::print_relaying_explanation};
+use crate::{
+    args::RelayerOpts,
+    config::{
+        resolve_cosmos_key, resolve_ethereum_keys, resolve_fees, DEFAULT_COSMOS_GRPC,
+        DEFAULT_ETHEREUM_RPC,
+    },
+    keys::derive::ethereum_keys,
+    orchestrator::get_contract_address,
+    utils::{print_relaying_explanation, spawn_budget_resume_handler, spawn_config_reload_handler},
+};
 
 pub async fn relayer(
     args: RelayerOpts,
     address_prefix: String,
-    config: &RelayerConfig,
+    config: &GravityBridgeToolsConfig,
+    home_dir: PathBuf,
 ) -> Result<(), GravityError> {
-    let cosmos_grpc = args.cosmos_grpc;
-    let ethereum_rpc = args.ethereum_rpc;
-    let ethereum_key = args.ethereum_key;
-    let cosmos_key = args.cosmos_phrase;
+    // flags and GBT_* environment variables take precedence over the config
+    let cosmos_grpc = args
+        .cosmos_grpc
+        .or_else(|| config.connection.cosmos_grpc.clone())
+        .unwrap_or_else(|| DEFAULT_COSMOS_GRPC.to_string());
+    let ethereum_rpc = args
+        .ethereum_rpc
+        .or_else(|| config.connection.ethereum_rpc.clone())
+        .unwrap_or_else(|| DEFAULT_ETHEREUM_RPC.to_string());
+    let gravity_contract_address = args
+        .gravity_contract_address
+        .or(config.connection.gravity_contract_address);
+    let fees = resolve_fees(args.fees, &config.connection)?;
+    let ethereum_keys = resolve_ethereum_keys(ethereum_keys(&args.ethereum)?, &config.keys)?;
+    if ethereum_keys.is_empty() {
+        return Err(GravityError::UnrecoverableError(
+            "An Ethereum key is required, set --ethereum-key, GBT_ETHEREUM_KEY or `ethereum_key_file` in the config".into(),
+        ));
+    }
+    let cosmos_key = resolve_cosmos_key(args.cosmos_phrase, &config.keys)?;
+    let config = &config.relayer;
 
     let connections = create_rpc_connections(
         address_prefix,
@@ -29,9 +57,10 @@ pub async fn relayer(
     )
     .await;
 
-    let public_eth_key = ethereum_key.to_address();
     info!("Starting Gravity Relayer");
-    info!("Ethereum Address: {}", public_eth_key);
+    for key in ethereum_keys.iter() {
+        info!("Ethereum Address: {}", key.to_address());
+    }
 
     let contact = connections.contact.clone().unwrap();
     let web3 = connections.web3.unwrap();
@@ -41,7 +70,9 @@ pub async fn relayer(
     // we can't move any steps above this because they may fail on an incorrect
     // historic chain state while syncing occurs
     wait_for_cosmos_node_ready(&contact).await;
-    check_for_eth(public_eth_key, &web3).await?;
+    for key in ethereum_keys.iter() {
+        check_for_eth(key.to_address(), &web3).await?;
+    }
 
     // get the gravity parameters
     let params = get_gravity_params(&mut grpc)
@@ -49,30 +80,11 @@ pub async fn relayer(
         .expect("Failed to get Gravity Bridge module parameters!");
 
     // get the gravity contract address, if not provided
-    let contract_address = if let Some(c) = args.gravity_contract_address {
-        c
-    } else {
-        let c = params.bridge_ethereum_address.parse();
-
-        match c {
-            Ok(v) => {
-                if v == ZERO_ADDRESS {
-                    return Err(GravityError::UnrecoverableError(
-                        "The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into(),
-                    ));
-                }
-
-                v
-            }
-            Err(_) => {
-                return Err(GravityError::UnrecoverableError("The Gravity address is not yet set as a chain parameter! You must specify --gravity-contract-address".into()));
-            }
-        }
-    };
+    let contract_address = get_contract_address(gravity_contract_address, &params)?;
     info!("Gravity contract address {}", contract_address);
 
     // setup and explain relayer settings
-    if let Some(fee) = args.fees.clone() {
+    if let (Some(fee), Some(cosmos_key)) = (fees.clone(), cosmos_key) {
         if config.batch_request_mode != BatchRequestMode::None {
             let public_cosmos_key = cosmos_key.to_address(&contact.get_prefix()).unwrap();
             check_for_fee(&fee, public_cosmos_key, &contact).await?;
@@ -83,11 +95,13 @@ pub async fn relayer(
     } else {
         print_relaying_explanation(config, false)
     }
+    spawn_budget_resume_handler();
+    spawn_config_reload_handler(home_dir, config.clone());
 
     relayer_main_loop(
-        ethereum_key,
-        Some(cosmos_key),
-        args.fees,
+        ethereum_keys,
+        cosmos_key,
+        fees,
         web3,
         contact,
         grpc,
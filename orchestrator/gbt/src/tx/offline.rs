@@ -0,0 +1,140 @@
+//! Writes unsigned transactions for commands run with --generate-only, and signs and broadcasts
+//! them with `gbt tx sign` and `gbt tx broadcast`
+
+use std::{fs, path::Path};
+
+use cosmos_gravity::offline::{
+    broadcast_tx, generate_unsigned_tx, sign_tx, EncodedMsg, SignedTx, UnsignedTx,
+};
+use gravity_utils::{
+    connection_prep::create_rpc_connections,
+    deep_space::{Address as CosmosAddress, Coin, Contact, PrivateKey as CosmosPrivateKey},
+    error::GravityError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    args::{GenerateOnlyOpts, TxBroadcastOpts, TxSignOpts},
+    utils::TIMEOUT,
+};
+
+/// Returns the address sending a transaction, which is given with --from when generating an
+/// unsigned transaction and is otherwise the address of the key
+pub fn sender_address(
+    key: Option<CosmosPrivateKey>,
+    offline: &GenerateOnlyOpts,
+    prefix: &str,
+) -> Result<CosmosAddress, GravityError> {
+    match (key, offline.from) {
+        (_, Some(from)) if offline.generate_only.is_some() => Ok(from),
+        (Some(key), _) => Ok(key.to_address(prefix).unwrap()),
+        _ => Err(GravityError::UnrecoverableError(
+            "A key is required unless --generate-only and --from are used".into(),
+        )),
+    }
+}
+
+/// Writes an unsigned transaction of `messages` from `--from` to the --generate-only file
+pub async fn write_unsigned_tx(
+    contact: &Contact,
+    offline: &GenerateOnlyOpts,
+    messages: Vec<EncodedMsg>,
+    fee: Coin,
+) -> Result<(), GravityError> {
+    let (path, from) = match (&offline.generate_only, offline.from) {
+        (Some(path), Some(from)) => (path, from),
+        _ => {
+            return Err(GravityError::UnrecoverableError(
+                "--generate-only and --from are required to write an unsigned transaction".into(),
+            ))
+        }
+    };
+    let tx = generate_unsigned_tx(
+        contact,
+        from,
+        messages,
+        fee,
+        offline.gas_limit,
+        offline.timeout_height,
+    )
+    .await?;
+    write_json(path, &tx)?;
+    info!(
+        "Wrote an unsigned transaction from {} with account number {} and sequence {} to {:?}",
+        from, tx.account_number, tx.sequence, path
+    );
+    info!("Sign it with `gbt tx sign` then submit it with `gbt tx broadcast`");
+    Ok(())
+}
+
+pub fn sign(opts: TxSignOpts) -> Result<(), GravityError> {
+    let tx: UnsignedTx = read_json(&opts.file)?;
+    info!(
+        "Signing {} messages from {} on {} with sequence {}",
+        tx.messages.len(),
+        tx.sender,
+        tx.chain_id,
+        tx.sequence
+    );
+    // every message is decoded and shown before anything is signed
+    for (i, msg) in tx.messages.iter().enumerate() {
+        info!("Message {}: {}", i + 1, msg.describe()?);
+    }
+    info!(
+        "Fee {}{} with a gas limit of {}",
+        tx.fee_amount, tx.fee_denom, tx.gas_limit
+    );
+    info!("Memo \"{}\"", tx.memo);
+    if tx.timeout_height != 0 {
+        info!("Times out at block {}", tx.timeout_height);
+    }
+    let signed = sign_tx(&tx, opts.cosmos_phrase)?;
+    write_json(&opts.output, &signed)?;
+    info!("Wrote the signed transaction to {:?}", opts.output);
+    Ok(())
+}
+
+pub async fn broadcast(opts: TxBroadcastOpts, prefix: String) -> Result<(), GravityError> {
+    let tx: SignedTx = read_json(&opts.file)?;
+    let connections = create_rpc_connections(prefix, Some(opts.cosmos_grpc), None, TIMEOUT).await;
+    let contact = connections.contact.unwrap();
+    let res = broadcast_tx(&contact, &tx).await?;
+    if res.code != 0 {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Transaction {} failed {}",
+            res.txhash, res.raw_log
+        )));
+    }
+    info!("Broadcast transaction txid {}", res.txhash);
+    Ok(())
+}
+
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), GravityError> {
+    let contents = serde_json::to_string_pretty(value).unwrap();
+    if let Err(e) = fs::write(path, contents) {
+        return Err(GravityError::UnrecoverableError(format!(
+            "Failed to write {:?} {:?}",
+            path, e
+        )));
+    }
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, GravityError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return Err(GravityError::UnrecoverableError(format!(
+                "Failed to read {:?} {:?}",
+                path, e
+            )))
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "Failed to parse {:?} {:?}",
+            path, e
+        ))),
+    }
+}
@@ -1,8 +1,98 @@
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
-use gravity_utils::types::{BatchRequestMode, RelayerConfig, ValsetRelayingMode};
+use cosmos_gravity::query::get_denom_to_erc20;
+use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
+use gravity_utils::{
+    clarity::{constants::ZERO_ADDRESS, Address as EthAddress, Uint256},
+    deep_space::Contact,
+    error::GravityError,
+    num_conversion::{format_token_amount, get_denom_decimals, get_erc20_decimals, print_eth},
+    types::{BatchRequestMode, RelayerConfig, ValsetRelayingMode},
+    web30::client::Web3,
+};
+use tonic::transport::Channel;
+
+use crate::config::{get_config_path, load_relayer_config};
 
 pub const TIMEOUT: Duration = Duration::from_secs(60);
+/// How often the config file is checked for changes to the relayer config
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Gets the decimals of `denom`, which amounts of it are given and displayed with. Cosmos denoms
+/// use the exponent of the display unit in their metadata, Ethereum originated vouchers have no
+/// metadata and use the `decimals()` of the ERC20 they represent
+pub async fn get_token_decimals(
+    contact: &Contact,
+    grpc: &mut GravityQueryClient<Channel>,
+    web3: &Web3,
+    denom: &str,
+) -> Result<u8, GravityError> {
+    if let Some(decimals) = get_denom_decimals(contact, denom.to_string()).await? {
+        return Ok(decimals);
+    }
+    let erc20 = match get_denom_to_erc20(grpc, denom.to_string()).await {
+        Ok(res) => res.erc20.parse::<EthAddress>()?,
+        Err(e) => {
+            return Err(GravityError::ValidationError(format!(
+                "{} has no metadata and no ERC20 representation, its decimals are unknown {:?}",
+                denom, e
+            )))
+        }
+    };
+    get_erc20_token_decimals(web3, erc20).await
+}
+
+/// Gets the decimals of an ERC20 from its `decimals()` function on Ethereum
+pub async fn get_erc20_token_decimals(web3: &Web3, erc20: EthAddress) -> Result<u8, GravityError> {
+    match get_erc20_decimals(web3, erc20, ZERO_ADDRESS).await {
+        Ok(decimals) => Ok(decimals),
+        Err(e) => Err(GravityError::ValidationError(format!(
+            "Could not read the decimals of ERC20 {} from {}, the Ethereum rpc is required for Ethereum originated tokens {:?}",
+            erc20,
+            web3.get_url(),
+            e
+        ))),
+    }
+}
+
+/// Formats ERC20 amounts for display using the decimals of each token, which are read from
+/// Ethereum once per token. Amounts of tokens whose decimals can't be read are shown in base units
+pub struct AmountFormatter {
+    web3: Web3,
+    decimals: HashMap<EthAddress, Option<u8>>,
+}
+
+impl AmountFormatter {
+    pub fn new(web3: Web3) -> AmountFormatter {
+        AmountFormatter {
+            web3,
+            decimals: HashMap::new(),
+        }
+    }
+
+    pub async fn format(&mut self, erc20: EthAddress, amount: Uint256) -> String {
+        if !self.decimals.contains_key(&erc20) {
+            let decimals = match get_erc20_token_decimals(&self.web3, erc20).await {
+                Ok(decimals) => Some(decimals),
+                Err(e) => {
+                    warn!("{:?}, amounts of it are shown in base units", e);
+                    None
+                }
+            };
+            self.decimals.insert(erc20, decimals);
+        }
+        match self.decimals[&erc20] {
+            Some(decimals) => format_token_amount(amount, decimals),
+            None => format!("{} base units", amount),
+        }
+    }
+}
 
 /// Explains the relaying config to users
 pub fn print_relaying_explanation(input: &RelayerConfig, batch_requests: bool) {
@@ -40,4 +130,155 @@ pub fn print_relaying_explanation(input: &RelayerConfig, batch_requests: bool) {
         gravity_utils::types::BatchRelayingMode::ProfitableWithWhitelist { margin, whitelist } =>
             info!("This relayer will relay profitable matches with {} margin, and the following tokens with the provided amounts {:?}", margin, whitelist)
     }
+    if input.max_relay_jitter > 0 {
+        info!(
+            "This relayer will wait up to {} seconds before each relay to avoid collisions",
+            input.max_relay_jitter
+        );
+    }
+    if input.check_pending_relays {
+        info!("This relayer will not submit relays that another relayer is already submitting");
+    }
+    if let Some(allowlist) = &input.token_allowlist {
+        info!(
+            "This relayer will only request and relay batches of the following tokens {:?}",
+            allowlist
+        );
+    }
+    if !input.token_denylist.is_empty() {
+        info!(
+            "This relayer will never request or relay batches of the following tokens {:?}",
+            input.token_denylist
+        );
+    }
+    if let Some(private_submission) = &input.private_submission {
+        info!(
+            "This relayer will submit transactions to {} using {:?}, falling back to the public rpc after {} blocks",
+            private_submission.url, private_submission.method, private_submission.fallback_blocks
+        );
+    }
+    let budgets = [
+        ("", Some(input.budget)),
+        ("altruistic valset ", input.valset_budget),
+    ];
+    for (name, budget) in budgets.iter() {
+        if let Some(budget) = budget {
+            if let Some(limit) = budget.hourly_limit {
+                info!(
+                    "This relayer will spend at most {} ETH per hour on {}relaying",
+                    print_eth(limit),
+                    name
+                );
+            }
+            if let Some(limit) = budget.daily_limit {
+                info!(
+                    "This relayer will spend at most {} ETH per day on {}relaying",
+                    print_eth(limit),
+                    name
+                );
+            }
+            if let Some(losses) = budget.max_consecutive_losses {
+                info!("This relayer will pause {}relaying for an hour after {} consecutive loss making relays", name, losses);
+            }
+        }
+    }
+    for token_override in input.token_overrides.iter() {
+        info!(
+            "Batches of {} will be requested with mode {:?} and relayed with mode {:?}",
+            token_override.token,
+            input.get_batch_request_mode(token_override.token),
+            input.get_batch_relaying_mode(token_override.token)
+        );
+    }
+}
+
+/// Resumes any paused relayer budgets when the process receives SIGUSR1
+pub fn spawn_budget_resume_handler() {
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut stream = match signal(SignalKind::user_defined1()) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Could not listen for SIGUSR1, relayer budgets can not be resumed manually {:?}", e);
+                return;
+            }
+        };
+        while stream.recv().await.is_some() {
+            info!("Received SIGUSR1, resuming relayer budgets");
+            relayer::budget::request_resume();
+        }
+    });
+}
+
+/// Reloads the [relayer] section of the config, logging what changed. An invalid config is
+/// rejected and the current config is kept
+fn reload_relayer_config(home_dir: &Path, current: &Mutex<RelayerConfig>) {
+    let reloaded = match load_relayer_config(home_dir) {
+        Ok(reloaded) => reloaded,
+        Err(e) => {
+            error!(
+                "Rejected the changed relayer config, keeping the current config {:?}",
+                e
+            );
+            return;
+        }
+    };
+    let mut current = current.lock().unwrap();
+    let changes = current.diff(&reloaded);
+    if changes.is_empty() {
+        info!("The relayer config is unchanged");
+        return;
+    }
+    for change in changes.iter() {
+        info!("Relayer config changed {}", change);
+    }
+    relayer::config_reload::request_reload(reloaded.clone());
+    *current = reloaded;
+}
+
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Reloads the relayer config when the config file changes or when the process receives
+/// SIGHUP, the relayer applies it at the start of its next loop
+pub fn spawn_config_reload_handler(home_dir: PathBuf, config: RelayerConfig) {
+    let current = Arc::new(Mutex::new(config));
+
+    #[cfg(unix)]
+    {
+        let home_dir = home_dir.clone();
+        let current = current.clone();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let mut stream = match signal(SignalKind::hangup()) {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("Could not listen for SIGHUP, the relayer config is only reloaded when the config file changes {:?}", e);
+                    return;
+                }
+            };
+            while stream.recv().await.is_some() {
+                info!("Received SIGHUP, reloading the relayer config");
+                reload_relayer_config(&home_dir, &current);
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let config_file = get_config_path(&home_dir);
+        let mut last_modified = get_modified(&config_file);
+        loop {
+            tokio::time::sleep(CONFIG_POLL_INTERVAL).await;
+            let modified = get_modified(&config_file);
+            if modified != last_modified {
+                last_modified = modified;
+                info!("{:?} changed, reloading the relayer config", config_file);
+                reload_relayer_config(&home_dir, &current);
+            }
+        }
+    });
 }
@@ -196,6 +196,27 @@ pub async fn create_rpc_connections(
 }
 
 /// Verify that a url has an http or https prefix
+/// Connects to the Gravity gRPC service at `grpc_url` without trying the fallback urls of
+/// `create_rpc_connections`, returning an error instead of panicking if it can not be reached
+pub async fn try_connect_grpc(grpc_url: &str) -> Result<GravityQueryClient<Channel>, GravityError> {
+    match Url::parse(grpc_url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+        _ => {
+            return Err(GravityError::ValidationError(format!(
+                "{} is not an http or https url",
+                grpc_url
+            )))
+        }
+    }
+    match GravityQueryClient::connect(grpc_url.trim_end_matches('/').to_string()).await {
+        Ok(client) => Ok(client),
+        Err(e) => Err(GravityError::UnrecoverableError(format!(
+            "Could not connect to Cosmos gRPC {} {:?}",
+            grpc_url, e
+        ))),
+    }
+}
+
 fn check_scheme(input: &Url, original_string: &str) {
     if !(input.scheme() == "http" || input.scheme() == "https") {
         panic!(
@@ -1,9 +1,18 @@
-use clarity::Uint256;
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use clarity::{Address as EthAddress, Uint256};
+use deep_space::Contact;
+use web30::client::Web3;
+
+use crate::error::GravityError;
 
 const ONE_ETH_FLOAT: f64 = 1000000000000000000.;
 const ONE_GWEI_FLOAT: f64 = 1000000000.;
 const ONE_NOM_FLOAT: f64 = 1000000000000000000.;
 
+/// The decimals of ETH and of the WETH and DAI amounts used to price relaying
+pub const ETH_DECIMALS: u8 = 18;
+
 /// TODO revisit this for higher precision while
 /// still representing the number to the user as a float
 /// this takes a number like 0.37 eth and turns it into wei
@@ -37,6 +46,129 @@ pub fn print_gwei(input: Uint256) -> String {
     format!("{:}", res)
 }
 
+/// A decimal token amount such as `1.25` as entered by a user. It is kept as digits so that it can
+/// be converted exactly into the base units of a token once the token's decimals are known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAmount {
+    whole: String,
+    fraction: String,
+}
+
+impl TokenAmount {
+    /// Converts this amount into the base units of a token with `decimals`, this fails
+    /// rather than rounding if the amount has more decimal places than the token
+    pub fn to_base_units(&self, decimals: u8) -> Result<Uint256, GravityError> {
+        let decimals = decimals as usize;
+        if self.fraction.len() > decimals {
+            return Err(GravityError::ValidationError(format!(
+                "{} has more than the {} decimal places supported by this token",
+                self, decimals
+            )));
+        }
+        let digits = format!("{}{:0<width$}", self.whole, self.fraction, width = decimals);
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0",
+            digits => digits,
+        };
+        Ok(Uint256::from_dec_or_hex_str_restricted(digits)?)
+    }
+}
+
+impl FromStr for TokenAmount {
+    type Err = GravityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (whole, fraction) = match s.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (s, ""),
+        };
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(GravityError::ValidationError(format!(
+                "Invalid token amount {}",
+                s
+            )));
+        }
+        Ok(TokenAmount {
+            whole: whole.to_string(),
+            fraction: fraction.trim_end_matches('0').to_string(),
+        })
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let whole = if self.whole.is_empty() {
+            "0"
+        } else {
+            &self.whole
+        };
+        if self.fraction.is_empty() {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, self.fraction)
+        }
+    }
+}
+
+/// Formats an amount in the base units of a token with `decimals` exactly, without trailing zeros
+pub fn format_token_amount(amount: Uint256, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount.to_string(), width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_string(),
+        fraction => format!("{}.{}", whole, fraction),
+    }
+}
+
+/// Gets the decimals of an ERC20 token using its `decimals()` function
+pub async fn get_erc20_decimals(
+    web3: &Web3,
+    erc20: EthAddress,
+    caller: EthAddress,
+) -> Result<u8, GravityError> {
+    let decimals = web3.get_erc20_decimals(erc20, caller).await?;
+    match decimals.try_resize_to_u64() {
+        Some(decimals) if decimals <= u8::MAX as u64 => Ok(decimals as u8),
+        _ => Err(GravityError::ValidationError(format!(
+            "ERC20 {} has invalid decimals {}",
+            erc20, decimals
+        ))),
+    }
+}
+
+/// Gets the decimals of a Cosmos denom from its metadata, this is the exponent of the display
+/// unit. Returns None if the denom has no metadata
+pub async fn get_denom_decimals(
+    contact: &Contact,
+    denom: String,
+) -> Result<Option<u8>, GravityError> {
+    let metadata = match contact.get_denom_metadata(denom.clone()).await? {
+        Some(metadata) => metadata,
+        None => return Ok(None),
+    };
+    match metadata
+        .denom_units
+        .iter()
+        .find(|unit| unit.denom == metadata.display)
+    {
+        Some(unit) => match u8::try_from(unit.exponent) {
+            Ok(decimals) => Ok(Some(decimals)),
+            Err(_) => Err(GravityError::ValidationError(format!(
+                "Denom {} has invalid exponent {}",
+                denom, unit.exponent
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
 #[test]
 fn even_f32_rounding() {
     use clarity::u256;
@@ -53,3 +185,32 @@ fn even_f32_rounding() {
     let res = fraction_to_exponent(1.1501f64, 18);
     assert_eq!(a_high_precision_number, res);
 }
+
+#[test]
+fn token_amount_conversion() {
+    use clarity::u256;
+    let amount: TokenAmount = "1.1501".parse().unwrap();
+    assert_eq!(
+        amount.to_base_units(18).unwrap(),
+        u256!(1150100000000000000)
+    );
+    let amount: TokenAmount = "123456789.123456789123456789".parse().unwrap();
+    assert_eq!(
+        amount.to_base_units(18).unwrap(),
+        u256!(123456789123456789123456789)
+    );
+    assert_eq!(amount.to_string(), "123456789.123456789123456789");
+    let amount: TokenAmount = ".5".parse().unwrap();
+    assert_eq!(amount.to_base_units(6).unwrap(), u256!(500000));
+    assert!(amount.to_base_units(0).is_err());
+    assert!("1.2.3".parse::<TokenAmount>().is_err());
+    assert!("-1".parse::<TokenAmount>().is_err());
+
+    assert_eq!(
+        format_token_amount(u256!(1150100000000000000), 18),
+        "1.1501"
+    );
+    assert_eq!(format_token_amount(u256!(500000), 6), "0.5");
+    assert_eq!(format_token_amount(u256!(7), 0), "7");
+    assert_eq!(format_token_amount(u256!(2000000), 6), "2");
+}
@@ -10,7 +10,7 @@ use web30::client::Web3;
 use super::*;
 use crate::{
     error::GravityError,
-    num_conversion::print_eth,
+    num_conversion::{format_token_amount, get_erc20_decimals, ETH_DECIMALS},
     prices::{get_dai_price, get_weth_price},
 };
 
@@ -90,6 +90,7 @@ impl TransactionBatch {
         let tx_value_weth = get_weth_price(token, tx_total, pubkey, web30);
         let tx_value_dai = get_dai_price(token, tx_total, pubkey, web30);
         let token_symbol = web30.get_erc20_symbol(token, pubkey);
+        let token_decimals = get_erc20_decimals(web30, token, pubkey);
         let current_block = web30.eth_block_number();
         if let (
             Ok(fee_value_weth),
@@ -97,6 +98,7 @@ impl TransactionBatch {
             Ok(tx_value_weth),
             Ok(tx_value_dai),
             Ok(token_symbol),
+            Ok(token_decimals),
             Ok(current_block),
         ) = join!(
             fee_value_weth,
@@ -104,20 +106,25 @@ impl TransactionBatch {
             tx_value_weth,
             tx_value_dai,
             token_symbol,
+            token_decimals,
             current_block,
         ) {
             info!("Batch Info:");
             info!("Token: {}  Contract Address: {}", token_symbol, token);
             info!(
-                "Contains {} transactions, total value {} DAI {} ETH",
+                "Contains {} transactions, total {} {} value {} DAI {} ETH",
                 self.transactions.len(),
-                print_eth(tx_value_dai),
-                print_eth(tx_value_weth)
+                format_token_amount(tx_total, token_decimals),
+                token_symbol,
+                format_token_amount(tx_value_dai, ETH_DECIMALS),
+                format_token_amount(tx_value_weth, ETH_DECIMALS)
             );
             info!(
-                "Total fee value {} DAI, {} ETH",
-                print_eth(fee_value_dai),
-                print_eth(fee_value_weth)
+                "Total fee {} {} value {} DAI, {} ETH",
+                format_token_amount(fee_total, token_decimals),
+                token_symbol,
+                format_token_amount(fee_value_dai, ETH_DECIMALS),
+                format_token_amount(fee_value_weth, ETH_DECIMALS)
             );
             if current_block < Uint256::from_u64(self.batch_timeout) {
                 let batch_timeout = Uint256::from_u64(self.batch_timeout);
@@ -1,11 +1,17 @@
 //! contains configuration structs that need to be accessed across crates.
 
-use clarity::{Address as EthAddress, Uint256};
+use std::{collections::HashSet, fmt::Debug, net::SocketAddr, path::PathBuf};
+
+use clarity::{constants::ZERO_ADDRESS, Address as EthAddress, Uint256};
 use serde::{Deserialize, Serialize};
 
+use crate::error::GravityError;
+
 /// Global configuration struct for Gravity bridge tools
 #[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
 pub struct GravityBridgeToolsConfig {
+    pub connection: ConnectionConfig,
+    pub keys: KeysConfig,
     pub relayer: RelayerConfig,
     pub orchestrator: OrchestratorConfig,
     pub metrics: MetricsConfig,
@@ -14,6 +20,10 @@ pub struct GravityBridgeToolsConfig {
 /// Toml serializable configuration struct for Gravity bridge tools
 #[derive(Serialize, Deserialize, Debug, PartialEq, Default, Clone)]
 pub struct TomlGravityBridgeToolsConfig {
+    #[serde(default = "ConnectionConfig::default")]
+    pub connection: ConnectionConfig,
+    #[serde(default = "KeysConfig::default")]
+    pub keys: KeysConfig,
     #[serde(default = "TomlRelayerConfig::default")]
     pub relayer: TomlRelayerConfig,
     #[serde(default = "OrchestratorConfig::default")]
@@ -22,9 +32,20 @@ pub struct TomlGravityBridgeToolsConfig {
     pub metrics: MetricsConfig,
 }
 
+impl TomlGravityBridgeToolsConfig {
+    /// Checks the values that parse but can not be used, so that a bad config is reported when
+    /// it is loaded rather than when the value is first used
+    pub fn validate(&self) -> Result<(), GravityError> {
+        self.relayer.validate()?;
+        self.metrics.validate()
+    }
+}
+
 impl From<TomlGravityBridgeToolsConfig> for GravityBridgeToolsConfig {
     fn from(input: TomlGravityBridgeToolsConfig) -> Self {
         GravityBridgeToolsConfig {
+            connection: input.connection,
+            keys: input.keys,
             relayer: input.relayer.into(),
             orchestrator: input.orchestrator,
             metrics: input.metrics,
@@ -32,6 +53,33 @@ impl From<TomlGravityBridgeToolsConfig> for GravityBridgeToolsConfig {
     }
 }
 
+/// The chain and endpoint options of the orchestrator and relayer, every option can be
+/// overridden by a GBT_* environment variable, which can in turn be overridden by a flag
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default, Clone)]
+pub struct ConnectionConfig {
+    /// The address prefix of the Cosmos chain
+    pub address_prefix: Option<String>,
+    /// The Cosmos gRPC server
+    pub cosmos_grpc: Option<String>,
+    /// The Ethereum RPC server
+    pub ethereum_rpc: Option<String>,
+    /// The denom and amount to pay Cosmos chain fees, eg. 100ugraviton
+    pub fees: Option<String>,
+    /// The address of the Gravity contract, by default the one set as a chain parameter
+    pub gravity_contract_address: Option<EthAddress>,
+}
+
+/// References to files holding the keys of the orchestrator and relayer, so that keys can be
+/// mounted as secrets rather than written into the config
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default, Clone)]
+pub struct KeysConfig {
+    /// A file holding the Cosmos mnemonic phrase
+    pub cosmos_phrase_file: Option<PathBuf>,
+    /// A file holding Ethereum private keys, one per line. The orchestrator uses the first key
+    /// and the relayer uses all of them as its pool of keys
+    pub ethereum_key_file: Option<PathBuf>,
+}
+
 /// Relayer configuration options
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct RelayerConfig {
@@ -42,6 +90,27 @@ pub struct RelayerConfig {
     /// the speed at which the relayer loop runs, in seconds
     /// higher values reduce the chances of money lost to a collision
     pub relayer_loop_speed: u64,
+    /// the maximum random delay before each relay, in seconds. Relayers
+    /// running the same loop speed are less likely to collide with jitter
+    pub max_relay_jitter: u64,
+    /// if pending transactions to the Gravity contract should be inspected before
+    /// relaying, backing off if another relayer is already submitting the same nonce
+    pub check_pending_relays: bool,
+    /// If set only batches of these ERC20 contracts will be requested or relayed
+    pub token_allowlist: Option<Vec<EthAddress>>,
+    /// Batches of these ERC20 contracts will never be requested or relayed,
+    /// this takes priority over the allowlist
+    pub token_denylist: Vec<EthAddress>,
+    /// Per token overrides of the batch margin and batch request mode
+    pub token_overrides: Vec<TokenOverride>,
+    /// Limits on the amount of ETH the relayer may spend
+    pub budget: RelayerBudgetConfig,
+    /// If set altruistic validator set relaying is charged to this budget
+    /// instead of the main relayer budget
+    pub valset_budget: Option<RelayerBudgetConfig>,
+    /// If set relayer transactions are submitted to this endpoint instead of
+    /// the public mempool
+    pub private_submission: Option<PrivateSubmissionConfig>,
 }
 
 /// Relayer configuration that's is more easily parsable with toml
@@ -57,6 +126,22 @@ pub struct TomlRelayerConfig {
     pub logic_call_market_enabled: bool,
     #[serde(default = "default_relayer_loop_speed")]
     pub relayer_loop_speed: u64,
+    #[serde(default = "default_max_relay_jitter")]
+    pub max_relay_jitter: u64,
+    #[serde(default = "default_check_pending_relays")]
+    pub check_pending_relays: bool,
+    #[serde(default = "default_token_allowlist")]
+    pub token_allowlist: Option<Vec<EthAddress>>,
+    #[serde(default = "default_token_denylist")]
+    pub token_denylist: Vec<EthAddress>,
+    #[serde(default = "default_token_overrides")]
+    pub token_overrides: Vec<TokenOverride>,
+    #[serde(default = "default_budget")]
+    pub budget: RelayerBudgetConfig,
+    #[serde(default = "default_valset_budget")]
+    pub valset_budget: Option<RelayerBudgetConfig>,
+    #[serde(default = "default_private_submission")]
+    pub private_submission: Option<PrivateSubmissionConfig>,
 }
 
 impl From<TomlRelayerConfig> for RelayerConfig {
@@ -67,6 +152,229 @@ impl From<TomlRelayerConfig> for RelayerConfig {
             batch_request_mode: input.batch_request_mode,
             logic_call_market_enabled: input.logic_call_market_enabled,
             relayer_loop_speed: input.relayer_loop_speed,
+            max_relay_jitter: input.max_relay_jitter,
+            check_pending_relays: input.check_pending_relays,
+            token_allowlist: input.token_allowlist,
+            token_denylist: input.token_denylist,
+            token_overrides: input.token_overrides,
+            budget: input.budget,
+            valset_budget: input.valset_budget,
+            private_submission: input.private_submission,
+        }
+    }
+}
+
+impl TomlRelayerConfig {
+    /// Checks that the relayer config can be converted without panicking and that every margin
+    /// and the loop speed are sensible, so that an invalid config can be rejected at runtime
+    pub fn validate(&self) -> Result<(), GravityError> {
+        let valset_margin = match self.valset_relaying_mode.parse()? {
+            ValsetRelayingMode::ProfitableOnly { margin } => Some(margin),
+            _ => None,
+        };
+        let batch_margin = match self.batch_relaying_mode.parse()? {
+            BatchRelayingMode::ProfitableOnly { margin }
+            | BatchRelayingMode::ProfitableWithWhitelist { margin, .. } => Some(margin),
+            BatchRelayingMode::EveryBatch => None,
+        };
+        if batch_margin.is_none() {
+            if let Some(o) = self.token_overrides.iter().find(|o| o.margin.is_some()) {
+                return Err(GravityError::ValidationError(format!(
+                    "token_overrides sets a margin for {} but batches are relayed in EveryBatch mode, which has no margin",
+                    o.token
+                )));
+            }
+        }
+        let margins = valset_margin
+            .into_iter()
+            .chain(batch_margin)
+            .chain(self.token_overrides.iter().filter_map(|o| o.margin));
+        for margin in margins {
+            if !margin.is_finite() || margin <= 0.0 {
+                return Err(GravityError::ValidationError(format!(
+                    "Invalid margin {}, margins must be positive numbers",
+                    margin
+                )));
+            }
+        }
+        if self.relayer_loop_speed == 0 {
+            return Err(GravityError::ValidationError(
+                "relayer_loop_speed must be at least one second".into(),
+            ));
+        }
+        if let Some(whitelist) = &self.batch_relaying_mode.whitelist {
+            check_tokens("whitelist", whitelist.iter().map(|w| w.token))?;
+        }
+        if let Some(allowlist) = &self.token_allowlist {
+            check_tokens("token_allowlist", allowlist.iter().cloned())?;
+        }
+        check_tokens("token_denylist", self.token_denylist.iter().cloned())?;
+        check_tokens(
+            "token_overrides",
+            self.token_overrides.iter().map(|o| o.token),
+        )
+    }
+}
+
+/// Checks that a list of ERC20 addresses has no zero address and lists every token only once
+fn check_tokens(name: &str, tokens: impl Iterator<Item = EthAddress>) -> Result<(), GravityError> {
+    let mut seen = HashSet::new();
+    for token in tokens {
+        if token == ZERO_ADDRESS {
+            return Err(GravityError::ValidationError(format!(
+                "{} contains the zero address",
+                name
+            )));
+        }
+        if !seen.insert(token) {
+            return Err(GravityError::ValidationError(format!(
+                "{} lists {} more than once",
+                name, token
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Appends a description of the change to `changes` if a config field differs
+fn diff_field<T: PartialEq + Debug>(changes: &mut Vec<String>, name: &str, old: &T, new: &T) {
+    if old != new {
+        changes.push(format!("{}: {:?} => {:?}", name, old, new));
+    }
+}
+
+impl RelayerConfig {
+    /// Describes every field that differs between this config and `new`
+    pub fn diff(&self, new: &RelayerConfig) -> Vec<String> {
+        let mut changes = Vec::new();
+        diff_field(
+            &mut changes,
+            "valset_relaying_mode",
+            &self.valset_relaying_mode,
+            &new.valset_relaying_mode,
+        );
+        diff_field(
+            &mut changes,
+            "batch_request_mode",
+            &self.batch_request_mode,
+            &new.batch_request_mode,
+        );
+        diff_field(
+            &mut changes,
+            "batch_relaying_mode",
+            &self.batch_relaying_mode,
+            &new.batch_relaying_mode,
+        );
+        diff_field(
+            &mut changes,
+            "logic_call_market_enabled",
+            &self.logic_call_market_enabled,
+            &new.logic_call_market_enabled,
+        );
+        diff_field(
+            &mut changes,
+            "relayer_loop_speed",
+            &self.relayer_loop_speed,
+            &new.relayer_loop_speed,
+        );
+        diff_field(
+            &mut changes,
+            "max_relay_jitter",
+            &self.max_relay_jitter,
+            &new.max_relay_jitter,
+        );
+        diff_field(
+            &mut changes,
+            "check_pending_relays",
+            &self.check_pending_relays,
+            &new.check_pending_relays,
+        );
+        diff_field(
+            &mut changes,
+            "token_allowlist",
+            &self.token_allowlist,
+            &new.token_allowlist,
+        );
+        diff_field(
+            &mut changes,
+            "token_denylist",
+            &self.token_denylist,
+            &new.token_denylist,
+        );
+        diff_field(
+            &mut changes,
+            "token_overrides",
+            &self.token_overrides,
+            &new.token_overrides,
+        );
+        diff_field(&mut changes, "budget", &self.budget, &new.budget);
+        diff_field(
+            &mut changes,
+            "valset_budget",
+            &self.valset_budget,
+            &new.valset_budget,
+        );
+        diff_field(
+            &mut changes,
+            "private_submission",
+            &self.private_submission,
+            &new.private_submission,
+        );
+        changes
+    }
+
+    /// Returns true if batches of the given ERC20 may be requested or relayed
+    /// according to the allowlist and denylist
+    pub fn is_token_allowed(&self, token: EthAddress) -> bool {
+        if self.token_denylist.contains(&token) {
+            return false;
+        }
+        match &self.token_allowlist {
+            Some(allowlist) => allowlist.contains(&token),
+            None => true,
+        }
+    }
+
+    fn get_token_override(&self, token: EthAddress) -> Option<&TokenOverride> {
+        self.token_overrides.iter().find(|o| o.token == token)
+    }
+
+    /// Returns the batch request mode for the given ERC20, taking per token
+    /// overrides and the allow/deny lists into account
+    pub fn get_batch_request_mode(&self, token: EthAddress) -> BatchRequestMode {
+        if !self.is_token_allowed(token) {
+            return BatchRequestMode::None;
+        }
+        match self.get_token_override(token) {
+            Some(TokenOverride {
+                batch_request_mode: Some(mode),
+                ..
+            }) => *mode,
+            _ => self.batch_request_mode,
+        }
+    }
+
+    /// Returns the batch relaying mode for the given ERC20, a margin override
+    /// replaces the margin of the profitable modes and does not apply to EveryBatch
+    pub fn get_batch_relaying_mode(&self, token: EthAddress) -> BatchRelayingMode {
+        let margin = match self.get_token_override(token) {
+            Some(TokenOverride {
+                margin: Some(margin),
+                ..
+            }) => *margin,
+            _ => return self.batch_relaying_mode.clone(),
+        };
+        match &self.batch_relaying_mode {
+            BatchRelayingMode::EveryBatch => BatchRelayingMode::EveryBatch,
+            BatchRelayingMode::ProfitableOnly { .. } => {
+                BatchRelayingMode::ProfitableOnly { margin }
+            }
+            BatchRelayingMode::ProfitableWithWhitelist { whitelist, .. } => {
+                BatchRelayingMode::ProfitableWithWhitelist {
+                    margin,
+                    whitelist: whitelist.clone(),
+                }
+            }
         }
     }
 }
@@ -93,21 +401,33 @@ pub struct TomlValsetRelayingMode {
     margin: Option<f64>,
 }
 
-impl From<TomlValsetRelayingMode> for ValsetRelayingMode {
-    fn from(input: TomlValsetRelayingMode) -> Self {
-        match input.mode.as_str() {
-            "ProfitableOnly" | "profitableonly" | "PROFITABLEONLY" => {
-                ValsetRelayingMode::ProfitableOnly {
-                    margin: input.margin.unwrap(),
-                }
+impl TomlValsetRelayingMode {
+    fn parse(&self) -> Result<ValsetRelayingMode, GravityError> {
+        match (self.mode.as_str(), self.margin) {
+            ("ProfitableOnly" | "profitableonly" | "PROFITABLEONLY", Some(margin)) => {
+                Ok(ValsetRelayingMode::ProfitableOnly { margin })
             }
-            "Altruistic" | "altruistic" | "ALTRUISTIC" => ValsetRelayingMode::Altruistic,
-            "EveryValset" | "everyvalset" | "EVERYVALSET" => ValsetRelayingMode::EveryValset,
-            _ => panic!("Invalid TomlValsetRelayingMode"),
+            ("ProfitableOnly" | "profitableonly" | "PROFITABLEONLY", None) => Err(
+                GravityError::ValidationError("valset_relaying_mode requires a margin".into()),
+            ),
+            ("Altruistic" | "altruistic" | "ALTRUISTIC", _) => Ok(ValsetRelayingMode::Altruistic),
+            ("EveryValset" | "everyvalset" | "EVERYVALSET", _) => {
+                Ok(ValsetRelayingMode::EveryValset)
+            }
+            (mode, _) => Err(GravityError::ValidationError(format!(
+                "Invalid valset_relaying_mode {}",
+                mode
+            ))),
         }
     }
 }
 
+impl From<TomlValsetRelayingMode> for ValsetRelayingMode {
+    fn from(input: TomlValsetRelayingMode) -> Self {
+        input.parse().expect("Invalid TomlValsetRelayingMode")
+    }
+}
+
 /// The various possible modes for automatic requests of batches
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum BatchRequestMode {
@@ -131,6 +451,55 @@ pub struct WhitelistToken {
     pub token: EthAddress,
 }
 
+/// Overrides the global batch settings for a specific token
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct TokenOverride {
+    /// the ERC20 contract this override applies to
+    pub token: EthAddress,
+    /// the margin to use for batches of this token instead of
+    /// the margin of the batch relaying mode
+    pub margin: Option<f64>,
+    /// the batch request mode to use for this token
+    pub batch_request_mode: Option<BatchRequestMode>,
+}
+
+/// Spending limits for the relayer, once a limit is hit relaying is paused
+/// until enough spending has left the rolling window or until it is resumed manually
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct RelayerBudgetConfig {
+    /// The maximum amount of wei that may be spent in any rolling one hour window
+    pub hourly_limit: Option<Uint256>,
+    /// The maximum amount of wei that may be spent in any rolling 24 hour window
+    pub daily_limit: Option<Uint256>,
+    /// The number of loss making relays in a row after which relaying is paused
+    /// for an hour
+    pub max_consecutive_losses: Option<u64>,
+}
+
+/// A private endpoint relayer transactions are submitted to, keeping them out of the
+/// public mempool where they could be front run or copied by other relayers
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PrivateSubmissionConfig {
+    /// The url of the private endpoint
+    pub url: String,
+    /// How transactions are submitted to the private endpoint
+    pub method: PrivateSubmissionMethod,
+    /// The number of blocks to wait for the transaction to be included before
+    /// broadcasting it through the public Ethereum rpc
+    #[serde(default = "default_fallback_blocks")]
+    pub fallback_blocks: u64,
+}
+
+/// The ways a transaction can be submitted to a private endpoint
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PrivateSubmissionMethod {
+    /// Sent with eth_sendRawTransaction to a private rpc, such as Flashbots Protect
+    RawTransaction,
+    /// Sent as a single transaction bundle with eth_sendBundle, authenticated with
+    /// a Flashbots signature header. The bundle is resubmitted for every block
+    Bundle,
+}
+
 /// The various possible modes for batch relaying
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum BatchRelayingMode {
@@ -159,26 +528,54 @@ pub struct TomlBatchRelayingMode {
     whitelist: Option<Vec<WhitelistToken>>,
 }
 
-impl From<TomlBatchRelayingMode> for BatchRelayingMode {
-    fn from(input: TomlBatchRelayingMode) -> Self {
-        match input.mode.as_str() {
-            "EveryBatch" | "everybatch" | "EVERYBATCH" => BatchRelayingMode::EveryBatch,
-            "ProfitableOnly" | "profitableonly" | "PROFITABLEONLY" => {
-                BatchRelayingMode::ProfitableOnly {
-                    margin: input.margin.unwrap(),
-                }
+impl TomlBatchRelayingMode {
+    fn parse(&self) -> Result<BatchRelayingMode, GravityError> {
+        match (self.mode.as_str(), self.margin, &self.whitelist) {
+            ("EveryBatch" | "everybatch" | "EVERYBATCH", _, _) => Ok(BatchRelayingMode::EveryBatch),
+            ("ProfitableOnly" | "profitableonly" | "PROFITABLEONLY", Some(margin), _) => {
+                Ok(BatchRelayingMode::ProfitableOnly { margin })
             }
-            "ProfitableWithWhitelist" | "profitablewithwhitelist" | "PROFITABLEWITHWHITELIST" => {
-                BatchRelayingMode::ProfitableWithWhitelist {
-                    margin: input.margin.unwrap(),
-                    whitelist: input.whitelist.unwrap(),
-                }
-            }
-            _ => panic!("Bad TomlBatchRelayingMode"),
+            (
+                "ProfitableWithWhitelist" | "profitablewithwhitelist" | "PROFITABLEWITHWHITELIST",
+                Some(margin),
+                Some(whitelist),
+            ) => Ok(BatchRelayingMode::ProfitableWithWhitelist {
+                margin,
+                whitelist: whitelist.clone(),
+            }),
+            (
+                "ProfitableWithWhitelist" | "profitablewithwhitelist" | "PROFITABLEWITHWHITELIST",
+                Some(_),
+                None,
+            ) => Err(GravityError::ValidationError(
+                "batch_relaying_mode ProfitableWithWhitelist requires a whitelist".into(),
+            )),
+            (
+                "ProfitableOnly"
+                | "profitableonly"
+                | "PROFITABLEONLY"
+                | "ProfitableWithWhitelist"
+                | "profitablewithwhitelist"
+                | "PROFITABLEWITHWHITELIST",
+                None,
+                _,
+            ) => Err(GravityError::ValidationError(
+                "batch_relaying_mode requires a margin".into(),
+            )),
+            (mode, _, _) => Err(GravityError::ValidationError(format!(
+                "Invalid batch_relaying_mode {}",
+                mode
+            ))),
         }
     }
 }
 
+impl From<TomlBatchRelayingMode> for BatchRelayingMode {
+    fn from(input: TomlBatchRelayingMode) -> Self {
+        input.parse().expect("Bad TomlBatchRelayingMode")
+    }
+}
+
 fn default_batch_relaying_mode() -> TomlBatchRelayingMode {
     TomlBatchRelayingMode {
         mode: "ProfitableOnly".to_string(),
@@ -206,6 +603,42 @@ fn default_relayer_loop_speed() -> u64 {
     600
 }
 
+fn default_max_relay_jitter() -> u64 {
+    0
+}
+
+fn default_check_pending_relays() -> bool {
+    false
+}
+
+fn default_token_allowlist() -> Option<Vec<EthAddress>> {
+    None
+}
+
+fn default_token_denylist() -> Vec<EthAddress> {
+    Vec::new()
+}
+
+fn default_token_overrides() -> Vec<TokenOverride> {
+    Vec::new()
+}
+
+fn default_budget() -> RelayerBudgetConfig {
+    RelayerBudgetConfig::default()
+}
+
+fn default_valset_budget() -> Option<RelayerBudgetConfig> {
+    None
+}
+
+fn default_private_submission() -> Option<PrivateSubmissionConfig> {
+    None
+}
+
+fn default_fallback_blocks() -> u64 {
+    5
+}
+
 impl Default for RelayerConfig {
     fn default() -> Self {
         RelayerConfig {
@@ -214,6 +647,14 @@ impl Default for RelayerConfig {
             batch_relaying_mode: default_batch_relaying_mode().into(),
             logic_call_market_enabled: default_logic_call_market_enabled(),
             relayer_loop_speed: default_relayer_loop_speed(),
+            max_relay_jitter: default_max_relay_jitter(),
+            check_pending_relays: default_check_pending_relays(),
+            token_allowlist: default_token_allowlist(),
+            token_denylist: default_token_denylist(),
+            token_overrides: default_token_overrides(),
+            budget: default_budget(),
+            valset_budget: default_valset_budget(),
+            private_submission: default_private_submission(),
         }
     }
 }
@@ -226,6 +667,14 @@ impl Default for TomlRelayerConfig {
             batch_relaying_mode: default_batch_relaying_mode(),
             logic_call_market_enabled: default_logic_call_market_enabled(),
             relayer_loop_speed: default_relayer_loop_speed(),
+            max_relay_jitter: default_max_relay_jitter(),
+            check_pending_relays: default_check_pending_relays(),
+            token_allowlist: default_token_allowlist(),
+            token_denylist: default_token_denylist(),
+            token_overrides: default_token_overrides(),
+            budget: default_budget(),
+            valset_budget: default_valset_budget(),
+            private_submission: default_private_submission(),
         }
     }
 }
@@ -261,6 +710,19 @@ pub struct MetricsConfig {
     pub metrics_bind: String,
 }
 
+impl MetricsConfig {
+    /// Checks that the metrics server can bind to `metrics_bind`
+    pub fn validate(&self) -> Result<(), GravityError> {
+        match self.metrics_bind.parse::<SocketAddr>() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GravityError::ValidationError(format!(
+                "metrics_bind {} is not an ip:port {:?}",
+                self.metrics_bind, e
+            ))),
+        }
+    }
+}
+
 fn default_metrics_enabled() -> bool {
     false
 }
@@ -277,3 +739,156 @@ impl Default for MetricsConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(last: u8) -> EthAddress {
+        let mut bytes = [0u8; 20];
+        bytes[19] = last;
+        EthAddress::from_slice(&bytes).unwrap()
+    }
+
+    fn batch_mode(mode: &str, margin: Option<f64>) -> TomlBatchRelayingMode {
+        TomlBatchRelayingMode {
+            mode: mode.to_string(),
+            margin,
+            whitelist: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_relaying_modes() {
+        let valset = |mode: &str, margin| TomlValsetRelayingMode {
+            mode: mode.to_string(),
+            margin,
+        };
+        assert_eq!(
+            valset("profitableonly", Some(1.5)).parse().unwrap(),
+            ValsetRelayingMode::ProfitableOnly { margin: 1.5 }
+        );
+        assert_eq!(
+            valset("Altruistic", None).parse().unwrap(),
+            ValsetRelayingMode::Altruistic
+        );
+        assert!(valset("ProfitableOnly", None).parse().is_err());
+        assert!(valset("Sometimes", None).parse().is_err());
+
+        assert_eq!(
+            batch_mode("EVERYBATCH", None).parse().unwrap(),
+            BatchRelayingMode::EveryBatch
+        );
+        assert_eq!(
+            batch_mode("ProfitableOnly", Some(1.1)).parse().unwrap(),
+            BatchRelayingMode::ProfitableOnly { margin: 1.1 }
+        );
+        assert!(batch_mode("ProfitableOnly", None).parse().is_err());
+        assert!(batch_mode("ProfitableWithWhitelist", None).parse().is_err());
+        assert!(batch_mode("ProfitableWithWhitelist", Some(1.1))
+            .parse()
+            .is_err());
+        assert!(batch_mode("Profitable", Some(1.1)).parse().is_err());
+
+        let whitelist = vec![WhitelistToken {
+            amount: Uint256::from_u64(100),
+            token: token(1),
+        }];
+        let mode = TomlBatchRelayingMode {
+            mode: "ProfitableWithWhitelist".to_string(),
+            margin: Some(1.1),
+            whitelist: Some(whitelist.clone()),
+        };
+        assert_eq!(
+            mode.parse().unwrap(),
+            BatchRelayingMode::ProfitableWithWhitelist {
+                margin: 1.1,
+                whitelist
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_relayer_config() {
+        TomlRelayerConfig::default().validate().unwrap();
+
+        let invalid = [
+            TomlRelayerConfig {
+                batch_relaying_mode: batch_mode("Sometimes", Some(1.1)),
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                batch_relaying_mode: batch_mode("ProfitableOnly", None),
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                batch_relaying_mode: batch_mode("ProfitableWithWhitelist", Some(1.1)),
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                batch_relaying_mode: batch_mode("ProfitableOnly", Some(-1.0)),
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                valset_relaying_mode: TomlValsetRelayingMode {
+                    mode: "ProfitableOnly".to_string(),
+                    margin: Some(f64::NAN),
+                },
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                relayer_loop_speed: 0,
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                token_denylist: vec![token(1), token(1)],
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                token_allowlist: Some(vec![ZERO_ADDRESS]),
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                token_overrides: vec![TokenOverride {
+                    token: token(1),
+                    margin: Some(0.0),
+                    batch_request_mode: None,
+                }],
+                ..Default::default()
+            },
+            TomlRelayerConfig {
+                batch_relaying_mode: batch_mode("EveryBatch", None),
+                token_overrides: vec![TokenOverride {
+                    token: token(1),
+                    margin: Some(1.5),
+                    batch_request_mode: None,
+                }],
+                ..Default::default()
+            },
+        ];
+        for config in invalid.iter() {
+            assert!(config.validate().is_err(), "{:?} is valid", config);
+        }
+    }
+
+    #[test]
+    fn test_relayer_config_diff() {
+        let old = RelayerConfig::default();
+        assert!(old.diff(&old.clone()).is_empty());
+
+        let new = RelayerConfig {
+            batch_relaying_mode: BatchRelayingMode::ProfitableOnly { margin: 1.5 },
+            relayer_loop_speed: 60,
+            token_denylist: vec![token(1)],
+            ..old.clone()
+        };
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes[0],
+            "batch_relaying_mode: ProfitableOnly { margin: 1.1 } => ProfitableOnly { margin: 1.5 }"
+        );
+        assert_eq!(changes[1], "relayer_loop_speed: 600 => 60");
+        assert!(changes[2].starts_with("token_denylist: [] => ["));
+    }
+}
@@ -23,7 +23,7 @@ pub const GRAVITY_POWER_TO_PASS: u64 = 2863311530;
 
 /// takes in an amount of power in the gravity bridge, returns a percentage of total
 /// use this for printing values to users only, for accurate calculations use GRAVITY_POWER_TO_PASS
-fn gravity_power_to_percent(input: u64) -> f32 {
+pub fn gravity_power_to_percent(input: u64) -> f32 {
     (input as f32 / TOTAL_GRAVITY_POWER as f32) * 100f32
 }
 /// This trait implements an overarching interface for signature confirmations
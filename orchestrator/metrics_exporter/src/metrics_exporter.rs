@@ -36,6 +36,14 @@ lazy_static! {
     // Information gauges
     pub static ref LATEST_INFO: IntGaugeVec =
         register_int_gauge_vec!("orchestrator_information", "Latest orchestrator information", &["gauge"]).unwrap();
+
+    // Relayer keys
+    pub static ref RELAYER_KEY_INFO: IntGaugeVec =
+        register_int_gauge_vec!("relayer_key_information", "Latest information for each relayer Ethereum key", &["address", "gauge"]).unwrap();
+    pub static ref RELAYER_KEY_SPEND: IntCounterVec =
+        register_int_counter_vec!("relayer_key_spend_gwei", "Estimated gwei spent by each relayer Ethereum key", &["address"]).unwrap();
+    pub static ref RELAYER_KEY_SUBMISSIONS: IntCounterVec =
+        register_int_counter_vec!("relayer_key_submissions", "Submissions by each relayer Ethereum key", &["address", "result"]).unwrap();
 }
 
 pub fn metrics_errors_counter(s: i32, e: &str) {
@@ -67,6 +75,22 @@ pub fn metrics_latest(u: u64, e: &str) {
     }
 }
 
+pub fn metrics_relayer_key_latest(address: &str, u: u64, e: &str) {
+    if let Ok(u) = i64::try_from(u) {
+        RELAYER_KEY_INFO.with_label_values(&[address, e]).set(u);
+    }
+}
+
+pub fn metrics_relayer_key_submission(address: &str, spend_gwei: u64, success: bool) {
+    RELAYER_KEY_SPEND
+        .with_label_values(&[address])
+        .inc_by(spend_gwei);
+    let result = if success { "success" } else { "failure" };
+    RELAYER_KEY_SUBMISSIONS
+        .with_label_values(&[address, result])
+        .inc();
+}
+
 pub fn metrics_server(config: &MetricsConfig) {
     // Parse address used to bind exporter to.
     let addr_raw = &config.metrics_bind;
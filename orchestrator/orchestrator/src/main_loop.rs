@@ -77,7 +77,7 @@ pub async fn orchestrator_main_loop(
     );
 
     let c = relayer_main_loop(
-        ethereum_key,
+        vec![ethereum_key],
         Some(cosmos_key),
         Some(fee),
         web3,
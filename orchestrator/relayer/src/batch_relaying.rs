@@ -2,14 +2,16 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use cosmos_gravity::query::{get_latest_transaction_batches, get_transaction_batch_signatures};
 use ethereum_gravity::{
-    message_signatures::encode_tx_batch_confirm_hashed, submit_batch::send_eth_transaction_batch,
-    utils::get_tx_batch_nonce,
+    message_signatures::encode_tx_batch_confirm_hashed,
+    pending_calls::PendingGravityCall,
+    submit_batch::send_eth_transaction_batch,
+    utils::{get_tx_batch_nonce, RelayOutcome},
 };
 use futures::stream::{self, StreamExt};
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_utils::{
-    clarity::{address::Address as EthAddress, PrivateKey as EthPrivateKey, Uint256},
-    num_conversion::{print_eth, print_gwei},
+    clarity::{address::Address as EthAddress, Uint256},
+    num_conversion::{format_token_amount, print_gwei, ETH_DECIMALS},
     prices::get_weth_price,
     types::{
         BatchConfirmResponse, BatchRelayingMode, RelayerConfig, TransactionBatch, Valset,
@@ -20,6 +22,12 @@ use gravity_utils::{
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
 use tonic::transport::Channel;
 
+use crate::{
+    budget::{RelayKind, RelayerBudget},
+    collision_avoidance::should_back_off,
+    key_pool::EthKeyPool,
+};
+
 #[derive(Debug, Clone)]
 struct SubmittableBatch {
     batch: TransactionBatch,
@@ -37,28 +45,30 @@ struct SubmittableBatch {
 pub async fn relay_batches(
     // the validator set currently in the contract on Ethereum
     current_valset: &Valset,
-    ethereum_key: EthPrivateKey,
+    ethereum_keys: &EthKeyPool,
     web3: &Web3,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
     config: &RelayerConfig,
+    budget: &RelayerBudget,
 ) {
     let possible_batches =
-        get_batches_and_signatures(current_valset, grpc_client, gravity_id.clone()).await;
+        get_batches_and_signatures(current_valset, grpc_client, gravity_id.clone(), config).await;
 
     trace!("possible batches {:?}", possible_batches);
 
     submit_batches(
         current_valset,
-        ethereum_key,
+        ethereum_keys,
         web3,
         gravity_contract_address,
         gravity_id,
         timeout,
         possible_batches,
         config,
+        budget,
     )
     .await;
 }
@@ -75,6 +85,7 @@ async fn get_batches_and_signatures(
     current_valset: &Valset,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_id: String,
+    config: &RelayerConfig,
 ) -> HashMap<EthAddress, Vec<SubmittableBatch>> {
     let latest_batches = if let Ok(lb) = get_latest_transaction_batches(grpc_client).await {
         lb
@@ -85,6 +96,14 @@ async fn get_batches_and_signatures(
 
     let mut possible_batches = HashMap::new();
     for batch in latest_batches {
+        if !config.is_token_allowed(batch.token_contract) {
+            trace!(
+                "Skipping batch {}/{}, token is not allowed by config",
+                batch.token_contract,
+                batch.nonce
+            );
+            continue;
+        }
         let signatures =
             get_transaction_batch_signatures(grpc_client, batch.nonce, batch.token_contract).await;
 
@@ -214,15 +233,16 @@ pub fn get_cost_with_margin(cost: Uint256, margin: f64) -> Uint256 {
 /// submit individual batches but also batches in different orders
 async fn submit_batches(
     current_valset: &Valset,
-    ethereum_key: EthPrivateKey,
+    ethereum_keys: &EthKeyPool,
     web3: &Web3,
     gravity_contract_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
     possible_batches: HashMap<EthAddress, Vec<SubmittableBatch>>,
     config: &RelayerConfig,
+    budget: &RelayerBudget,
 ) {
-    let our_ethereum_address = ethereum_key.to_address();
+    let our_ethereum_address = ethereum_keys.primary().to_address();
     let ethereum_block_height = if let Ok(bn) = web3.eth_block_number().await {
         bn
     } else {
@@ -276,6 +296,13 @@ async fn submit_batches(
 
             let latest_cosmos_batch_nonce = oldest_signed_batch.clone().nonce;
             if latest_cosmos_batch_nonce > latest_ethereum_batch {
+                let ethereum_key = match ethereum_keys
+                    .select_key(web3, Uint256::default(), timeout)
+                    .await
+                {
+                    Some(key) => key,
+                    None => continue,
+                };
                 let cost = ethereum_gravity::submit_batch::estimate_tx_batch_cost(
                     current_valset,
                     oldest_signed_batch.clone(),
@@ -296,7 +323,7 @@ async fn submit_batches(
                     "We have detected a batch to relay. This batch is estimated to cost {} Gas @ {} gwei / {:.4} ETH to submit",
                     cost.gas,
                     print_gwei(cost.gas_price),
-                    print_eth(cost.get_total())
+                    format_token_amount(cost.get_total(), ETH_DECIMALS)
                 );
                 oldest_signed_batch
                     .display_with_eth_info(our_ethereum_address, web3)
@@ -307,11 +334,35 @@ async fn submit_batches(
                     &oldest_signed_batch,
                     cost.get_total(),
                     our_ethereum_address,
-                    &config.batch_relaying_mode,
+                    &config.get_batch_relaying_mode(oldest_signed_batch.token_contract),
                 )
                 .await;
 
-                if should_relay {
+                if should_relay && budget.can_spend(RelayKind::Batch, cost.get_total()) {
+                    let relay = PendingGravityCall::Batch {
+                        token_contract: oldest_signed_batch.token_contract,
+                        nonce: oldest_signed_batch.nonce,
+                    };
+                    if should_back_off(
+                        web3,
+                        gravity_contract_address,
+                        our_ethereum_address,
+                        &relay,
+                        timeout,
+                        config,
+                    )
+                    .await
+                    {
+                        continue;
+                    }
+                    let ethereum_key = match ethereum_keys
+                        .select_key_for_cost(web3, ethereum_key, cost.get_total(), timeout)
+                        .await
+                    {
+                        Some(key) => key,
+                        None => continue,
+                    };
+                    let reward = oldest_signed_batch.total_fee;
                     let res = send_eth_transaction_batch(
                         current_valset,
                         oldest_signed_batch,
@@ -321,11 +372,41 @@ async fn submit_batches(
                         gravity_contract_address,
                         gravity_id.clone(),
                         ethereum_key,
+                        config.private_submission.as_ref(),
                     )
                     .await;
-                    if res.is_err() {
-                        info!("Batch submission failed with {:?}", res);
+                    let outcome = match res {
+                        Ok(outcome) => outcome,
+                        Err(e) => {
+                            info!("Batch submission failed with {:?}", e);
+                            ethereum_keys.record_submission(ethereum_key, cost.get_total(), false);
+                            RelayOutcome::Skipped
+                        }
+                    };
+                    // only a transaction that was actually sent has spent gas, one that was
+                    // not confirmed is charged to the budget without counting as a success,
+                    // a reward that can't be priced is neither a loss nor a profit
+                    if outcome == RelayOutcome::Sent {
+                        ethereum_keys.record_submission(ethereum_key, cost.get_total(), false);
+                        budget.record_spend(RelayKind::Batch, cost.get_total(), None);
+                    } else if outcome == RelayOutcome::Confirmed {
+                        ethereum_keys.record_submission(ethereum_key, cost.get_total(), true);
+                        let loss = get_weth_price(
+                            reward.token_contract_address,
+                            reward.amount,
+                            our_ethereum_address,
+                            web3,
+                        )
+                        .await
+                        .ok()
+                        .map(|price| price < cost.get_total());
+                        budget.record_spend(RelayKind::Batch, cost.get_total(), loss);
                     }
+                } else if should_relay {
+                    info!(
+                        "Not relaying batch {}/{} due to the relayer budget",
+                        oldest_signed_batch.token_contract, oldest_signed_batch.nonce
+                    );
                 } else {
                     info!(
                         "Not relaying batch {}/{} due to it not being profitable",
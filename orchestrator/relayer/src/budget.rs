@@ -0,0 +1,335 @@
+//! This module tracks the ETH spent by the relayer and acts as a circuit breaker, pausing
+//! relaying once a configured spending limit or number of consecutive losses has been hit.
+//! A paused budget resumes on it's own once enough spending has left the rolling window,
+//! or manually by calling request_resume()
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use gravity_utils::{
+    clarity::Uint256,
+    num_conversion::{format_token_amount, ETH_DECIMALS},
+    types::{RelayerBudgetConfig, RelayerConfig, ValsetRelayingMode},
+};
+use lazy_static::lazy_static;
+use metrics_exporter::{metrics_latest, metrics_warnings_counter};
+
+const ONE_HOUR: Duration = Duration::from_secs(60 * 60);
+const ONE_DAY: Duration = Duration::from_secs(24 * 60 * 60);
+const ONE_GWEI: u64 = 1_000_000_000;
+
+lazy_static! {
+    // incremented every time a manual resume is requested, each budget tracks
+    // the last value it has seen so that every budget is resumed exactly once
+    static ref RESUME_REQUESTS: AtomicU64 = AtomicU64::new(0);
+}
+
+/// Resumes every paused relayer budget, clearing the recorded spending
+/// and consecutive losses
+pub fn request_resume() {
+    RESUME_REQUESTS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// The type of relay being paid for, used to select the budget it is charged to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayKind {
+    Valset,
+    Batch,
+    LogicCall,
+}
+
+/// Tracks relayer spending against the configured limits
+pub struct RelayerBudget {
+    general: Mutex<BudgetTracker>,
+    /// a separate budget for altruistic valset relaying, if configured
+    valset: Option<Mutex<BudgetTracker>>,
+}
+
+impl RelayerBudget {
+    pub fn new(config: &RelayerConfig) -> RelayerBudget {
+        let valset = match (config.valset_relaying_mode, config.valset_budget) {
+            (ValsetRelayingMode::Altruistic, Some(valset_budget)) => {
+                Some(Mutex::new(BudgetTracker::new("valset", valset_budget)))
+            }
+            _ => None,
+        };
+        RelayerBudget {
+            general: Mutex::new(BudgetTracker::new("general", config.budget)),
+            valset,
+        }
+    }
+
+    /// Applies the limits of a reloaded config, the spending recorded so far is kept and
+    /// counts towards the new limits. The valset budget is dropped, along with it's recorded
+    /// spending, if the new config no longer charges valsets to a separate budget
+    pub fn update_limits(&mut self, config: &RelayerConfig) {
+        self.general.get_mut().unwrap().config = config.budget;
+        self.valset = match (config.valset_relaying_mode, config.valset_budget) {
+            (ValsetRelayingMode::Altruistic, Some(valset_budget)) => match self.valset.take() {
+                Some(mut valset) => {
+                    valset.get_mut().unwrap().config = valset_budget;
+                    Some(valset)
+                }
+                None => Some(Mutex::new(BudgetTracker::new("valset", valset_budget))),
+            },
+            _ => None,
+        };
+    }
+
+    fn get_tracker(&self, kind: RelayKind) -> &Mutex<BudgetTracker> {
+        match (kind, &self.valset) {
+            (RelayKind::Valset, Some(valset)) => valset,
+            _ => &self.general,
+        }
+    }
+
+    /// Returns true if a relay with the given estimated cost fits within the budget,
+    /// logs the reason and returns false if the budget is paused or would be exceeded
+    pub fn can_spend(&self, kind: RelayKind, cost: Uint256) -> bool {
+        let mut tracker = self.get_tracker(kind).lock().unwrap();
+        match tracker.check(cost, Instant::now()) {
+            Ok(()) => true,
+            Err(reason) => {
+                warn!(
+                    "Not relaying {:?} costing {} ETH, the {} relayer budget is paused: {}",
+                    kind,
+                    format_token_amount(cost, ETH_DECIMALS),
+                    tracker.name,
+                    reason
+                );
+                metrics_warnings_counter(1, "Relayer budget paused");
+                false
+            }
+        }
+    }
+
+    /// Records the cost of a submitted relay, `loss` should be true if the reward
+    /// was worth less than the cost of relaying, or None if the relay had no reward
+    /// or it could not be priced, which neither counts as a loss nor resets the losses
+    pub fn record_spend(&self, kind: RelayKind, cost: Uint256, loss: Option<bool>) {
+        let mut tracker = self.get_tracker(kind).lock().unwrap();
+        tracker.record(cost, loss, Instant::now());
+    }
+}
+
+struct BudgetTracker {
+    name: &'static str,
+    config: RelayerBudgetConfig,
+    spends: VecDeque<(Instant, Uint256)>,
+    consecutive_losses: u64,
+    paused_until: Option<Instant>,
+    last_resume_request: u64,
+}
+
+impl BudgetTracker {
+    fn new(name: &'static str, config: RelayerBudgetConfig) -> BudgetTracker {
+        BudgetTracker {
+            name,
+            config,
+            spends: VecDeque::new(),
+            consecutive_losses: 0,
+            paused_until: None,
+            last_resume_request: RESUME_REQUESTS.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Removes spending older than the longest window and handles manual resumes
+    fn update(&mut self, now: Instant) {
+        while let Some((time, _)) = self.spends.front() {
+            if now.duration_since(*time) > ONE_DAY {
+                self.spends.pop_front();
+            } else {
+                break;
+            }
+        }
+        let resume_requests = RESUME_REQUESTS.load(Ordering::SeqCst);
+        if resume_requests != self.last_resume_request {
+            self.last_resume_request = resume_requests;
+            if self.paused_until.is_some() || !self.spends.is_empty() {
+                info!("Manually resuming the {} relayer budget", self.name);
+            }
+            self.spends.clear();
+            self.consecutive_losses = 0;
+            self.paused_until = None;
+        }
+        if let Some(paused_until) = self.paused_until {
+            if paused_until <= now {
+                info!("The {} relayer budget has resumed", self.name);
+                self.paused_until = None;
+                self.consecutive_losses = 0;
+            }
+        }
+        self.update_metrics(now);
+    }
+
+    fn spent_within(&self, window: Duration, now: Instant) -> Uint256 {
+        let mut total = Uint256::default();
+        for (time, amount) in self.spends.iter() {
+            if now.duration_since(*time) <= window {
+                total = saturating_add(total, *amount);
+            }
+        }
+        total
+    }
+
+    fn check(&mut self, cost: Uint256, now: Instant) -> Result<(), String> {
+        self.update(now);
+        if let Some(paused_until) = self.paused_until {
+            return Err(format!(
+                "{} consecutive loss making relays, resuming in {} seconds",
+                self.consecutive_losses,
+                paused_until.duration_since(now).as_secs()
+            ));
+        }
+        let limits = [
+            ("hourly", ONE_HOUR, self.config.hourly_limit),
+            ("daily", ONE_DAY, self.config.daily_limit),
+        ];
+        for (name, window, limit) in limits.iter() {
+            if let Some(limit) = limit {
+                let spent = self.spent_within(*window, now);
+                if saturating_add(spent, cost) > *limit {
+                    return Err(format!(
+                        "{} ETH of the {} limit of {} ETH has already been spent",
+                        format_token_amount(spent, ETH_DECIMALS),
+                        name,
+                        format_token_amount(*limit, ETH_DECIMALS)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, cost: Uint256, loss: Option<bool>, now: Instant) {
+        self.spends.push_back((now, cost));
+        match loss {
+            Some(true) => self.consecutive_losses += 1,
+            Some(false) => self.consecutive_losses = 0,
+            None => {}
+        }
+        if let Some(max) = self.config.max_consecutive_losses {
+            if self.consecutive_losses >= max && self.paused_until.is_none() {
+                error!(
+                    "The {} relayer budget is paused for one hour after {} consecutive loss making relays",
+                    self.name, self.consecutive_losses
+                );
+                metrics_warnings_counter(1, "Relayer budget paused by consecutive losses");
+                self.paused_until = Some(now + ONE_HOUR);
+            }
+        }
+        self.update_metrics(now);
+    }
+
+    fn update_metrics(&self, now: Instant) {
+        metrics_latest(
+            to_gwei(self.spent_within(ONE_HOUR, now)),
+            &format!("relayer_{}_budget_hourly_spend_gwei", self.name),
+        );
+        metrics_latest(
+            to_gwei(self.spent_within(ONE_DAY, now)),
+            &format!("relayer_{}_budget_daily_spend_gwei", self.name),
+        );
+        metrics_latest(
+            self.consecutive_losses,
+            &format!("relayer_{}_budget_consecutive_losses", self.name),
+        );
+        metrics_latest(
+            self.paused_until.is_some() as u64,
+            &format!("relayer_{}_budget_paused", self.name),
+        );
+    }
+}
+
+/// Adds two amounts, returning the maximum value instead of overflowing
+fn saturating_add(a: Uint256, b: Uint256) -> Uint256 {
+    a.checked_add(b).unwrap_or_else(Uint256::max_value)
+}
+
+/// Converts a wei amount to gwei for metrics, which only accept integers
+pub(crate) fn to_gwei(amount: Uint256) -> u64 {
+    amount
+        .divide(Uint256::from_u64(ONE_GWEI))
+        .unwrap()
+        .0
+        .resize_to_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_limits() {
+        let config = RelayerBudgetConfig {
+            hourly_limit: Some(Uint256::from_u64(100)),
+            daily_limit: Some(Uint256::from_u64(150)),
+            max_consecutive_losses: Some(2),
+        };
+        let mut tracker = BudgetTracker::new("test", config);
+        let start = Instant::now();
+
+        assert!(tracker.check(Uint256::from_u64(60), start).is_ok());
+        tracker.record(Uint256::from_u64(60), Some(false), start);
+        // exceeds the hourly limit
+        assert!(tracker.check(Uint256::from_u64(60), start).is_err());
+        // the first spend has left the hourly window but not the daily window
+        let later = start + ONE_HOUR + Duration::from_secs(1);
+        assert!(tracker.check(Uint256::from_u64(60), later).is_ok());
+        tracker.record(Uint256::from_u64(60), Some(true), later);
+        assert!(tracker.check(Uint256::from_u64(60), later).is_err());
+        assert!(tracker.check(Uint256::from_u64(10), later).is_ok());
+        // a relay without a reward does not count as a loss or reset the losses
+        tracker.record(Uint256::from_u64(5), None, later);
+        assert_eq!(tracker.consecutive_losses, 1);
+        tracker.record(Uint256::from_u64(5), Some(true), later);
+        // two consecutive losses pause the budget
+        assert!(tracker.check(Uint256::from_u64(1), later).is_err());
+        assert!(tracker
+            .check(
+                Uint256::from_u64(1),
+                later + ONE_HOUR + Duration::from_secs(1)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_update_limits() {
+        let mut config = RelayerConfig::default();
+        config.budget.hourly_limit = Some(Uint256::from_u64(100));
+        let mut budget = RelayerBudget::new(&config);
+        assert!(budget.can_spend(RelayKind::Batch, Uint256::from_u64(60)));
+        budget.record_spend(RelayKind::Batch, Uint256::from_u64(60), None);
+
+        // the recorded spending counts towards the reloaded limits
+        config.budget.hourly_limit = Some(Uint256::from_u64(200));
+        budget.update_limits(&config);
+        assert!(budget.can_spend(RelayKind::Batch, Uint256::from_u64(100)));
+        config.budget.hourly_limit = Some(Uint256::from_u64(50));
+        budget.update_limits(&config);
+        assert!(!budget.can_spend(RelayKind::Batch, Uint256::from_u64(1)));
+
+        // valsets are charged to the general budget until a valset budget is configured
+        assert!(!budget.can_spend(RelayKind::Valset, Uint256::from_u64(1)));
+        config.valset_relaying_mode = ValsetRelayingMode::Altruistic;
+        config.valset_budget = Some(RelayerBudgetConfig {
+            hourly_limit: Some(Uint256::from_u64(10)),
+            daily_limit: None,
+            max_consecutive_losses: None,
+        });
+        budget.update_limits(&config);
+        assert!(budget.can_spend(RelayKind::Valset, Uint256::from_u64(10)));
+        assert!(!budget.can_spend(RelayKind::Valset, Uint256::from_u64(11)));
+
+        assert_eq!(
+            saturating_add(Uint256::max_value(), Uint256::from_u64(1)),
+            Uint256::max_value()
+        );
+    }
+}
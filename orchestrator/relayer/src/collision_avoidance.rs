@@ -0,0 +1,50 @@
+//! Relayers running the same software tend to notice a relay at the same moment, when two of them
+//! submit the same batch, valset or logic call only one succeeds and the other pays for a failed
+//! transaction. This module adds optional jitter before relaying and checks for competing submissions
+//! that are already pending. The on-chain nonce is then checked once more right before broadcasting
+//! by the submission functions in ethereum_gravity
+
+use std::time::Duration;
+
+use ethereum_gravity::pending_calls::{get_pending_gravity_calls, PendingGravityCall};
+use gravity_utils::{clarity::Address as EthAddress, types::RelayerConfig, web30::client::Web3};
+use rand::Rng;
+use tokio::time::sleep;
+
+/// Waits a random amount of time up to the configured jitter and then checks the pending
+/// transactions to the Gravity contract, returns true if another relayer is already submitting
+/// `relay` or a relay that makes it impossible to submit
+pub async fn should_back_off(
+    web3: &Web3,
+    gravity_contract_address: EthAddress,
+    our_address: EthAddress,
+    relay: &PendingGravityCall,
+    timeout: Duration,
+    config: &RelayerConfig,
+) -> bool {
+    if config.max_relay_jitter > 0 {
+        let jitter = rand::thread_rng().gen_range(0..=config.max_relay_jitter * 1000);
+        trace!("Waiting {}ms before relaying {:?}", jitter, relay);
+        sleep(Duration::from_millis(jitter)).await;
+    }
+    if !config.check_pending_relays {
+        return false;
+    }
+
+    match get_pending_gravity_calls(web3, gravity_contract_address, our_address, timeout).await {
+        Ok(pending) => match pending.iter().find(|call| call.supersedes(relay)) {
+            Some(call) => {
+                info!(
+                    "Another relayer is already submitting {:?}, not relaying {:?}",
+                    call, relay
+                );
+                true
+            }
+            None => false,
+        },
+        Err(e) => {
+            warn!("Could not check pending Gravity transactions {:?}", e);
+            false
+        }
+    }
+}
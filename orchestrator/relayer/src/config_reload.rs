@@ -0,0 +1,38 @@
+//! Replaces the relayer config while the relayer is running. A reloaded config is picked up by
+//! every relayer loop at the start of its next iteration. The spending budgets keep the limits
+//! they were started with, since replacing them would forget the spending recorded so far
+
+use std::sync::RwLock;
+
+use gravity_utils::types::RelayerConfig;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // the generation is incremented on every reload, each relayer loop tracks the last
+    // generation it has applied so that every loop applies every reload exactly once
+    static ref RELOADED_CONFIG: RwLock<(u64, Option<RelayerConfig>)> = RwLock::new((0, None));
+}
+
+/// Replaces the config of every running relayer loop, the config must already be validated
+pub fn request_reload(config: RelayerConfig) {
+    let mut reloaded = RELOADED_CONFIG.write().unwrap();
+    reloaded.0 += 1;
+    reloaded.1 = Some(config);
+}
+
+/// The current reload generation, a relayer loop starts from the current generation so that
+/// it only applies reloads requested after it started
+pub fn current_generation() -> u64 {
+    RELOADED_CONFIG.read().unwrap().0
+}
+
+/// Returns the latest reloaded config if it was requested after `last_generation`, updating
+/// `last_generation` to the generation returned
+pub fn get_reloaded_config(last_generation: &mut u64) -> Option<RelayerConfig> {
+    let reloaded = RELOADED_CONFIG.read().unwrap();
+    if reloaded.0 == *last_generation {
+        return None;
+    }
+    *last_generation = reloaded.0;
+    reloaded.1.clone()
+}
@@ -0,0 +1,159 @@
+//! A pool of Ethereum keys used to sign relayer submissions. Using several keys means that a
+//! single key running low on ETH or having a transaction stuck in the mempool does not halt
+//! relaying. Keys are selected round robin, skipping any that can't currently submit
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use ethereum_gravity::private_submission::has_pending_private_transaction;
+use gravity_utils::{
+    clarity::{Address as EthAddress, PrivateKey as EthPrivateKey, Uint256},
+    error::GravityError,
+    num_conversion::{format_token_amount, ETH_DECIMALS},
+    web30::{client::Web3, jsonrpc::client::HttpClient},
+};
+use metrics_exporter::{metrics_relayer_key_latest, metrics_relayer_key_submission};
+use serde_json::{json, Value};
+
+use crate::budget::to_gwei;
+
+/// A pool of relayer Ethereum keys with round robin selection
+pub struct EthKeyPool {
+    keys: Vec<EthPrivateKey>,
+    next: AtomicUsize,
+}
+
+impl EthKeyPool {
+    /// Creates a pool from `keys`, returns an error if there are no keys, which can happen
+    /// when the configured key file is empty
+    pub fn new(keys: Vec<EthPrivateKey>) -> Result<EthKeyPool, GravityError> {
+        if keys.is_empty() {
+            return Err(GravityError::UnrecoverableError(
+                "The relayer requires at least one Ethereum key".into(),
+            ));
+        }
+        Ok(EthKeyPool {
+            keys,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// The first key of the pool, this is used as the caller address of read
+    /// only calls to the Gravity contract
+    pub fn primary(&self) -> EthPrivateKey {
+        self.keys[0]
+    }
+
+    pub fn addresses(&self) -> Vec<EthAddress> {
+        self.keys.iter().map(|k| k.to_address()).collect()
+    }
+
+    /// Selects the key to sign the next submission. Starting from the round robin position
+    /// keys are skipped if their balance is below `min_balance` or if they have a transaction
+    /// that is still pending, returns None if no key can currently submit
+    pub async fn select_key(
+        &self,
+        web3: &Web3,
+        min_balance: Uint256,
+        timeout: Duration,
+    ) -> Option<EthPrivateKey> {
+        let start = self.next.fetch_add(1, Ordering::SeqCst);
+        for i in 0..self.keys.len() {
+            let key = self.keys[(start + i) % self.keys.len()];
+            let address = key.to_address();
+            match get_key_state(web3, address, timeout).await {
+                Ok((balance, pending)) => {
+                    if pending {
+                        info!(
+                            "Relayer key {} has a pending transaction, trying the next key",
+                            address
+                        );
+                    } else if balance.is_zero() || balance < min_balance {
+                        info!(
+                            "Relayer key {} has {} ETH, not enough to relay, trying the next key",
+                            address,
+                            format_token_amount(balance, ETH_DECIMALS)
+                        );
+                    } else {
+                        return Some(key);
+                    }
+                }
+                Err(e) => warn!("Failed to get state of relayer key {} {:?}", address, e),
+            }
+        }
+        warn!(
+            "None of the {} relayer keys can currently submit a relay costing {} ETH",
+            self.keys.len(),
+            format_token_amount(min_balance, ETH_DECIMALS)
+        );
+        None
+    }
+
+    /// Returns `key` if it can pay `cost`, otherwise selects a different key from the pool
+    pub async fn select_key_for_cost(
+        &self,
+        web3: &Web3,
+        key: EthPrivateKey,
+        cost: Uint256,
+        timeout: Duration,
+    ) -> Option<EthPrivateKey> {
+        match web3.eth_get_balance(key.to_address()).await {
+            Ok(balance)
+                if balance >= cost && !has_pending_private_transaction(key.to_address()) =>
+            {
+                Some(key)
+            }
+            _ => self.select_key(web3, cost, timeout).await,
+        }
+    }
+
+    /// Records a submission made with `key` in the metrics
+    pub fn record_submission(&self, key: EthPrivateKey, cost: Uint256, success: bool) {
+        metrics_relayer_key_submission(&key.to_address().to_string(), to_gwei(cost), success);
+    }
+}
+
+/// Gets the balance of `address` and if it has any pending transactions, comparing
+/// the pending and latest transaction counts. A private transaction is never in the
+/// public pending pool, so those are tracked separately
+async fn get_key_state(
+    web3: &Web3,
+    address: EthAddress,
+    timeout: Duration,
+) -> Result<(Uint256, bool), GravityError> {
+    let balance = web3.eth_get_balance(address).await?;
+    if has_pending_private_transaction(address) {
+        return Ok((balance, true));
+    }
+    let latest_nonce = web3.eth_get_transaction_count(address).await?;
+    let pending_nonce: Value = HttpClient::new(&web3.get_url())
+        .request_method(
+            "eth_getTransactionCount",
+            json!([address.to_string(), "pending"]),
+            timeout,
+        )
+        .await?;
+    let pending_nonce = match pending_nonce.as_str() {
+        Some(n) => Uint256::from_dec_or_hex_str_restricted(n)?,
+        None => {
+            return Err(GravityError::ValidationError(format!(
+                "Invalid pending transaction count {}",
+                pending_nonce
+            )))
+        }
+    };
+
+    let address = address.to_string();
+    metrics_relayer_key_latest(&address, to_gwei(balance), "balance_gwei");
+    metrics_relayer_key_latest(
+        &address,
+        pending_nonce
+            .checked_sub(latest_nonce)
+            .unwrap_or_default()
+            .resize_to_u64(),
+        "pending_transactions",
+    );
+    Ok((balance, pending_nonce > latest_nonce))
+}
@@ -1,5 +1,9 @@
 pub mod batch_relaying;
+pub mod budget;
+pub mod collision_avoidance;
+pub mod config_reload;
 pub mod find_latest_valset;
+pub mod key_pool;
 pub mod logic_call_relaying;
 pub mod main_loop;
 pub mod request_batches;
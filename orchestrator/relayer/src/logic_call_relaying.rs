@@ -5,21 +5,26 @@ use std::{
 
 use cosmos_gravity::query::{get_latest_logic_calls, get_logic_call_signatures};
 use ethereum_gravity::{
-    logic_call::send_eth_logic_call, message_signatures::encode_logic_call_confirm_hashed,
-    utils::get_logic_call_nonce,
+    logic_call::send_eth_logic_call,
+    message_signatures::encode_logic_call_confirm_hashed,
+    pending_calls::PendingGravityCall,
+    utils::{get_logic_call_nonce, RelayOutcome},
 };
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_utils::{
-    clarity::{
-        address::Address as EthAddress, utils::bytes_to_hex_str, PrivateKey as EthPrivateKey,
-        Uint256,
-    },
-    num_conversion::{print_eth, print_gwei},
+    clarity::{address::Address as EthAddress, utils::bytes_to_hex_str, Uint256},
+    num_conversion::{format_token_amount, print_gwei, ETH_DECIMALS},
     types::{LogicCall, LogicCallConfirmResponse, RelayerConfig, Valset},
     web30::{amm::WETH_CONTRACT_ADDRESS, client::Web3},
 };
 use tonic::transport::Channel;
 
+use crate::{
+    budget::{RelayKind, RelayerBudget},
+    collision_avoidance::should_back_off,
+    key_pool::EthKeyPool,
+};
+
 // Determines whether or not submitting `logic_call` will be profitable given the estimated `cost`
 // and the current exchange rate available on uniswap
 async fn should_relay_logic_call(
@@ -28,6 +33,17 @@ async fn should_relay_logic_call(
     logic_call: &LogicCall,
     cost: Uint256,
 ) -> bool {
+    get_logic_call_reward(our_address, web3, logic_call, Some(cost)).await > cost
+}
+
+// Gets the WETH value of the rewards of `logic_call` according to the current exchange rate
+// available on uniswap, if `stop_at` is provided this returns early once the value exceeds it
+async fn get_logic_call_reward(
+    our_address: EthAddress,
+    web3: &Web3,
+    logic_call: &LogicCall,
+    stop_at: Option<Uint256>,
+) -> Uint256 {
     // Fill a hashmap with reward totals by token type
     let mut rewards: HashMap<EthAddress, Uint256> = HashMap::new();
     for fee in &logic_call.fees {
@@ -75,26 +91,29 @@ async fn should_relay_logic_call(
                 }
             }
         }
-        if total_weth_reward > cost {
-            return true; // Exit early if we have enough
+        if let Some(stop_at) = stop_at {
+            if total_weth_reward > stop_at {
+                return total_weth_reward; // Exit early if we have enough
+            }
         }
     }
-    false // Never found enough
+    total_weth_reward
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn relay_logic_calls(
     // the validator set currently in the contract on Ethereum
     current_valset: &Valset,
-    ethereum_key: EthPrivateKey,
+    ethereum_keys: &EthKeyPool,
     web3: &Web3,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
     config: &RelayerConfig,
+    budget: &RelayerBudget,
 ) {
-    let our_ethereum_address = ethereum_key.to_address();
+    let our_ethereum_address = ethereum_keys.primary().to_address();
 
     let latest_calls = get_latest_logic_calls(grpc_client).await;
     trace!("Latest Logic calls {:?}", latest_calls);
@@ -158,6 +177,13 @@ pub async fn relay_logic_calls(
     let latest_ethereum_call = latest_ethereum_call.unwrap();
     let latest_cosmos_call_nonce = oldest_signed_call.clone().invalidation_nonce;
     if latest_cosmos_call_nonce > latest_ethereum_call {
+        let ethereum_key = match ethereum_keys
+            .select_key(web3, Uint256::default(), timeout)
+            .await
+        {
+            Some(key) => key,
+            None => return,
+        };
         let cost = ethereum_gravity::logic_call::estimate_logic_call_cost(
             current_valset,
             oldest_signed_call.clone(),
@@ -179,7 +205,7 @@ pub async fn relay_logic_calls(
                 latest_ethereum_call,
                 cost.gas.clone(),
                 print_gwei(cost.gas_price),
-                print_eth(cost.get_total())
+                format_token_amount(cost.get_total(), ETH_DECIMALS)
             );
 
         let should_relay = if config.logic_call_market_enabled {
@@ -194,7 +220,32 @@ pub async fn relay_logic_calls(
             true
         };
 
-        if should_relay {
+        if should_relay && budget.can_spend(RelayKind::LogicCall, cost.get_total()) {
+            let relay = PendingGravityCall::LogicCall {
+                invalidation_id: oldest_signed_call.invalidation_id.clone(),
+                nonce: oldest_signed_call.invalidation_nonce,
+            };
+            if should_back_off(
+                web3,
+                gravity_contract_address,
+                our_ethereum_address,
+                &relay,
+                timeout,
+                config,
+            )
+            .await
+            {
+                return;
+            }
+            let ethereum_key = match ethereum_keys
+                .select_key_for_cost(web3, ethereum_key, cost.get_total(), timeout)
+                .await
+            {
+                Some(key) => key,
+                None => return,
+            };
+            let reward =
+                get_logic_call_reward(our_ethereum_address, web3, &oldest_signed_call, None).await;
             let res = send_eth_logic_call(
                 current_valset,
                 oldest_signed_call,
@@ -204,11 +255,36 @@ pub async fn relay_logic_calls(
                 gravity_contract_address,
                 gravity_id.clone(),
                 ethereum_key,
+                config.private_submission.as_ref(),
             )
             .await;
-            if res.is_err() {
-                info!("LogicCall submission failed with {:?}", res);
+            let outcome = match res {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    info!("LogicCall submission failed with {:?}", e);
+                    ethereum_keys.record_submission(ethereum_key, cost.get_total(), false);
+                    RelayOutcome::Skipped
+                }
+            };
+            // only a transaction that was actually sent has spent gas, one that was not
+            // confirmed is charged to the budget without counting as a success
+            if outcome == RelayOutcome::Sent {
+                ethereum_keys.record_submission(ethereum_key, cost.get_total(), false);
+                budget.record_spend(RelayKind::LogicCall, cost.get_total(), None);
+            } else if outcome == RelayOutcome::Confirmed {
+                ethereum_keys.record_submission(ethereum_key, cost.get_total(), true);
+                budget.record_spend(
+                    RelayKind::LogicCall,
+                    cost.get_total(),
+                    Some(reward < cost.get_total()),
+                );
             }
+        } else if should_relay {
+            info!(
+                "Not relaying logic call {}/{} due to the relayer budget",
+                bytes_to_hex_str(&oldest_signed_call.invalidation_id),
+                oldest_signed_call.invalidation_nonce
+            );
         } else {
             info!(
                 "Not relaying logic call because it is not profitable to do so: {:?}",
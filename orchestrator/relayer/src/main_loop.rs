@@ -12,8 +12,13 @@ use tokio::time::sleep;
 use tonic::transport::Channel;
 
 use crate::{
-    batch_relaying::relay_batches, find_latest_valset::find_latest_valset,
-    logic_call_relaying::relay_logic_calls, request_batches::request_batches,
+    batch_relaying::relay_batches,
+    budget::RelayerBudget,
+    config_reload::{current_generation, get_reloaded_config},
+    find_latest_valset::find_latest_valset,
+    key_pool::EthKeyPool,
+    logic_call_relaying::relay_logic_calls,
+    request_batches::request_batches,
     valset_relaying::relay_valsets,
 };
 
@@ -23,7 +28,7 @@ pub const TIMEOUT: Duration = Duration::from_secs(10);
 /// it can be called in the test runner for easier orchestration of multi-node tests
 #[allow(clippy::too_many_arguments)]
 pub async fn relayer_main_loop(
-    ethereum_key: EthPrivateKey,
+    ethereum_keys: Vec<EthPrivateKey>,
     cosmos_key: Option<CosmosPrivateKey>,
     cosmos_fee: Option<Coin>,
     web3: Web3,
@@ -34,8 +39,19 @@ pub async fn relayer_main_loop(
     relayer_config: &RelayerConfig,
 ) -> Result<(), GravityError> {
     let mut grpc_client = grpc_client;
-    let loop_speed = Duration::from_secs(relayer_config.relayer_loop_speed);
+    let mut budget = RelayerBudget::new(relayer_config);
+    let ethereum_keys = EthKeyPool::new(ethereum_keys)?;
+    let mut relayer_config = relayer_config.clone();
+    let mut config_generation = current_generation();
     loop {
+        if let Some(reloaded) = get_reloaded_config(&mut config_generation) {
+            info!("Relayer config reloaded");
+            budget.update_limits(&reloaded);
+            relayer_config = reloaded;
+        }
+        let relayer_config = &relayer_config;
+        let budget = &budget;
+        let loop_speed = Duration::from_secs(relayer_config.relayer_loop_speed);
         let (async_result, _) = tokio::join!(
             async {
                 let current_valset =
@@ -50,37 +66,40 @@ pub async fn relayer_main_loop(
 
                 relay_valsets(
                     &current_valset,
-                    ethereum_key,
+                    &ethereum_keys,
                     &web3,
                     &mut grpc_client,
                     gravity_contract_address,
                     gravity_id.clone(),
                     TIMEOUT,
                     relayer_config,
+                    budget,
                 )
                 .await;
 
                 relay_batches(
                     &current_valset,
-                    ethereum_key,
+                    &ethereum_keys,
                     &web3,
                     &mut grpc_client,
                     gravity_contract_address,
                     gravity_id.clone(),
                     TIMEOUT,
                     relayer_config,
+                    budget,
                 )
                 .await;
 
                 relay_logic_calls(
                     &current_valset,
-                    ethereum_key,
+                    &ethereum_keys,
                     &web3,
                     &mut grpc_client,
                     gravity_contract_address,
                     gravity_id.clone(),
                     TIMEOUT,
                     relayer_config,
+                    budget,
                 )
                 .await;
 
@@ -89,8 +108,8 @@ pub async fn relayer_main_loop(
                         &contact,
                         &web3,
                         &mut grpc_client,
-                        relayer_config.batch_request_mode,
-                        ethereum_key.to_address(),
+                        relayer_config,
+                        ethereum_keys.primary().to_address(),
                         cosmos_key,
                         cosmos_fee,
                     )
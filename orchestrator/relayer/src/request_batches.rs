@@ -12,7 +12,7 @@ use gravity_utils::{
     clarity::{u256, Address as EthAddress, Uint256},
     deep_space::{Coin, Contact, PrivateKey},
     prices::get_weth_price,
-    types::BatchRequestMode,
+    types::{BatchRequestMode, RelayerConfig},
     u64_array_bigints,
     web30::client::Web3,
 };
@@ -22,7 +22,7 @@ pub async fn request_batches(
     contact: &Contact,
     web30: &Web3,
     grpc_client: &mut GravityQueryClient<Channel>,
-    batch_request_mode: BatchRequestMode,
+    config: &RelayerConfig,
     eth_address: EthAddress,
     private_key: PrivateKey,
     request_fee: Coin,
@@ -57,6 +57,11 @@ pub async fn request_batches(
     for fee in batch_fees.batch_fees {
         let total_fee = Uint256::from_dec_or_hex_str_restricted(&fee.total_fees).unwrap();
         let token: EthAddress = fee.token.parse().unwrap();
+        let batch_request_mode = config.get_batch_request_mode(token);
+        if let BatchRequestMode::None = batch_request_mode {
+            trace!("Batch requests for {} are disabled by config", token);
+            continue;
+        }
         let denom = get_erc20_to_denom(grpc_client, token).await;
         if let Err(e) = denom {
             error!(
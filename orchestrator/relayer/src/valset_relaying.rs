@@ -5,21 +5,27 @@ use std::time::Duration;
 use cosmos_gravity::query::{get_all_valset_confirms, get_latest_valsets, get_valset};
 use ethereum_gravity::{
     message_signatures::encode_valset_confirm_hashed,
-    utils::{get_valset_nonce, GasCost},
+    pending_calls::PendingGravityCall,
+    utils::{get_valset_nonce, GasCost, RelayOutcome},
     valset_update::send_eth_valset_update,
 };
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_utils::{
-    clarity::{address::Address as EthAddress, PrivateKey as EthPrivateKey},
+    clarity::{address::Address as EthAddress, PrivateKey as EthPrivateKey, Uint256},
     error::GravityError,
-    num_conversion::{print_eth, print_gwei},
+    num_conversion::{format_token_amount, print_gwei, ETH_DECIMALS},
     prices::get_weth_price,
     types::{RelayerConfig, Valset, ValsetConfirmResponse, ValsetRelayingMode},
     web30::client::Web3,
 };
 use tonic::transport::Channel;
 
-use crate::batch_relaying::get_cost_with_margin;
+use crate::{
+    batch_relaying::get_cost_with_margin,
+    budget::{RelayKind, RelayerBudget},
+    collision_avoidance::should_back_off,
+    key_pool::EthKeyPool,
+};
 
 #[allow(clippy::too_many_arguments)]
 /// High level entry point for valset relaying, this function starts by finding
@@ -29,13 +35,14 @@ use crate::batch_relaying::get_cost_with_margin;
 pub async fn relay_valsets(
     // the validator set currently in the contract on Ethereum
     current_valset: &Valset,
-    ethereum_key: EthPrivateKey,
+    ethereum_keys: &EthKeyPool,
     web3: &Web3,
     grpc_client: &mut GravityQueryClient<Channel>,
     gravity_contract_address: EthAddress,
     gravity_id: String,
     timeout: Duration,
     config: &RelayerConfig,
+    budget: &RelayerBudget,
 ) {
     // we have to start with the current valset, we need to know what's currently
     // in the contract in order to determine if a new validator set is valid.
@@ -80,9 +87,10 @@ pub async fn relay_valsets(
         web3,
         gravity_contract_address,
         gravity_id,
-        ethereum_key,
+        ethereum_keys,
         timeout,
         config,
+        budget,
     )
     .await;
 }
@@ -97,10 +105,18 @@ async fn relay_valid_valset(
     web3: &Web3,
     gravity_contract_address: EthAddress,
     gravity_id: String,
-    ethereum_key: EthPrivateKey,
+    ethereum_keys: &EthKeyPool,
     timeout: Duration,
     config: &RelayerConfig,
+    budget: &RelayerBudget,
 ) {
+    let ethereum_key = match ethereum_keys
+        .select_key(web3, Uint256::default(), timeout)
+        .await
+    {
+        Some(key) => key,
+        None => return,
+    };
     let cost = ethereum_gravity::valset_update::estimate_valset_cost(
         valset_to_relay,
         current_valset,
@@ -130,7 +146,7 @@ async fn relay_valid_valset(
         valset_to_relay.nonce, current_valset.nonce,
         cost.gas.clone(),
         print_gwei(cost.gas_price),
-        print_eth(cost.get_total())
+        format_token_amount(cost.get_total(), ETH_DECIMALS)
     );
 
     let should_relay = should_relay_valset(
@@ -143,8 +159,30 @@ async fn relay_valid_valset(
     )
     .await;
 
-    if should_relay {
-        let _res = send_eth_valset_update(
+    if should_relay && budget.can_spend(RelayKind::Valset, cost.get_total()) {
+        let relay = PendingGravityCall::Valset {
+            nonce: valset_to_relay.nonce,
+        };
+        if should_back_off(
+            web3,
+            gravity_contract_address,
+            ethereum_key.to_address(),
+            &relay,
+            timeout,
+            config,
+        )
+        .await
+        {
+            return;
+        }
+        let ethereum_key = match ethereum_keys
+            .select_key_for_cost(web3, ethereum_key, cost.get_total(), timeout)
+            .await
+        {
+            Some(key) => key,
+            None => return,
+        };
+        let res = send_eth_valset_update(
             valset_to_relay,
             current_valset,
             &conformations,
@@ -153,8 +191,44 @@ async fn relay_valid_valset(
             gravity_contract_address,
             gravity_id,
             ethereum_key,
+            config.private_submission.as_ref(),
         )
         .await;
+        let outcome = match res {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                info!("Valset submission failed with {:?}", e);
+                ethereum_keys.record_submission(ethereum_key, cost.get_total(), false);
+                RelayOutcome::Skipped
+            }
+        };
+        // only a transaction that was actually sent has spent gas, one that was not confirmed
+        // is charged to the budget without counting as a success, a valset without a reward
+        // or one that can't be priced is neither a loss nor a profit
+        if outcome == RelayOutcome::Sent {
+            ethereum_keys.record_submission(ethereum_key, cost.get_total(), false);
+            budget.record_spend(RelayKind::Valset, cost.get_total(), None);
+        } else if outcome == RelayOutcome::Confirmed {
+            ethereum_keys.record_submission(ethereum_key, cost.get_total(), true);
+            let loss = match valset_to_relay.reward_token {
+                Some(reward_token) => get_weth_price(
+                    reward_token,
+                    valset_to_relay.reward_amount,
+                    ethereum_key.to_address(),
+                    web3,
+                )
+                .await
+                .ok()
+                .map(|price| price < cost.get_total()),
+                None => None,
+            };
+            budget.record_spend(RelayKind::Valset, cost.get_total(), loss);
+        }
+    } else if should_relay {
+        info!(
+            "The valset {} won't be relayed by this orchestrator due to the relayer budget",
+            valset_to_relay.nonce
+        );
     } else {
         info!(
             "The valset {} won't be relayed by this orchestrator, because relayer is disabled",
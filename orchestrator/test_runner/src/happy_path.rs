@@ -6,6 +6,7 @@ use cosmos_gravity::{
     send::{send_ethereum_claims, send_to_eth},
 };
 use ethereum_gravity::{
+    erc20_approval::Erc20Approval,
     send_to_cosmos::send_to_cosmos,
     utils::{get_tx_batch_nonce, get_valset_nonce},
 };
@@ -371,6 +372,7 @@ pub async fn test_erc20_deposit_result(
         OPERATION_TIMEOUT,
         web30,
         vec![],
+        Erc20Approval::Max,
     )
     .await
     .expect("Failed to send tokens to Cosmos");
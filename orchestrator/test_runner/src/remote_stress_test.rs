@@ -1,7 +1,7 @@
 use std::{collections::HashSet, env, time::Duration};
 
 use cosmos_gravity::send::send_to_eth;
-use ethereum_gravity::send_to_cosmos::send_to_cosmos;
+use ethereum_gravity::{erc20_approval::Erc20Approval, send_to_cosmos::send_to_cosmos};
 use futures::future::join_all;
 use gravity_utils::{
     clarity::{u256, Address as EthAddress, Uint256},
@@ -120,6 +120,7 @@ pub async fn remote_stress_test(
                     TIMEOUT,
                     web30,
                     vec![SendTxOption::GasPriceMultiplier(1.5)],
+                    Erc20Approval::Max,
                 );
                 info!(
                     "Locked {}{} from {} user into the Gravity Ethereum Contract",
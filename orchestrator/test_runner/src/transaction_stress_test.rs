@@ -4,7 +4,9 @@ use cosmos_gravity::{
     query::get_pending_send_to_eth,
     send::{cancel_send_to_eth, send_request_batch, send_to_eth},
 };
-use ethereum_gravity::{send_to_cosmos::send_to_cosmos, utils::get_tx_batch_nonce};
+use ethereum_gravity::{
+    erc20_approval::Erc20Approval, send_to_cosmos::send_to_cosmos, utils::get_tx_batch_nonce,
+};
 use futures::future::join_all;
 use gravity_proto::gravity::query_client::QueryClient as GravityQueryClient;
 use gravity_utils::{
@@ -82,6 +84,7 @@ pub async fn transaction_stress_test(
                 TIMEOUT,
                 web30,
                 vec![SendTxOption::GasPriceMultiplier(5.0)],
+                Erc20Approval::Max,
             );
             sends.push(fut);
         }